@@ -0,0 +1,146 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use criterion::{criterion_group, criterion_main, Criterion};
+use dbus::blocking::Connection;
+use dbus::channel::Channel;
+use dbus_crossroads::Crossroads;
+use jami_rs::{Jami, JamiClient};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+// Compare one connection per call with the connection shared by Jami's functions
+// and with a JamiClient, for a burst of 1000 getAccountDetails.
+// A private dbus-daemon serves a fake ConfigurationManager, so jamid is not needed.
+const BURST: usize = 1000;
+
+/**
+ * Start a private bus and a fake daemon answering getAccountDetails
+ * @return the bus process and its address
+ */
+fn start_fake_daemon() -> (Child, String) {
+    let mut bus = Command::new("dbus-daemon")
+        .args(["--session", "--nofork", "--print-address"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("dbus-daemon is needed to run this benchmark");
+    let mut address = String::new();
+    BufReader::new(bus.stdout.take().unwrap())
+        .read_line(&mut address)
+        .unwrap();
+    let address = address.trim().to_string();
+
+    let server_address = address.clone();
+    std::thread::spawn(move || {
+        let mut channel = Channel::open_private(&server_address).unwrap();
+        channel.register().unwrap();
+        let conn = Connection::from(channel);
+        conn.request_name("cx.ring.Ring", false, true, false)
+            .unwrap();
+        let mut cr = Crossroads::new();
+        let iface = cr.register("cx.ring.Ring.ConfigurationManager", |b| {
+            b.method(
+                "getAccountDetails",
+                ("accountId",),
+                ("details",),
+                |_, _, (id,): (String,)| {
+                    let mut details = HashMap::new();
+                    details.insert(String::from("Account.alias"), id);
+                    details.insert(String::from("Account.type"), String::from("RING"));
+                    Ok((details,))
+                },
+            );
+        });
+        cr.insert("/cx/ring/Ring/ConfigurationManager", &[iface], ());
+        cr.serve(&conn).unwrap();
+    });
+    // Wait for the fake daemon to own its name
+    std::thread::sleep(Duration::from_millis(200));
+    (bus, address)
+}
+
+fn per_call_connection() {
+    for _ in 0..BURST {
+        let handle = Jami::connect().unwrap();
+        assert!(!handle.try_get_account_details("bench").unwrap().is_empty());
+    }
+}
+
+fn jami_functions() {
+    for _ in 0..BURST {
+        assert!(!Jami::try_get_account_details("bench").unwrap().is_empty());
+    }
+}
+
+fn shared_connection(client: &JamiClient) {
+    for _ in 0..BURST {
+        assert!(!client.try_get_account_details("bench").unwrap().is_empty());
+    }
+}
+
+fn connection_benchmark(c: &mut Criterion) {
+    let (mut bus, address) = start_fake_daemon();
+    // Jami's functions and JamiClient::new use the session bus
+    std::env::set_var("DBUS_SESSION_BUS_ADDRESS", &address);
+    let client = JamiClient::new().unwrap();
+
+    // Summary printed with the criterion results. Target: at least 5x
+    let start = Instant::now();
+    per_call_connection();
+    let per_call = start.elapsed();
+    let start = Instant::now();
+    jami_functions();
+    let functions = start.elapsed();
+    let start = Instant::now();
+    shared_connection(&client);
+    let shared = start.elapsed();
+    let ratio = per_call.as_secs_f64() / shared.as_secs_f64();
+    println!(
+        "burst of {} getAccountDetails: per call connection {:?}, Jami's functions {:?}, shared connection {:?} ({:.1}x, target 5x {})",
+        BURST,
+        per_call,
+        functions,
+        shared,
+        ratio,
+        if ratio >= 5. { "met" } else { "NOT met" }
+    );
+
+    let mut group = c.benchmark_group("getAccountDetails burst");
+    group.sample_size(20);
+    group.bench_function("per call connection", |b| b.iter(per_call_connection));
+    group.bench_function("Jami's functions", |b| b.iter(jami_functions));
+    group.bench_function("shared connection", |b| {
+        b.iter(|| shared_connection(&client))
+    });
+    group.finish();
+
+    let _ = bus.kill();
+}
+
+criterion_group!(benches, connection_benchmark);
+criterion_main!(benches);
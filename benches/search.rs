@@ -0,0 +1,85 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use criterion::{criterion_group, criterion_main, Criterion};
+use jami_rs::{Profile, ProfileManager};
+use std::time::Instant;
+
+// Search 1000 cached profiles as a quick-switcher would, one search per keystroke.
+// Only memory is used, no daemon is needed.
+const PROFILES: usize = 1000;
+// Typed one char at a time
+const QUERY: &str = "émilie";
+
+/**
+ * @return a cache of PROFILES profiles, with some accents and shared prefixes
+ */
+fn profiles() -> ProfileManager {
+    let names = ["Émilie", "Emil", "Zoé", "Ångström", "Bob"];
+    let mut profiles = ProfileManager::new();
+    for i in 0..PROFILES {
+        let mut profile = Profile::new();
+        profile.uri = format!("{:040x}", i);
+        profile.display_name = format!("{} {}", names[i % names.len()], i);
+        if i % 3 == 0 {
+            profile.username = format!("{}{}", names[i % names.len()].to_lowercase(), i);
+        }
+        profiles.profiles.insert(profile.uri.clone(), profile);
+    }
+    profiles
+}
+
+fn type_query(profiles: &ProfileManager) {
+    for end in QUERY.char_indices().map(|(i, c)| i + c.len_utf8()) {
+        let hits = profiles.search("", &QUERY[..end], 10);
+        assert!(!hits.is_empty());
+    }
+}
+
+fn search_benchmark(c: &mut Criterion) {
+    let profiles = profiles();
+
+    // Summary printed with the criterion results. Target: one frame (16 ms) per keystroke
+    let keystrokes = QUERY.chars().count() as u32;
+    let start = Instant::now();
+    type_query(&profiles);
+    let per_keystroke = start.elapsed() / keystrokes;
+    let met = per_keystroke.as_millis() < 16;
+    println!(
+        "search in {} profiles: {:?} per keystroke (target 16ms {})",
+        PROFILES,
+        per_keystroke,
+        if met { "met" } else { "NOT met" }
+    );
+
+    c.bench_function("search per keystroke", |b| {
+        b.iter(|| profiles.search("", "émi", 10))
+    });
+    c.bench_function("type a query", |b| b.iter(|| type_query(&profiles)));
+}
+
+criterion_group!(benches, search_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,78 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+// Answer every text message with the same text, and accept every request.
+// Uses the first enabled Jami account, or creates "echo-bot".
+use jami_rs::{Event, JamiSession, SessionConfig};
+use tokio::sync::broadcast;
+
+#[tokio::main]
+async fn main() {
+    let config = SessionConfig {
+        create_account: Some(String::from("echo-bot")),
+        accept_trust_requests: true,
+        ..Default::default()
+    };
+    let session = match JamiSession::start(config).await {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("Can't start the session: {}", e);
+            return;
+        }
+    };
+    let own_uri = session.account().hash.clone();
+    println!("Running as {} ({})", session.account().alias, own_uri);
+
+    let mut events = session.events();
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::RecvError::Lagged(_)) => continue,
+            Err(broadcast::RecvError::Closed) => break,
+        };
+        if let Event::Message {
+            account_id,
+            conversation_id,
+            payloads,
+        } = event
+        {
+            if account_id != session.account().id
+                || payloads.get("type").map(|t| &**t) != Some("text/plain")
+                || payloads.get("author") == Some(&own_uri)
+            {
+                continue;
+            }
+            let body = payloads.get("body").cloned().unwrap_or_default();
+            if body == "!quit" {
+                break;
+            }
+            if let Err(e) = session.send_text(&conversation_id, &body) {
+                eprintln!("Can't answer: {}", e);
+            }
+        }
+    }
+    session.shutdown();
+}
@@ -25,43 +25,1266 @@
  * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  **/
 
+use super::error::{JamiError, NameRegistrationError};
+use super::eventloop::EventLoopHandle;
+use super::profile::Profile;
+use super::uri::{self, InfoHash, JamiUri};
+use super::{Event, Jami};
+
+use app_dirs::{get_app_dir, AppDataType, AppInfo};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::stream::Stream;
+use tokio::sync::{broadcast, mpsc};
 
 /**
- * Represent a Jami account, just here to store informations.
+ * Registration state of an account, as sent by registrationStateChanged
+ **/
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum RegistrationState {
+    #[default]
+    Unregistered,
+    Trying,
+    Registered,
+    ErrorGeneric,
+    ErrorAuth,
+    ErrorNetwork,
+    ErrorHost,
+    ErrorServiceUnavailable,
+    ErrorNeedMigration,
+    Initializing,
+    // State not known by jami-rs, the daemon's string is kept
+    Unknown(String),
+}
+
+impl FromStr for RegistrationState {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "UNREGISTERED" => RegistrationState::Unregistered,
+            "TRYING" => RegistrationState::Trying,
+            "REGISTERED" => RegistrationState::Registered,
+            "ERROR_GENERIC" => RegistrationState::ErrorGeneric,
+            "ERROR_AUTH" => RegistrationState::ErrorAuth,
+            "ERROR_NETWORK" => RegistrationState::ErrorNetwork,
+            "ERROR_HOST" => RegistrationState::ErrorHost,
+            "ERROR_SERVICE_UNAVAILABLE" => RegistrationState::ErrorServiceUnavailable,
+            "ERROR_NEED_MIGRATION" => RegistrationState::ErrorNeedMigration,
+            "INITIALIZING" => RegistrationState::Initializing,
+            other => RegistrationState::Unknown(other.to_string()),
+        })
+    }
+}
+
+// Used for println!
+impl fmt::Display for RegistrationState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            RegistrationState::Unregistered => "UNREGISTERED",
+            RegistrationState::Trying => "TRYING",
+            RegistrationState::Registered => "REGISTERED",
+            RegistrationState::ErrorGeneric => "ERROR_GENERIC",
+            RegistrationState::ErrorAuth => "ERROR_AUTH",
+            RegistrationState::ErrorNetwork => "ERROR_NETWORK",
+            RegistrationState::ErrorHost => "ERROR_HOST",
+            RegistrationState::ErrorServiceUnavailable => "ERROR_SERVICE_UNAVAILABLE",
+            RegistrationState::ErrorNeedMigration => "ERROR_NEED_MIGRATION",
+            RegistrationState::Initializing => "INITIALIZING",
+            RegistrationState::Unknown(other) => other,
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl RegistrationState {
+    pub fn is_error(&self) -> bool {
+        match self {
+            RegistrationState::ErrorGeneric
+            | RegistrationState::ErrorAuth
+            | RegistrationState::ErrorNetwork
+            | RegistrationState::ErrorHost
+            | RegistrationState::ErrorServiceUnavailable
+            | RegistrationState::ErrorNeedMigration => true,
+            RegistrationState::Unknown(other) => other.starts_with("ERROR"),
+            _ => false,
+        }
+    }
+
+    pub fn is_registered(&self) -> bool {
+        *self == RegistrationState::Registered
+    }
+}
+
+/**
+ * Type of an account (Account.type)
+ **/
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum AccountType {
+    #[default]
+    Ring,
+    Sip,
+    Other(String),
+}
+
+impl FromStr for AccountType {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "RING" | "JAMI" => AccountType::Ring,
+            "SIP" => AccountType::Sip,
+            other => AccountType::Other(other.to_string()),
+        })
+    }
+}
+
+// Used for println!
+impl fmt::Display for AccountType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AccountType::Ring => write!(f, "RING"),
+            AccountType::Sip => write!(f, "SIP"),
+            AccountType::Other(other) => write!(f, "{}", other),
+        }
+    }
+}
+
+/**
+ * DHT proxy settings of an account
+ **/
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ProxyConfig {
+    // Account.proxyEnabled
+    pub enabled: bool,
+    // Account.proxyServer
+    pub server: String,
+    // Account.dhtProxyListUrl, used to get a proxy if server is empty
+    pub list_url: String,
+}
+
+impl ProxyConfig {
+    /**
+     * Read the config from account details
+     * @param details   Details from getAccountDetails
+     */
+    pub fn from_details(details: &HashMap<String, String>) -> Self {
+        Self {
+            enabled: details
+                .get("Account.proxyEnabled")
+                .map(|v| v == "true")
+                .unwrap_or(false),
+            server: details
+                .get("Account.proxyServer")
+                .cloned()
+                .unwrap_or_default(),
+            list_url: details
+                .get("Account.dhtProxyListUrl")
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+
+    /**
+     * Write the config into account details
+     * @param details   Details to update
+     */
+    pub fn apply(&self, details: &mut HashMap<String, String>) {
+        details.insert(
+            String::from("Account.proxyEnabled"),
+            self.enabled.to_string(),
+        );
+        details.insert(String::from("Account.proxyServer"), self.server.clone());
+        details.insert(
+            String::from("Account.dhtProxyListUrl"),
+            self.list_url.clone(),
+        );
+    }
+}
+
+/**
+ * DHT proxy and push notifications settings, changed together by mobile-style clients
+ **/
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct PushConfig {
+    // Account.proxyEnabled
+    pub proxy_enabled: bool,
+    // Account.proxyServer
+    pub proxy_server: String,
+    // Account.proxyPushToken
+    pub push_token: String,
+    // Account.notificationTopic
+    pub push_topic: String,
+    // Proxy in use, from volatile details (read only)
+    #[serde(default)]
+    pub effective_proxy: Option<String>,
+}
+
+impl PushConfig {
+    /**
+     * Read the config from account details
+     * @param details   Details from getAccountDetails
+     * @param volatile  Details from getVolatileAccountDetails
+     */
+    pub fn from_details(
+        details: &HashMap<String, String>,
+        volatile: &HashMap<String, String>,
+    ) -> Self {
+        let proxy = ProxyConfig::from_details(details);
+        Self {
+            proxy_enabled: proxy.enabled,
+            proxy_server: proxy.server,
+            push_token: details
+                .get("Account.proxyPushToken")
+                .cloned()
+                .unwrap_or_default(),
+            push_topic: details
+                .get("Account.notificationTopic")
+                .cloned()
+                .unwrap_or_default(),
+            effective_proxy: volatile
+                .get("Account.proxyServer")
+                .filter(|server| !server.is_empty())
+                .cloned(),
+        }
+    }
+
+    /**
+     * Check the proxy server: [http(s)://]host[:port], required if the proxy is enabled.
+     * The host can be a bracketed IPv6 address, the port a range like the daemon's
+     * default, dhtproxy.jami.net:[80-95]
+     */
+    pub fn validate(&self) -> Result<(), JamiError> {
+        let invalid = |reason: &str| {
+            Err(JamiError::InvalidArgument(
+                String::from("proxy_server"),
+                reason.to_string(),
+            ))
+        };
+        if self.proxy_server.is_empty() {
+            if self.proxy_enabled {
+                return invalid("needed when the proxy is enabled");
+            }
+            return Ok(());
+        }
+        let address = self
+            .proxy_server
+            .trim_start_matches("https://")
+            .trim_start_matches("http://");
+        let port = if let Some(ipv6) = address.strip_prefix('[') {
+            let mut parts = ipv6.splitn(2, ']');
+            let host = parts.next().unwrap_or_default();
+            if host.is_empty()
+                || !host
+                    .chars()
+                    .all(|c| c.is_ascii_hexdigit() || c == ':' || c == '.')
+            {
+                return invalid("invalid host");
+            }
+            match parts.next() {
+                None => return invalid("invalid host"),
+                Some("") => None,
+                Some(rest) => match rest.strip_prefix(':') {
+                    Some(port) => Some(port),
+                    None => return invalid("invalid host"),
+                },
+            }
+        } else {
+            let mut parts = address.splitn(2, ':');
+            let host = parts.next().unwrap_or_default();
+            if host.is_empty()
+                || !host
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+            {
+                return invalid("invalid host");
+            }
+            parts.next()
+        };
+        if let Some(port) = port {
+            if !PushConfig::is_port(port) {
+                return invalid("invalid port");
+            }
+        }
+        Ok(())
+    }
+
+    // A port, or a range of ports like [80-95]
+    fn is_port(port: &str) -> bool {
+        match port
+            .strip_prefix('[')
+            .and_then(|range| range.strip_suffix(']'))
+        {
+            Some(range) => {
+                let mut bounds = range.splitn(2, '-').map(|bound| bound.parse::<u16>());
+                match (bounds.next(), bounds.next()) {
+                    (Some(Ok(first)), Some(Ok(last))) => first <= last,
+                    _ => false,
+                }
+            }
+            None => port.parse::<u16>().is_ok(),
+        }
+    }
+}
+
+/**
+ * Overview of an account (cf Jami::account_stats)
+ **/
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct AccountStats {
+    pub conversations: usize,
+    pub requests: usize,
+    pub contacts: usize,
+    // Only computed by Jami::account_stats_with_messages
+    pub messages: Option<usize>,
+}
+
+/**
+ * Connectivity of an account (cf Jami::account_network_status).
+ * Fields are None when the daemon doesn't report them for this account.
+ * The daemon doesn't report if the DHT proxy answers, see ProxyConfig for its settings
+ **/
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct NetworkStatus {
+    // The device is announced on the DHT (RING only, Account.deviceAnnounced)
+    pub dht_connected: Option<bool>,
+    // The name server answers (RING only)
+    pub nameserver_reachable: Option<bool>,
+}
+
+impl NetworkStatus {
+    /**
+     * Read the status from account details. nameserver_reachable is left to None
+     * @param details   Details from getAccountDetails
+     * @param volatile  Details from getVolatileAccountDetails
+     */
+    pub fn from_details(
+        details: &HashMap<String, String>,
+        volatile: &HashMap<String, String>,
+    ) -> Self {
+        let kind: AccountType = details
+            .get("Account.type")
+            .map(|t| t.parse().unwrap())
+            .unwrap_or_default();
+        if kind != AccountType::Ring {
+            return Self::default();
+        }
+        Self {
+            dht_connected: volatile.get("Account.deviceAnnounced").map(|a| a == "true"),
+            nameserver_reachable: None,
+        }
+    }
+}
+
+/**
+ * Try to open a TCP connection to a name server
+ * @param uri       RingNS.uri ("" for the default name server)
+ * @param timeout   Max time to connect
+ * @return None if the uri can't be resolved, else if the connection succeeded
+ */
+pub fn ping_name_server(uri: &str, timeout: Duration) -> Option<bool> {
+    let uri = if uri.is_empty() {
+        "https://ns.jami.net"
+    } else {
+        uri
+    };
+    let (rest, port) = if let Some(rest) = uri.strip_prefix("https://") {
+        (rest, 443)
+    } else if let Some(rest) = uri.strip_prefix("http://") {
+        (rest, 80)
+    } else {
+        (uri, 443)
+    };
+    let host = rest.split('/').next().unwrap_or_default();
+    let address = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{}:{}", host, port)
+    };
+    let address = address.to_socket_addrs().ok()?.next()?;
+    Some(TcpStream::connect_timeout(&address, timeout).is_ok())
+}
+
+/**
+ * Change of one account (cf Account::watch)
+ **/
+#[derive(Clone, Debug, PartialEq)]
+pub enum AccountChange {
+    RegistrationState(RegistrationState),
+    VolatileDetails(HashMap<String, String>),
+    DetailsChanged(HashMap<String, String>),
+    // The account is not in the daemon anymore. Last change of the stream
+    Removed,
+}
+
+impl AccountChange {
+    /**
+     * Get the change of an account carried by a global event.
+     * Never returns Removed, as AccountsChanged doesn't say which account changed
+     * @param account_id    Account to watch
+     * @param event         Event from the event loop
+     * @return the change, None if the event is about something else
+     */
+    pub fn from_event<I>(account_id: &str, event: &Event<I>) -> Option<AccountChange> {
+        match event {
+            Event::RegistrationStateChanged {
+                account_id: id,
+                state,
+                ..
+            } if id == account_id => Some(AccountChange::RegistrationState(state.clone())),
+            Event::VolatileDetailsChanged(id, details) if id == account_id => {
+                Some(AccountChange::VolatileDetails(details.clone()))
+            }
+            Event::AccountDetailsChanged(id, details) if id == account_id => {
+                Some(AccountChange::DetailsChanged(details.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/**
+ * An account present in both lists of diff(), with the fields that changed
+ **/
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AccountModification {
+    pub id: String,
+    // Names of the fields of Account
+    pub fields: Vec<String>,
+}
+
+/**
+ * Difference between two lists of accounts (cf diff)
+ **/
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct AccountListDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<AccountModification>,
+}
+
+impl AccountListDiff {
+    /**
+     * @return if both lists are the same
+     */
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/**
+ * Compare two lists of accounts, accounts are matched by id
+ * @param old   Previous list
+ * @param new   Current list
+ * @return ids added and removed, and the fields changed for other accounts
+ */
+pub fn diff(old: &[Account], new: &[Account]) -> AccountListDiff {
+    let mut result = AccountListDiff::default();
+    for account in new {
+        match old.iter().find(|a| a.id == account.id) {
+            None => result.added.push(account.id.clone()),
+            Some(previous) => {
+                let fields = previous.changed_fields(account);
+                if !fields.is_empty() {
+                    result.modified.push(AccountModification {
+                        id: account.id.clone(),
+                        fields,
+                    });
+                }
+            }
+        }
+    }
+    for account in old {
+        if !new.iter().any(|a| a.id == account.id) {
+            result.removed.push(account.id.clone());
+        }
+    }
+    result
+}
+
+/**
+ * Represent a device linked to an account
  **/
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Device {
+    pub id: String,
+    pub name: String,
+    pub is_current: bool,
+}
+
+/**
+ * Represent a Jami account, just here to store informations.
+ * Can be serialized to keep a snapshot of known accounts. Fields added later
+ * must use #[serde(default)] so old snapshots still load, and secrets
+ * (passwords, tokens, volatile details) must be #[serde(skip)].
+ **/
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Account {
     pub id: String,
     pub hash: String,
     pub alias: String,
     pub registered_name: String,
     pub enabled: bool,
+    #[serde(default)]
+    pub kind: AccountType,
+    // SIP only
+    #[serde(default)]
+    pub hostname: String,
+    // SIP only
+    #[serde(default)]
+    pub username: String,
+    // Last known registration state (volatile)
+    #[serde(skip)]
+    pub registration_state: RegistrationState,
+    // If the device is announced on the DHT (volatile)
+    #[serde(skip)]
+    pub device_announced: bool,
+    // Id of this device (Account.deviceID)
+    #[serde(default)]
+    pub device_id: String,
+    // Known devices. Empty until load_devices() is called
+    #[serde(default)]
+    pub devices: Vec<Device>,
+    // Avatar of the account. None until load_avatar() is called
+    #[serde(skip)]
+    avatar: Option<Vec<u8>>,
+    // Banned contacts. None until banned_contacts() is called
+    #[serde(skip)]
+    banned: Option<HashSet<String>>,
+    // Default moderators. None until moderators() is called
+    #[serde(skip)]
+    moderators: Option<HashSet<String>>,
+}
+
+// Accounts are identified by their id
+impl PartialEq for Account {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Account {}
+
+impl Hash for Account {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
 }
 
 // Used for println!
 impl fmt::Display for Account {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "[{}]: {} ({}) - Active: {}",
-            self.id, self.hash, self.alias, self.enabled
-        )
+        if self.registered_name.is_empty() && self.alias.is_empty() {
+            return write!(f, "{}…", self.short_hash(8));
+        }
+        write!(f, "{} ({}…)", self.display_identity(), self.short_hash(8))
+    }
+}
+
+/**
+ * Name to show for an identity: registered name, else alias, else the start of the hash.
+ * Shared by Account and ProfileManager so both agree for the same identity.
+ * @param registered_name
+ * @param alias
+ * @param hash
+ * @return the name to show
+ */
+pub fn display_identity(registered_name: &str, alias: &str, hash: &str) -> String {
+    if !registered_name.is_empty() {
+        return registered_name.to_string();
+    }
+    if !alias.is_empty() {
+        return alias.to_string();
     }
+    hash.chars().take(8).collect()
+}
+
+/**
+ * Check a name against the rules of the name server:
+ * 3 to 32 chars, lowercase letters, digits, '-' or '_'
+ * @param name      Name to check
+ */
+pub fn validate_name(name: &str) -> Result<(), NameRegistrationError> {
+    if name.len() < 3 || name.len() > 32 {
+        return Err(NameRegistrationError::Invalid(String::from(
+            "must be 3 to 32 chars long",
+        )));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !(c.is_ascii_lowercase() || c.is_ascii_digit() || *c == '-' || *c == '_'))
+    {
+        return Err(NameRegistrationError::Invalid(format!(
+            "'{}' is not allowed",
+            c
+        )));
+    }
+    Ok(())
 }
 
 impl Account {
-    pub fn null() -> Account {
+    /**
+     * Generate an empty account, fields are filled by update_details()
+     * @param id    Id of the account
+     */
+    pub fn new(id: &str) -> Account {
         Account {
-            id: String::new(),
+            id: id.to_string(),
             hash: String::new(),
             alias: String::new(),
             registered_name: String::new(),
             enabled: false,
+            kind: AccountType::Ring,
+            hostname: String::new(),
+            username: String::new(),
+            registration_state: RegistrationState::Unregistered,
+            device_announced: false,
+            device_id: String::new(),
+            devices: Vec::new(),
+            avatar: None,
+            banned: None,
+            moderators: None,
+        }
+    }
+
+    #[deprecated(
+        note = "Jami::get_account returns an Option, use Account::new for an empty account"
+    )]
+    pub fn null() -> Account {
+        Account::new("")
+    }
+
+    /**
+     * @return if the account is empty (no id)
+     */
+    pub fn is_null(&self) -> bool {
+        self.id.is_empty()
+    }
+
+    /**
+     * Serialize the account (to store a snapshot)
+     * @return the json string
+     */
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /**
+     * Load an account from a snapshot
+     * @param json  The string generated by to_json
+     * @return the account
+     */
+    pub fn from_json(json: &str) -> serde_json::Result<Account> {
+        serde_json::from_str(json)
+    }
+
+    /**
+     * Check the type of the account before a type specific operation
+     * @param expected  Type needed
+     * @return WrongAccountType if the account has another type
+     */
+    pub fn ensure_kind(&self, expected: AccountType) -> Result<(), JamiError> {
+        if self.kind != expected {
+            return Err(JamiError::WrongAccountType {
+                expected,
+                actual: self.kind.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /**
+     * Register a name on the name server (RING only) and wait for the result.
+     * The name is checked with validate_name() before calling the daemon
+     * @param password  Password of the account
+     * @param name      Name to register
+     */
+    pub async fn register_name(
+        &mut self,
+        password: &str,
+        name: &str,
+    ) -> Result<(), NameRegistrationError> {
+        self.ensure_kind(AccountType::Ring)?;
+        validate_name(name)?;
+        Jami::register_name_await(&self.id, password, name, Duration::from_secs(30)).await?;
+        self.registered_name = name.to_string();
+        Ok(())
+    }
+
+    /**
+     * Export the account on the DHT to link a new device (RING only).
+     * The pin is sent by exportOnRingEnded
+     * @param password  Password of the account
+     * @return if the export started
+     */
+    pub fn export_on_ring(&self, password: &str) -> Result<bool, JamiError> {
+        self.ensure_kind(AccountType::Ring)?;
+        Jami::try_export_on_ring(&self.id, password)
+    }
+
+    /**
+     * Export the account to an archive
+     * @param path      Destination of the archive
+     * @param password  Password of the account ("" if none)
+     */
+    pub fn export_backup(&self, path: &str, password: &str) -> Result<(), JamiError> {
+        self.ensure_kind(AccountType::Ring)?;
+        if !Jami::try_export_to_file(&self.id, path, password)? {
+            return Err(JamiError::OperationFailed(format!(
+                "Unable to export {} to {}",
+                self.id, path
+            )));
+        }
+        Ok(())
+    }
+
+    /**
+     * Allow a new device to be linked (RING only)
+     * @param password  Password of the account ("" if none)
+     * @return the pin to enter on the new device
+     */
+    pub async fn link_new_device(&self, password: &str) -> Result<String, JamiError> {
+        self.ensure_kind(AccountType::Ring)?;
+        Jami::export_on_ring_await(&self.id, password, Duration::from_secs(30)).await
+    }
+
+    /**
+     * Revoke a device of the account (RING only).
+     * The result is sent by deviceRevocationEnded
+     * @param device_id Device to revoke
+     * @param password  Password of the account ("" if none)
+     */
+    pub fn revoke_device(&self, device_id: &str, password: &str) -> Result<(), JamiError> {
+        self.ensure_kind(AccountType::Ring)?;
+        if !Jami::try_revoke_device(&self.id, device_id, password)? {
+            return Err(JamiError::OperationFailed(format!(
+                "Unable to revoke {}",
+                device_id
+            )));
+        }
+        Ok(())
+    }
+
+    /**
+     * Parse an uri of a peer given by the user
+     * @param uri   Bare hash or jami:/ring: uri
+     * @return the bare hash
+     */
+    fn peer_hash(uri: &str) -> Result<String, JamiError> {
+        match JamiUri::parse(uri) {
+            Ok(JamiUri::Hash(hash)) => Ok(hash.to_string()),
+            Ok(JamiUri::Username(_)) => Err(JamiError::InvalidArgument(
+                String::from("uri"),
+                String::from("a hash is needed, lookup the name first"),
+            )),
+            Err(e) => Err(JamiError::InvalidArgument(
+                String::from("uri"),
+                e.to_string(),
+            )),
+        }
+    }
+
+    /**
+     * Remove and ban a contact
+     * @param uri   Contact to ban (bare hash or jami: uri)
+     */
+    pub fn ban_contact(&mut self, uri: &str) -> Result<(), JamiError> {
+        let hash = Account::peer_hash(uri)?;
+        Jami::try_remove_contact(&self.id, &hash, true)?;
+        if let Some(banned) = &mut self.banned {
+            banned.insert(hash);
+        }
+        Ok(())
+    }
+
+    /**
+     * Unban a contact (the contact is added back)
+     * @param uri   Contact to unban (bare hash or jami: uri)
+     */
+    pub fn unban_contact(&mut self, uri: &str) -> Result<(), JamiError> {
+        let hash = Account::peer_hash(uri)?;
+        Jami::try_add_contact(&self.id, &hash)?;
+        if let Some(banned) = &mut self.banned {
+            banned.remove(&hash);
+        }
+        Ok(())
+    }
+
+    /**
+     * Get banned contacts. The list is cached after the first call,
+     * use refresh_banned_contacts() to reload it
+     * @return banned uris
+     */
+    pub fn banned_contacts(&mut self) -> Result<Vec<String>, JamiError> {
+        if self.banned.is_none() {
+            self.refresh_banned_contacts()?;
+        }
+        let mut banned: Vec<String> = self.banned.iter().flatten().cloned().collect();
+        banned.sort();
+        Ok(banned)
+    }
+
+    /**
+     * Reload banned contacts from the daemon. The cache is kept on errors
+     */
+    pub fn refresh_banned_contacts(&mut self) -> Result<(), JamiError> {
+        let contacts = Jami::try_get_contacts(&self.id)?;
+        self.banned = Some(
+            contacts
+                .into_iter()
+                .filter(|c| c.get("banned").map(|b| b == "true").unwrap_or(false))
+                .filter_map(|mut c| c.remove("id"))
+                .collect(),
+        );
+        Ok(())
+    }
+
+    /**
+     * @param uri   Contact to check (bare hash or jami: uri)
+     * @return if the contact is banned
+     */
+    pub fn is_banned(&mut self, uri: &str) -> Result<bool, JamiError> {
+        let hash = uri::normalize_peer(uri);
+        if self.banned.is_none() {
+            self.refresh_banned_contacts()?;
+        }
+        Ok(self
+            .banned
+            .as_ref()
+            .map(|b| b.contains(&hash))
+            .unwrap_or(false))
+    }
+
+    /**
+     * Get default moderators of the conferences hosted by this account.
+     * The list is cached after the first call, use refresh_moderators() to reload it
+     * @return moderators uris
+     */
+    pub fn moderators(&mut self) -> Result<Vec<String>, JamiError> {
+        if self.moderators.is_none() {
+            self.refresh_moderators()?;
+        }
+        let mut moderators: Vec<String> = self.moderators.iter().flatten().cloned().collect();
+        moderators.sort();
+        Ok(moderators)
+    }
+
+    /**
+     * Reload default moderators from the daemon. The cache is kept on errors
+     */
+    pub fn refresh_moderators(&mut self) -> Result<(), JamiError> {
+        let moderators = Jami::try_get_default_moderators(&self.id)?;
+        self.moderators = Some(moderators.iter().map(|m| uri::normalize_peer(m)).collect());
+        Ok(())
+    }
+
+    /**
+     * Add a default moderator
+     * @param uri   Peer (bare hash or jami: uri)
+     */
+    pub fn add_moderator(&mut self, uri: &str) -> Result<(), JamiError> {
+        let hash = Account::peer_hash(uri)?;
+        Jami::try_set_default_moderator(&self.id, &hash, true)?;
+        if let Some(moderators) = &mut self.moderators {
+            moderators.insert(hash);
+        }
+        Ok(())
+    }
+
+    /**
+     * Remove a default moderator
+     * @param uri   Peer (bare hash or jami: uri)
+     */
+    pub fn remove_moderator(&mut self, uri: &str) -> Result<(), JamiError> {
+        let hash = Account::peer_hash(uri)?;
+        Jami::try_set_default_moderator(&self.id, &hash, false)?;
+        if let Some(moderators) = &mut self.moderators {
+            moderators.remove(&hash);
+        }
+        Ok(())
+    }
+
+    /**
+     * Make every participant of the hosted conferences a moderator
+     * @param enabled   If all participants are moderators
+     */
+    pub fn set_all_moderators(&self, enabled: bool) -> Result<(), JamiError> {
+        Jami::try_set_all_moderators(&self.id, enabled)
+    }
+
+    /**
+     * @return if every participant of the hosted conferences is a moderator
+     */
+    pub fn all_moderators(&self) -> Result<bool, JamiError> {
+        Jami::try_is_all_moderators(&self.id)
+    }
+
+    /**
+     * Get credentials (SIP only)
+     * @return the credentials
+     */
+    pub fn credentials(&self) -> Result<Vec<HashMap<String, String>>, JamiError> {
+        self.ensure_kind(AccountType::Sip)?;
+        Jami::try_get_credentials(&self.id)
+    }
+
+    /**
+     * Set credentials (SIP only)
+     * @param credentials   New credentials
+     */
+    pub fn set_credentials(
+        &self,
+        credentials: Vec<HashMap<String, String>>,
+    ) -> Result<(), JamiError> {
+        self.ensure_kind(AccountType::Sip)?;
+        Jami::try_set_credentials(&self.id, credentials)
+    }
+
+    /**
+     * Rename the account. The struct is unchanged if the daemon refuses
+     * @param alias     New alias
+     */
+    pub fn set_alias(&mut self, alias: &str) -> Result<(), JamiError> {
+        Jami::set_account_detail(&self.id, "Account.alias", alias)?;
+        self.alias = alias.to_string();
+        Ok(())
+    }
+
+    /**
+     * Enable or disable the account. The struct is unchanged if the daemon refuses
+     * @param enabled   If the account is enabled
+     */
+    pub fn set_enabled(&mut self, enabled: bool) -> Result<(), JamiError> {
+        Jami::set_account_detail(&self.id, "Account.enable", &enabled.to_string())?;
+        self.enabled = enabled;
+        Ok(())
+    }
+
+    /**
+     * Change the display name sent to peers
+     * @param display_name  New display name
+     */
+    pub fn set_display_name(&self, display_name: &str) -> Result<(), JamiError> {
+        Jami::set_account_detail(&self.id, "Account.displayName", display_name)
+    }
+
+    /**
+     * Answer incoming calls automatically
+     * @param auto_answer   If calls are answered
+     */
+    pub fn set_auto_answer(&self, auto_answer: bool) -> Result<(), JamiError> {
+        Jami::set_account_detail(&self.id, "Account.autoAnswer", &auto_answer.to_string())
+    }
+
+    /**
+     * Follow the changes of this account. Must be called from a tokio runtime.
+     * The stream ends after AccountChange::Removed or when the handle is dropped
+     * @param events    Events of the daemon
+     * @return the changes
+     */
+    pub fn watch(&self, events: &EventLoopHandle) -> impl Stream<Item = AccountChange> {
+        let mut rx = events.subscribe();
+        let (tx, changes) = mpsc::unbounded_channel();
+        let id = self.id.clone();
+        tokio::spawn(async move {
+            loop {
+                let event = match rx.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::RecvError::Closed) => break,
+                };
+                if let Event::AccountsChanged() = event {
+                    let account = id.clone();
+                    // Not on errors, the daemon may just be restarting
+                    let removed = tokio::task::spawn_blocking(move || {
+                        matches!(Jami::try_get_account(&account), Ok(None))
+                    })
+                    .await
+                    .unwrap_or(false);
+                    if removed {
+                        let _ = tx.send(AccountChange::Removed);
+                        break;
+                    }
+                } else if let Some(change) = AccountChange::from_event(&id, &event) {
+                    if tx.send(change).is_err() {
+                        // Stream dropped
+                        break;
+                    }
+                }
+            }
+        });
+        changes
+    }
+
+    /**
+     * Compare the fields of two snapshots of an account
+     * @param other     Newer snapshot
+     * @return names of the fields that differ
+     */
+    pub fn changed_fields(&self, other: &Account) -> Vec<String> {
+        let mut fields = Vec::new();
+        let mut check = |name: &str, changed: bool| {
+            if changed {
+                fields.push(name.to_string());
+            }
+        };
+        check("hash", self.hash != other.hash);
+        check("alias", self.alias != other.alias);
+        check(
+            "registered_name",
+            self.registered_name != other.registered_name,
+        );
+        check("enabled", self.enabled != other.enabled);
+        check("kind", self.kind != other.kind);
+        check("hostname", self.hostname != other.hostname);
+        check("username", self.username != other.username);
+        check(
+            "registration_state",
+            self.registration_state != other.registration_state,
+        );
+        check(
+            "device_announced",
+            self.device_announced != other.device_announced,
+        );
+        check("device_id", self.device_id != other.device_id);
+        check("devices", self.devices != other.devices);
+        fields
+    }
+
+    /**
+     * Get proxy and push settings, with the proxy really used if the daemon reports it
+     */
+    pub fn push_config(&self) -> PushConfig {
+        PushConfig::from_details(
+            &Jami::try_get_account_details(&self.id).unwrap_or_default(),
+            &Jami::try_get_volatile_account_details(&self.id).unwrap_or_default(),
+        )
+    }
+
+    /**
+     * Change proxy and push settings together. The token and the topic are given to
+     * the daemon with setPushNotificationToken and setPushNotificationTopic
+     * @param config    New settings (effective_proxy is ignored)
+     */
+    pub fn set_push_config(&self, config: PushConfig) -> Result<(), JamiError> {
+        config.validate()?;
+        let mut changes = HashMap::new();
+        changes.insert(
+            String::from("Account.proxyEnabled"),
+            config.proxy_enabled.to_string(),
+        );
+        changes.insert(String::from("Account.proxyServer"), config.proxy_server);
+        Jami::update_account_details(&self.id, changes)?;
+        Jami::set_push_notification_token(&config.push_token, &config.push_topic)
+    }
+
+    /**
+     * Re-read details and volatile details from the daemon
+     */
+    pub fn refresh(&mut self) {
+        if let Ok(details) = Jami::try_get_account_details(&self.id) {
+            self.update_details(&details);
+        }
+        if let Ok(details) = Jami::try_get_volatile_account_details(&self.id) {
+            self.update_volatile_details(&details);
+        }
+    }
+
+    /**
+     * Update fields from account details
+     * @param details   Details from getAccountDetails
+     */
+    pub fn update_details(&mut self, details: &HashMap<String, String>) {
+        // Account.username depends on the type
+        if let Some(kind) = details.get("Account.type") {
+            self.kind = kind.parse().unwrap();
+        }
+        for (key, value) in details {
+            match &**key {
+                "Account.enable" => self.enabled = value == "true",
+                "Account.alias" => self.alias = value.clone(),
+                "Account.hostname" => self.hostname = value.clone(),
+                "Account.username" => {
+                    if self.kind == AccountType::Sip {
+                        self.username = value.clone()
+                    } else {
+                        self.hash = uri::normalize_peer(value)
+                    }
+                }
+                "Account.registeredName" if !value.is_empty() => {
+                    self.registered_name = value.clone()
+                }
+                "Account.deviceID" => self.device_id = value.clone(),
+                _ => {}
+            }
+        }
+    }
+
+    /**
+     * Update fields from volatile details
+     * @param details   Details from getVolatileAccountDetails
+     */
+    pub fn update_volatile_details(&mut self, details: &HashMap<String, String>) {
+        for (key, value) in details {
+            match &**key {
+                "Account.registrationStatus" => self.registration_state = value.parse().unwrap(),
+                "Account.deviceAnnounced" => self.device_announced = value == "true",
+                "Account.registeredName" if !value.is_empty() => {
+                    self.registered_name = value.clone()
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /**
+     * Retrieve known devices from the daemon
+     */
+    pub fn load_devices(&mut self) {
+        if let Ok(devices) = Jami::try_get_known_devices(&self.id) {
+            self.set_known_devices(&devices);
+        }
+    }
+
+    /**
+     * Update devices (on Event::KnownDevicesChanged)
+     * @param devices   device id -> device name
+     */
+    pub fn set_known_devices(&mut self, devices: &HashMap<String, String>) {
+        let mut devices: Vec<Device> = devices
+            .iter()
+            .map(|(id, name)| Device {
+                id: id.clone(),
+                name: name.clone(),
+                is_current: *id == self.device_id,
+            })
+            .collect();
+        devices.sort_by(|a, b| b.is_current.cmp(&a.is_current).then(a.name.cmp(&b.name)));
+        self.devices = devices;
+    }
+
+    /**
+     * @return the bare hash of the account (no jami:/ring: prefix)
+     */
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    /**
+     * @return the uri of the account
+     */
+    pub fn uri(&self) -> JamiUri {
+        match InfoHash::parse(&self.hash) {
+            Ok(hash) => JamiUri::Hash(hash),
+            // Not loaded yet, or a SIP account
+            Err(_) => JamiUri::Username(self.hash.clone()),
         }
     }
 
+    /**
+     * Link to share to be added as a contact
+     * @return https://jami.net/?uri=<registered name or hash>
+     */
+    pub fn share_uri(&self) -> String {
+        let id = if self.registered_name.is_empty() {
+            &self.hash
+        } else {
+            &self.registered_name
+        };
+        format!("https://jami.net/?uri={}", id)
+    }
+
+    /**
+     * Content of the QR code to show to be added as a contact
+     * @return jami:<hash>
+     */
+    pub fn qr_payload(&self) -> String {
+        self.uri().with_scheme()
+    }
+
+    /**
+     * Render the QR code of qr_payload()
+     * @return the SVG image
+     */
+    #[cfg(feature = "qrcode")]
+    pub fn qr_svg(&self) -> String {
+        match qrcode::QrCode::new(self.qr_payload().as_bytes()) {
+            Ok(code) => code.render::<qrcode::render::svg::Color>().build(),
+            Err(_) => String::new(),
+        }
+    }
+
+    /**
+     * Name to show: registered name, else alias, else the start of the hash
+     */
+    pub fn display_identity(&self) -> String {
+        display_identity(&self.registered_name, &self.alias, &self.hash)
+    }
+
+    /**
+     * Abbreviated hash
+     * @param len   Number of chars to keep
+     */
+    pub fn short_hash(&self, len: usize) -> String {
+        self.hash.chars().take(len).collect()
+    }
+
+    /**
+     * Path of the vCard of the account (jami::app_data + id/profile.vcf)
+     */
+    pub fn profile_path(&self) -> Option<String> {
+        let path = get_app_dir(
+            AppDataType::UserData,
+            &AppInfo {
+                name: "jami",
+                author: "SFL",
+            },
+            &format!("{}/profile.vcf", self.id),
+        )
+        .ok()?;
+        path.to_str().map(|p| p.to_string())
+    }
+
+    /**
+     * Read the avatar from the profile of the account and cache it
+     */
+    pub fn load_avatar(&mut self) {
+        self.avatar = self
+            .profile_path()
+            .and_then(|path| Profile::load(&path))
+            .and_then(|profile| profile.avatar_bytes());
+    }
+
+    /**
+     * @return the avatar loaded by load_avatar()
+     */
+    pub fn avatar(&self) -> Option<Vec<u8>> {
+        self.avatar.clone()
+    }
+
+    /**
+     * Change the avatar of the account (the display name is kept).
+     * The cached avatar is unchanged if the daemon refuses
+     * @param path  Path of the new image
+     */
+    pub fn set_avatar(&mut self, path: &str) -> Result<(), JamiError> {
+        let display_name = self
+            .profile_path()
+            .and_then(|path| Profile::load(&path))
+            .map(|profile| profile.display_name)
+            .unwrap_or_default();
+        let file_type = path.rsplit('.').next().unwrap_or_default().to_lowercase();
+        Jami::try_update_profile(&self.id, &display_name, path, &file_type, 0)?;
+        self.load_avatar();
+        Ok(())
+    }
+
     pub fn get_display_name(&self) -> String {
         if !self.alias.is_empty() {
             return self.alias.clone();
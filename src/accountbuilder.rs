@@ -0,0 +1,387 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use super::account::RegistrationState;
+use super::client::JamiClient;
+use super::error::JamiError;
+use super::eventloop::EventLoopHandle;
+use super::{Event, Jami};
+
+use log::info;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tokio::stream::Stream;
+use tokio::sync::{broadcast, mpsc, oneshot};
+
+// (account id, watch id, to notify) for the creations followed (cf CreationWatch)
+static CREATION_WATCHES: Mutex<Vec<(String, u64, oneshot::Sender<()>)>> = Mutex::new(Vec::new());
+static NEXT_WATCH_ID: AtomicU64 = AtomicU64::new(0);
+
+/**
+ * Wait on the creation of an account, ended by Jami::abort_account_creation
+ **/
+pub(crate) struct CreationWatch {
+    id: u64,
+    // Resolves when the creation is aborted
+    pub(crate) aborted: oneshot::Receiver<()>,
+}
+
+impl CreationWatch {
+    pub(crate) fn new(account_id: &str) -> CreationWatch {
+        let id = NEXT_WATCH_ID.fetch_add(1, Ordering::Relaxed);
+        let (tx, aborted) = oneshot::channel();
+        CREATION_WATCHES
+            .lock()
+            .unwrap()
+            .push((account_id.to_string(), id, tx));
+        CreationWatch { id, aborted }
+    }
+
+    /**
+     * Stop the waits on the creation of an account
+     * @param account_id    Account removed
+     */
+    pub(crate) fn abort(account_id: &str) {
+        let mut watches = CREATION_WATCHES.lock().unwrap();
+        let (aborted, kept) = watches
+            .drain(..)
+            .partition::<Vec<_>, _>(|(id, _, _)| id == account_id);
+        *watches = kept;
+        for (_, _, tx) in aborted {
+            let _ = tx.send(());
+        }
+    }
+}
+
+impl Drop for CreationWatch {
+    fn drop(&mut self) {
+        CREATION_WATCHES
+            .lock()
+            .unwrap()
+            .retain(|(_, id, _)| *id != self.id);
+    }
+}
+
+/**
+ * Stages of the creation of an account (cf AccountBuilder::build_with_progress)
+ **/
+#[derive(Clone, Debug, PartialEq)]
+pub enum AccountCreationProgress {
+    // addAccount accepted the details
+    Initializing,
+    // The daemon generates the keys (INITIALIZING)
+    GeneratingKeys,
+    // Keys are ready, waiting for the device to be announced on the DHT
+    Announcing,
+    // Registered and announced. Last stage
+    Registered,
+    // Last stage
+    Failed(String),
+}
+
+/**
+ * Map the events of the daemon for a new account to AccountCreationProgress
+ **/
+#[derive(Clone, Debug)]
+pub struct CreationProgressTracker {
+    account_id: String,
+    stage: AccountCreationProgress,
+    registered: bool,
+    announced: bool,
+}
+
+impl CreationProgressTracker {
+    /**
+     * @param account_id    Id returned by addAccount
+     */
+    pub fn new(account_id: &str) -> CreationProgressTracker {
+        CreationProgressTracker {
+            account_id: account_id.to_string(),
+            stage: AccountCreationProgress::Initializing,
+            registered: false,
+            announced: false,
+        }
+    }
+
+    /**
+     * @return the current stage
+     */
+    pub fn stage(&self) -> &AccountCreationProgress {
+        &self.stage
+    }
+
+    /**
+     * @return if Registered or Failed is reached
+     */
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.stage,
+            AccountCreationProgress::Registered | AccountCreationProgress::Failed(_)
+        )
+    }
+
+    /**
+     * Update the stage from an event
+     * @param event     Event from the event loop
+     * @return the new stage if it changed
+     */
+    pub fn on_event<I>(&mut self, event: &Event<I>) -> Option<AccountCreationProgress> {
+        if self.is_finished() {
+            return None;
+        }
+        let stage = match event {
+            Event::RegistrationStateChanged {
+                account_id,
+                state,
+                code,
+                detail,
+                ..
+            } if *account_id == self.account_id => {
+                self.registered = state.is_registered();
+                if *state == RegistrationState::Initializing {
+                    AccountCreationProgress::GeneratingKeys
+                } else if state.is_error() {
+                    AccountCreationProgress::Failed(format!("{} ({} {})", state, code, detail))
+                } else {
+                    self.next_stage()
+                }
+            }
+            Event::VolatileDetailsChanged(account_id, details)
+                if *account_id == self.account_id =>
+            {
+                if let Some(announced) = details.get("Account.deviceAnnounced") {
+                    self.announced = announced == "true";
+                }
+                if let Some(state) = details.get("Account.registrationStatus") {
+                    let state: RegistrationState = state.parse().unwrap();
+                    if state == RegistrationState::Initializing {
+                        return None;
+                    }
+                    self.registered = state.is_registered();
+                }
+                if self.stage == AccountCreationProgress::Initializing {
+                    return None;
+                }
+                self.next_stage()
+            }
+            _ => return None,
+        };
+        if stage == self.stage {
+            return None;
+        }
+        self.stage = stage.clone();
+        Some(stage)
+    }
+
+    fn next_stage(&self) -> AccountCreationProgress {
+        if self.registered && self.announced {
+            AccountCreationProgress::Registered
+        } else {
+            AccountCreationProgress::Announcing
+        }
+    }
+}
+
+/**
+ * State of an AccountBuilder creating a new identity
+ **/
+pub struct NewAccount;
+
+/**
+ * State of an AccountBuilder importing an existing identity (backup or pin).
+ * from_backup()/from_pin() are not available anymore, so both can't be used.
+ **/
+pub struct ImportedAccount;
+
+/**
+ * Build the details of a new account and create it
+ * e.g. AccountBuilder::new_ring().alias("bot").display_name("Bot").build()
+ **/
+pub struct AccountBuilder<S = NewAccount> {
+    details: HashMap<String, String>,
+    state: PhantomData<S>,
+}
+
+impl AccountBuilder<NewAccount> {
+    /**
+     * Generate a builder for a Jami account
+     * @return the new builder
+     */
+    pub fn new_ring() -> Self {
+        let mut details = HashMap::new();
+        details.insert(String::from("Account.type"), String::from("RING"));
+        Self {
+            details,
+            state: PhantomData,
+        }
+    }
+
+    /**
+     * Import the account from an archive
+     * @param path      Path of the archive
+     */
+    pub fn from_backup(mut self, path: &str) -> AccountBuilder<ImportedAccount> {
+        self.details
+            .insert(String::from("Account.archivePath"), path.to_string());
+        AccountBuilder {
+            details: self.details,
+            state: PhantomData,
+        }
+    }
+
+    /**
+     * Import the account from another device
+     * @param pin       Pin generated by the other device
+     */
+    pub fn from_pin(mut self, pin: &str) -> AccountBuilder<ImportedAccount> {
+        self.details
+            .insert(String::from("Account.archivePin"), pin.to_string());
+        AccountBuilder {
+            details: self.details,
+            state: PhantomData,
+        }
+    }
+}
+
+impl<S> AccountBuilder<S> {
+    pub fn alias(self, alias: &str) -> Self {
+        self.detail("Account.alias", alias)
+    }
+
+    /**
+     * Password of the archive
+     */
+    pub fn password(self, password: &str) -> Self {
+        self.detail("Account.archivePassword", password)
+    }
+
+    pub fn display_name(self, display_name: &str) -> Self {
+        self.detail("Account.displayName", display_name)
+    }
+
+    pub fn device_name(self, device_name: &str) -> Self {
+        self.detail("Account.deviceName", device_name)
+    }
+
+    pub fn auto_answer(self, auto_answer: bool) -> Self {
+        self.detail(
+            "Account.autoAnswer",
+            if auto_answer { "true" } else { "false" },
+        )
+    }
+
+    /**
+     * Use a DHT proxy
+     * @param server    Proxy to use
+     */
+    pub fn proxy(self, server: &str) -> Self {
+        self.detail("Account.proxyEnabled", "true")
+            .detail("Account.proxyServer", server)
+    }
+
+    /**
+     * Set any other detail
+     * @param key       Key of the detail
+     * @param value     Value of the detail
+     */
+    pub fn detail(mut self, key: &str, value: &str) -> Self {
+        self.details.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /**
+     * Get the details that will be sent (without the template)
+     */
+    pub fn details(&self) -> &HashMap<String, String> {
+        &self.details
+    }
+
+    /**
+     * Create the account and follow its creation. Must be called from a tokio runtime
+     * @param events    Events of the daemon (the event loop must be started)
+     * @return the id of the account and its stages, until Registered or Failed
+     *         (Failed("aborted") after Jami::abort_account_creation)
+     */
+    pub fn build_with_progress(
+        self,
+        events: &EventLoopHandle,
+    ) -> Result<(String, impl Stream<Item = AccountCreationProgress>), JamiError> {
+        // Subscribe first to not miss the first events
+        let mut rx = events.subscribe();
+        let account_id = self.build()?;
+        let (tx, progress) = mpsc::unbounded_channel();
+        let _ = tx.send(AccountCreationProgress::Initializing);
+        let mut tracker = CreationProgressTracker::new(&account_id);
+        let mut watch = CreationWatch::new(&account_id);
+        tokio::spawn(async move {
+            while !tracker.is_finished() {
+                let event = tokio::select! {
+                    event = rx.recv() => event,
+                    _ = &mut watch.aborted => {
+                        let _ = tx.send(AccountCreationProgress::Failed(String::from("aborted")));
+                        break;
+                    }
+                };
+                let event = match event {
+                    Ok(event) => event,
+                    Err(broadcast::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::RecvError::Closed) => break,
+                };
+                if let Some(stage) = tracker.on_event(&event) {
+                    if tx.send(stage).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        Ok((account_id, progress))
+    }
+
+    /**
+     * Create the account. Details are merged into the daemon's template
+     * @return the id of the new account
+     */
+    pub fn build(self) -> Result<String, JamiError> {
+        let account_type = self
+            .details
+            .get("Account.type")
+            .cloned()
+            .unwrap_or_default();
+        let mut details = Jami::try_get_account_template(&account_type)?;
+        details.extend(self.details);
+
+        let (result,): (String,) = JamiClient::shared()?.configuration("addAccount", (details,))?;
+        if result.is_empty() {
+            return Err(JamiError::InvalidReply(String::from(
+                "addAccount returned an empty id",
+            )));
+        }
+        info!("New account: {:?}", result);
+        Ok(result)
+    }
+}
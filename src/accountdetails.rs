@@ -0,0 +1,573 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use super::account::AccountType;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/**
+ * Well-known keys of account details
+ **/
+pub mod details_keys {
+    pub const ACCOUNT_TYPE: &str = "Account.type";
+    pub const ALIAS: &str = "Account.alias";
+    pub const DISPLAY_NAME: &str = "Account.displayName";
+    pub const ENABLED: &str = "Account.enable";
+    pub const USERNAME: &str = "Account.username";
+    pub const HOSTNAME: &str = "Account.hostname";
+    pub const PASSWORD: &str = "Account.password";
+    pub const MAILBOX: &str = "Account.mailbox";
+    pub const USER_AGENT: &str = "Account.useragent";
+    pub const HAS_CUSTOM_USER_AGENT: &str = "Account.hasCustomUserAgent";
+    pub const ROUTESET: &str = "Account.routeset";
+    pub const DEVICE_ID: &str = "Account.deviceID";
+    pub const DEVICE_NAME: &str = "Account.deviceName";
+    pub const REGISTERED_NAME: &str = "Account.registeredName";
+    pub const REGISTRATION_EXPIRE: &str = "Account.registrationExpire";
+    pub const ARCHIVE_PATH: &str = "Account.archivePath";
+    pub const ARCHIVE_PIN: &str = "Account.archivePin";
+    pub const ARCHIVE_PASSWORD: &str = "Account.archivePassword";
+    pub const ARCHIVE_HAS_PASSWORD: &str = "Account.archiveHasPassword";
+    pub const MANAGER_URI: &str = "Account.managerUri";
+    pub const MANAGER_USERNAME: &str = "Account.managerUsername";
+    pub const NAME_SERVER: &str = "RingNS.uri";
+    pub const AUTO_ANSWER: &str = "Account.autoAnswer";
+    pub const SEND_READ_RECEIPT: &str = "Account.sendReadReceipt";
+    pub const SEND_COMPOSING: &str = "Account.sendComposing";
+    pub const IS_RENDEZVOUS: &str = "Account.rendezVous";
+    pub const ACTIVE_CALL_LIMIT: &str = "Account.activeCallLimit";
+    pub const RINGTONE_ENABLED: &str = "Account.ringtoneEnabled";
+    pub const RINGTONE_PATH: &str = "Account.ringtonePath";
+    pub const VIDEO_ENABLED: &str = "Account.videoEnabled";
+    pub const UPNP_ENABLED: &str = "Account.upnpEnabled";
+    pub const PEER_DISCOVERY: &str = "Account.peerDiscovery";
+    pub const ACCOUNT_DISCOVERY: &str = "Account.accountDiscovery";
+    pub const ACCOUNT_PUBLISH: &str = "Account.accountPublish";
+    pub const DHT_PORT: &str = "Account.dhtPort";
+    pub const PROXY_ENABLED: &str = "Account.proxyEnabled";
+    pub const PROXY_SERVER: &str = "Account.proxyServer";
+    pub const PROXY_LIST_URL: &str = "Account.dhtProxyListUrl";
+    pub const PROXY_PUSH_TOKEN: &str = "Account.proxyPushToken";
+    pub const PUSH_TOPIC: &str = "Account.notificationTopic";
+    pub const DEFAULT_MODERATORS: &str = "Account.defaultModerators";
+    pub const LOCAL_MODERATORS_ENABLED: &str = "Account.localModeratorsEnabled";
+    pub const ALL_MODERATORS_ENABLED: &str = "Account.allModeratorsEnabled";
+    pub const UI_CUSTOMIZATION: &str = "Account.uiCustomization";
+    pub const ALLOW_CERT_FROM_HISTORY: &str = "Account.allowCertFromHistory";
+    pub const ALLOW_CERT_FROM_CONTACT: &str = "Account.allowCertFromContact";
+    pub const ALLOW_CERT_FROM_TRUSTED: &str = "Account.allowCertFromTrusted";
+    pub const PRESENCE_ENABLED: &str = "Account.presenceEnabled";
+    pub const PRESENCE_PUBLISH_SUPPORTED: &str = "Account.presencePublishSupported";
+    pub const PRESENCE_SUBSCRIBE_SUPPORTED: &str = "Account.presenceSubscribeSupported";
+    pub const PRESENCE_NOTE: &str = "Account.presenceNote";
+    pub const LOCAL_INTERFACE: &str = "Account.localInterface";
+    pub const LOCAL_PORT: &str = "Account.localPort";
+    pub const PUBLISHED_SAME_AS_LOCAL: &str = "Account.publishedSameAsLocal";
+    pub const PUBLISHED_ADDRESS: &str = "Account.publishedAddress";
+    pub const PUBLISHED_PORT: &str = "Account.publishedPort";
+    pub const DTMF_TYPE: &str = "Account.dtmfType";
+    pub const STUN_ENABLED: &str = "STUN.enable";
+    pub const STUN_SERVER: &str = "STUN.server";
+    pub const TURN_ENABLED: &str = "TURN.enable";
+    pub const TURN_SERVER: &str = "TURN.server";
+    pub const TURN_USERNAME: &str = "TURN.username";
+    pub const TURN_PASSWORD: &str = "TURN.password";
+    pub const TURN_REALM: &str = "TURN.realm";
+    pub const SRTP_ENABLED: &str = "SRTP.enable";
+    pub const SRTP_KEY_EXCHANGE: &str = "SRTP.keyExchange";
+    pub const SRTP_RTP_FALLBACK: &str = "SRTP.rtpFallback";
+    pub const TLS_ENABLED: &str = "TLS.enable";
+    pub const TLS_LISTENER_PORT: &str = "TLS.listenerPort";
+    pub const TLS_CA_LIST_FILE: &str = "TLS.certificateListFile";
+    pub const TLS_CERTIFICATE_FILE: &str = "TLS.certificateFile";
+    pub const TLS_PRIVATE_KEY_FILE: &str = "TLS.privateKeyFile";
+    pub const TLS_PASSWORD: &str = "TLS.password";
+    pub const TLS_METHOD: &str = "TLS.method";
+    pub const TLS_CIPHERS: &str = "TLS.ciphers";
+    pub const TLS_SERVER_NAME: &str = "TLS.serverName";
+    pub const TLS_VERIFY_SERVER: &str = "TLS.verifyServer";
+    pub const TLS_VERIFY_CLIENT: &str = "TLS.verifyClient";
+    pub const TLS_REQUIRE_CLIENT_CERTIFICATE: &str = "TLS.requireClientCertificate";
+    pub const TLS_NEGOTIATION_TIMEOUT: &str = "TLS.negotiationTimeoutSec";
+    // Volatile details (getVolatileAccountDetails)
+    pub const REGISTRATION_STATUS: &str = "Account.registrationStatus";
+    pub const REGISTRATION_CODE: &str = "Account.registrationCode";
+    pub const REGISTRATION_DESCRIPTION: &str = "Account.registrationDescription";
+    pub const DEVICE_ANNOUNCED: &str = "Account.deviceAnnounced";
+}
+
+use details_keys as keys;
+
+/**
+ * Kind of value of a detail, to choose how to edit it
+ **/
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum DetailKind {
+    Bool,
+    Int,
+    String,
+    // One of the given values
+    Enum(Vec<String>),
+}
+
+/**
+ * A detail of an account (cf Jami::account_detail_schema)
+ **/
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct DetailField {
+    pub key: String,
+    pub kind: DetailKind,
+    // Value in the template of the daemon
+    pub default: String,
+}
+
+const BOOL_KEYS: &[&str] = &[
+    keys::ENABLED,
+    keys::HAS_CUSTOM_USER_AGENT,
+    keys::ARCHIVE_HAS_PASSWORD,
+    keys::AUTO_ANSWER,
+    keys::SEND_READ_RECEIPT,
+    keys::SEND_COMPOSING,
+    keys::IS_RENDEZVOUS,
+    keys::RINGTONE_ENABLED,
+    keys::VIDEO_ENABLED,
+    keys::UPNP_ENABLED,
+    keys::PEER_DISCOVERY,
+    keys::ACCOUNT_DISCOVERY,
+    keys::ACCOUNT_PUBLISH,
+    keys::PROXY_ENABLED,
+    keys::LOCAL_MODERATORS_ENABLED,
+    keys::ALL_MODERATORS_ENABLED,
+    keys::ALLOW_CERT_FROM_HISTORY,
+    keys::ALLOW_CERT_FROM_CONTACT,
+    keys::ALLOW_CERT_FROM_TRUSTED,
+    keys::PRESENCE_ENABLED,
+    keys::PRESENCE_PUBLISH_SUPPORTED,
+    keys::PRESENCE_SUBSCRIBE_SUPPORTED,
+    keys::PUBLISHED_SAME_AS_LOCAL,
+    keys::STUN_ENABLED,
+    keys::TURN_ENABLED,
+    keys::SRTP_ENABLED,
+    keys::SRTP_RTP_FALLBACK,
+    keys::TLS_ENABLED,
+    keys::TLS_VERIFY_SERVER,
+    keys::TLS_VERIFY_CLIENT,
+    keys::TLS_REQUIRE_CLIENT_CERTIFICATE,
+];
+
+const INT_KEYS: &[&str] = &[
+    keys::REGISTRATION_EXPIRE,
+    keys::ACTIVE_CALL_LIMIT,
+    keys::DHT_PORT,
+    keys::LOCAL_PORT,
+    keys::PUBLISHED_PORT,
+    keys::TLS_LISTENER_PORT,
+    keys::TLS_NEGOTIATION_TIMEOUT,
+];
+
+const ENUM_KEYS: &[(&str, &[&str])] = &[
+    (keys::ACCOUNT_TYPE, &["RING", "SIP"]),
+    (keys::DTMF_TYPE, &["overrtp", "sipinfo"]),
+    (keys::SRTP_KEY_EXCHANGE, &["", "sdes"]),
+    (
+        keys::TLS_METHOD,
+        &["Default", "TLSv1", "TLSv1.1", "TLSv1.2"],
+    ),
+];
+
+impl DetailKind {
+    /**
+     * Get the kind of a detail, from the keys known by jami-rs, else from its value
+     * @param key       Key of the detail
+     * @param value     Its value, e.g. the default one
+     */
+    pub fn of(key: &str, value: &str) -> DetailKind {
+        if BOOL_KEYS.contains(&key) {
+            return DetailKind::Bool;
+        }
+        if INT_KEYS.contains(&key) {
+            return DetailKind::Int;
+        }
+        if let Some((_, values)) = ENUM_KEYS.iter().find(|(k, _)| *k == key) {
+            return DetailKind::Enum(values.iter().map(|v| v.to_string()).collect());
+        }
+        if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+            DetailKind::Bool
+        } else if !value.is_empty() && value.parse::<i64>().is_ok() {
+            DetailKind::Int
+        } else {
+            DetailKind::String
+        }
+    }
+}
+
+impl DetailField {
+    /**
+     * Describe the details of an account template
+     * @param template  Result of getAccountTemplate
+     * @return the fields, sorted by key
+     */
+    pub fn from_template(template: &HashMap<String, String>) -> Vec<DetailField> {
+        let mut fields: Vec<DetailField> = template
+            .iter()
+            .map(|(key, default)| DetailField {
+                key: key.clone(),
+                kind: DetailKind::of(key, default),
+                default: default.clone(),
+            })
+            .collect();
+        fields.sort_by(|a, b| a.key.cmp(&b.key));
+        fields
+    }
+}
+
+// Generate a typed getter/setter for a boolean detail
+macro_rules! bool_detail {
+    ($get:ident, $set:ident, $key:expr) => {
+        pub fn $get(&self) -> Option<bool> {
+            self.get_bool($key)
+        }
+
+        pub fn $set(&mut self, value: bool) {
+            self.set_bool($key, value)
+        }
+    };
+}
+
+// Generate a typed getter/setter for an integer detail
+macro_rules! int_detail {
+    ($get:ident, $set:ident, $key:expr, $t:ty) => {
+        pub fn $get(&self) -> Option<$t> {
+            self.get_parsed($key)
+        }
+
+        pub fn $set(&mut self, value: $t) {
+            self.set($key, &value.to_string())
+        }
+    };
+}
+
+// Generate a typed getter/setter for a string (or path) detail
+macro_rules! string_detail {
+    ($get:ident, $set:ident, $key:expr) => {
+        pub fn $get(&self) -> Option<&str> {
+            self.get($key)
+        }
+
+        pub fn $set(&mut self, value: &str) {
+            self.set($key, value)
+        }
+    };
+}
+
+/**
+ * Typed view of the details of an account (getAccountDetails).
+ * Unknown keys are kept untouched.
+ **/
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct AccountDetails {
+    map: HashMap<String, String>,
+}
+
+impl AccountDetails {
+    pub fn from_map(map: HashMap<String, String>) -> Self {
+        Self { map }
+    }
+
+    pub fn into_map(self) -> HashMap<String, String> {
+        self.map
+    }
+
+    pub fn as_map(&self) -> &HashMap<String, String> {
+        &self.map
+    }
+
+    /**
+     * Get a raw detail
+     * @param key   Key of the detail (cf details_keys)
+     */
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.map.get(key).map(|v| &**v)
+    }
+
+    /**
+     * Set a raw detail
+     * @param key   Key of the detail (cf details_keys)
+     * @param value New value
+     */
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.map.insert(key.to_string(), value.to_string());
+    }
+
+    /**
+     * Get a boolean detail ("true"/"TRUE"/"True" are accepted)
+     */
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.get(key)? {
+            v if v.eq_ignore_ascii_case("true") => Some(true),
+            v if v.eq_ignore_ascii_case("false") => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn set_bool(&mut self, key: &str, value: bool) {
+        self.set(key, if value { "true" } else { "false" })
+    }
+
+    /**
+     * Get and parse a detail
+     */
+    pub fn get_parsed<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.get(key)?.trim().parse().ok()
+    }
+
+    pub fn account_type(&self) -> Option<AccountType> {
+        self.get_parsed(keys::ACCOUNT_TYPE)
+    }
+
+    pub fn set_account_type(&mut self, value: AccountType) {
+        self.set(keys::ACCOUNT_TYPE, &value.to_string())
+    }
+
+    string_detail!(alias, set_alias, keys::ALIAS);
+    string_detail!(display_name, set_display_name, keys::DISPLAY_NAME);
+    bool_detail!(enabled, set_enabled, keys::ENABLED);
+    string_detail!(username, set_username, keys::USERNAME);
+    string_detail!(hostname, set_hostname, keys::HOSTNAME);
+    string_detail!(password, set_password, keys::PASSWORD);
+    string_detail!(mailbox, set_mailbox, keys::MAILBOX);
+    string_detail!(user_agent, set_user_agent, keys::USER_AGENT);
+    bool_detail!(
+        has_custom_user_agent,
+        set_has_custom_user_agent,
+        keys::HAS_CUSTOM_USER_AGENT
+    );
+    string_detail!(routeset, set_routeset, keys::ROUTESET);
+    string_detail!(device_id, set_device_id, keys::DEVICE_ID);
+    string_detail!(device_name, set_device_name, keys::DEVICE_NAME);
+    string_detail!(registered_name, set_registered_name, keys::REGISTERED_NAME);
+    int_detail!(
+        registration_expire,
+        set_registration_expire,
+        keys::REGISTRATION_EXPIRE,
+        u32
+    );
+    string_detail!(archive_path, set_archive_path, keys::ARCHIVE_PATH);
+    string_detail!(archive_pin, set_archive_pin, keys::ARCHIVE_PIN);
+    string_detail!(
+        archive_password,
+        set_archive_password,
+        keys::ARCHIVE_PASSWORD
+    );
+    bool_detail!(
+        archive_has_password,
+        set_archive_has_password,
+        keys::ARCHIVE_HAS_PASSWORD
+    );
+    string_detail!(manager_uri, set_manager_uri, keys::MANAGER_URI);
+    string_detail!(
+        manager_username,
+        set_manager_username,
+        keys::MANAGER_USERNAME
+    );
+    string_detail!(name_server, set_name_server, keys::NAME_SERVER);
+    bool_detail!(auto_answer, set_auto_answer, keys::AUTO_ANSWER);
+    bool_detail!(
+        send_read_receipt,
+        set_send_read_receipt,
+        keys::SEND_READ_RECEIPT
+    );
+    bool_detail!(send_composing, set_send_composing, keys::SEND_COMPOSING);
+    bool_detail!(is_rendezvous, set_is_rendezvous, keys::IS_RENDEZVOUS);
+    int_detail!(
+        active_call_limit,
+        set_active_call_limit,
+        keys::ACTIVE_CALL_LIMIT,
+        i32
+    );
+    bool_detail!(
+        ringtone_enabled,
+        set_ringtone_enabled,
+        keys::RINGTONE_ENABLED
+    );
+    string_detail!(ringtone_path, set_ringtone_path, keys::RINGTONE_PATH);
+    bool_detail!(video_enabled, set_video_enabled, keys::VIDEO_ENABLED);
+    bool_detail!(upnp_enabled, set_upnp_enabled, keys::UPNP_ENABLED);
+    bool_detail!(peer_discovery, set_peer_discovery, keys::PEER_DISCOVERY);
+    bool_detail!(
+        account_discovery,
+        set_account_discovery,
+        keys::ACCOUNT_DISCOVERY
+    );
+    bool_detail!(account_publish, set_account_publish, keys::ACCOUNT_PUBLISH);
+    int_detail!(dht_port, set_dht_port, keys::DHT_PORT, u16);
+    bool_detail!(proxy_enabled, set_proxy_enabled, keys::PROXY_ENABLED);
+    string_detail!(proxy_server, set_proxy_server, keys::PROXY_SERVER);
+    string_detail!(proxy_list_url, set_proxy_list_url, keys::PROXY_LIST_URL);
+    string_detail!(
+        proxy_push_token,
+        set_proxy_push_token,
+        keys::PROXY_PUSH_TOKEN
+    );
+    string_detail!(push_topic, set_push_topic, keys::PUSH_TOPIC);
+    string_detail!(
+        default_moderators,
+        set_default_moderators,
+        keys::DEFAULT_MODERATORS
+    );
+    bool_detail!(
+        local_moderators_enabled,
+        set_local_moderators_enabled,
+        keys::LOCAL_MODERATORS_ENABLED
+    );
+    bool_detail!(
+        all_moderators_enabled,
+        set_all_moderators_enabled,
+        keys::ALL_MODERATORS_ENABLED
+    );
+    string_detail!(
+        ui_customization,
+        set_ui_customization,
+        keys::UI_CUSTOMIZATION
+    );
+    bool_detail!(
+        allow_cert_from_history,
+        set_allow_cert_from_history,
+        keys::ALLOW_CERT_FROM_HISTORY
+    );
+    bool_detail!(
+        allow_cert_from_contact,
+        set_allow_cert_from_contact,
+        keys::ALLOW_CERT_FROM_CONTACT
+    );
+    bool_detail!(
+        allow_cert_from_trusted,
+        set_allow_cert_from_trusted,
+        keys::ALLOW_CERT_FROM_TRUSTED
+    );
+    bool_detail!(
+        presence_enabled,
+        set_presence_enabled,
+        keys::PRESENCE_ENABLED
+    );
+    bool_detail!(
+        presence_publish_supported,
+        set_presence_publish_supported,
+        keys::PRESENCE_PUBLISH_SUPPORTED
+    );
+    bool_detail!(
+        presence_subscribe_supported,
+        set_presence_subscribe_supported,
+        keys::PRESENCE_SUBSCRIBE_SUPPORTED
+    );
+    string_detail!(presence_note, set_presence_note, keys::PRESENCE_NOTE);
+    string_detail!(local_interface, set_local_interface, keys::LOCAL_INTERFACE);
+    int_detail!(local_port, set_local_port, keys::LOCAL_PORT, u16);
+    bool_detail!(
+        published_same_as_local,
+        set_published_same_as_local,
+        keys::PUBLISHED_SAME_AS_LOCAL
+    );
+    string_detail!(
+        published_address,
+        set_published_address,
+        keys::PUBLISHED_ADDRESS
+    );
+    int_detail!(
+        published_port,
+        set_published_port,
+        keys::PUBLISHED_PORT,
+        u16
+    );
+    string_detail!(dtmf_type, set_dtmf_type, keys::DTMF_TYPE);
+    bool_detail!(stun_enabled, set_stun_enabled, keys::STUN_ENABLED);
+    string_detail!(stun_server, set_stun_server, keys::STUN_SERVER);
+    bool_detail!(turn_enabled, set_turn_enabled, keys::TURN_ENABLED);
+    string_detail!(turn_server, set_turn_server, keys::TURN_SERVER);
+    string_detail!(turn_username, set_turn_username, keys::TURN_USERNAME);
+    string_detail!(turn_password, set_turn_password, keys::TURN_PASSWORD);
+    string_detail!(turn_realm, set_turn_realm, keys::TURN_REALM);
+    bool_detail!(srtp_enabled, set_srtp_enabled, keys::SRTP_ENABLED);
+    string_detail!(
+        srtp_key_exchange,
+        set_srtp_key_exchange,
+        keys::SRTP_KEY_EXCHANGE
+    );
+    bool_detail!(
+        srtp_rtp_fallback,
+        set_srtp_rtp_fallback,
+        keys::SRTP_RTP_FALLBACK
+    );
+    bool_detail!(tls_enabled, set_tls_enabled, keys::TLS_ENABLED);
+    int_detail!(
+        tls_listener_port,
+        set_tls_listener_port,
+        keys::TLS_LISTENER_PORT,
+        u16
+    );
+    string_detail!(
+        tls_ca_list_file,
+        set_tls_ca_list_file,
+        keys::TLS_CA_LIST_FILE
+    );
+    string_detail!(
+        tls_certificate_file,
+        set_tls_certificate_file,
+        keys::TLS_CERTIFICATE_FILE
+    );
+    string_detail!(
+        tls_private_key_file,
+        set_tls_private_key_file,
+        keys::TLS_PRIVATE_KEY_FILE
+    );
+    string_detail!(tls_password, set_tls_password, keys::TLS_PASSWORD);
+    string_detail!(tls_method, set_tls_method, keys::TLS_METHOD);
+    string_detail!(tls_ciphers, set_tls_ciphers, keys::TLS_CIPHERS);
+    string_detail!(tls_server_name, set_tls_server_name, keys::TLS_SERVER_NAME);
+    bool_detail!(
+        tls_verify_server,
+        set_tls_verify_server,
+        keys::TLS_VERIFY_SERVER
+    );
+    bool_detail!(
+        tls_verify_client,
+        set_tls_verify_client,
+        keys::TLS_VERIFY_CLIENT
+    );
+    bool_detail!(
+        tls_require_client_certificate,
+        set_tls_require_client_certificate,
+        keys::TLS_REQUIRE_CLIENT_CERTIFICATE
+    );
+    int_detail!(
+        tls_negotiation_timeout,
+        set_tls_negotiation_timeout,
+        keys::TLS_NEGOTIATION_TIMEOUT,
+        i32
+    );
+}
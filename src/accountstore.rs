@@ -0,0 +1,85 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use super::account::{self, Account, AccountListDiff};
+use super::{Event, Jami};
+
+/**
+ * Keep the last list of accounts to know what changed on Event::AccountsChanged
+ **/
+#[derive(Clone, Debug, Default)]
+pub struct AccountStore {
+    accounts: Vec<Account>,
+}
+
+impl AccountStore {
+    /**
+     * Load the current accounts from the daemon
+     */
+    pub fn new() -> AccountStore {
+        AccountStore {
+            accounts: Jami::try_get_account_list().unwrap_or_default(),
+        }
+    }
+
+    /**
+     * @return the last known accounts
+     */
+    pub fn accounts(&self) -> &[Account] {
+        &self.accounts
+    }
+
+    /**
+     * Reload the accounts from the daemon
+     * @return what changed since the last snapshot
+     */
+    pub fn refresh(&mut self) -> AccountListDiff {
+        self.update(Jami::try_get_account_list().unwrap_or_default())
+    }
+
+    /**
+     * Replace the snapshot
+     * @param accounts  New list
+     * @return what changed since the last snapshot
+     */
+    pub fn update(&mut self, accounts: Vec<Account>) -> AccountListDiff {
+        let diff = account::diff(&self.accounts, &accounts);
+        self.accounts = accounts;
+        diff
+    }
+
+    /**
+     * Refresh on Event::AccountsChanged
+     * @param event     Event from the event loop
+     * @return what changed, None for other events
+     */
+    pub fn apply<I>(&mut self, event: &Event<I>) -> Option<AccountListDiff> {
+        match event {
+            Event::AccountsChanged() => Some(self.refresh()),
+            _ => None,
+        }
+    }
+}
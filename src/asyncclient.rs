@@ -0,0 +1,1005 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use super::account::Account;
+use super::bus::{self, BusType, JamiConfig};
+use super::call::{self, CallInfo, MediaAttribute};
+use super::calllog::logged_call_async;
+use super::error::{JamiError, TransferError};
+use super::uri;
+use super::validate;
+use super::{DataTransferInfo, Jami};
+
+use dbus::arg::{AppendAll, ReadAll};
+use dbus::nonblock::{Proxy, SyncConnection};
+use log::error;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{oneshot, Semaphore};
+
+// Max getAccountDetails calls in flight in get_account_list
+const MAX_CONCURRENT_CALLS: usize = 8;
+
+/**
+ * Async counterpart of JamiClient: one non-blocking connection shared by all calls.
+ * Cheap to clone, clones share the connection. Dropping a call before its reply
+ * is safe, the reply is discarded and the connection stays usable
+ **/
+#[derive(Clone)]
+pub struct AsyncJamiClient {
+    conn: Arc<SyncConnection>,
+    timeout: Duration,
+    config: JamiConfig,
+    // Dropped with the last clone, to stop the resource of the connection
+    _dropped: Arc<oneshot::Sender<()>>,
+}
+
+impl AsyncJamiClient {
+    /**
+     * Connect to the bus (cf Jami::set_bus). Must be called from a tokio runtime
+     * @return the client
+     */
+    pub fn new() -> Result<AsyncJamiClient, JamiError> {
+        AsyncJamiClient::connect_to(&bus::bus())
+    }
+
+    /**
+     * Connect to a given bus, whatever Jami::set_bus says. Must be called from a
+     * tokio runtime
+     * @param bus       e.g. BusType::System for a daemon running as a system service
+     * @return the client
+     */
+    pub fn connect_to(bus: &BusType) -> Result<AsyncJamiClient, JamiError> {
+        let (resource, conn) = bus::connect_async_to(bus).map_err(JamiError::BusConnection)?;
+        // The resource is dropped with the last clone of the client
+        let (dropped_tx, dropped) = oneshot::channel::<()>();
+        tokio::spawn(async {
+            tokio::select! {
+                err = resource => error!("Lost connection to D-Bus: {}", err),
+                _ = dropped => {}
+            }
+        });
+        Ok(AsyncJamiClient {
+            conn,
+            _dropped: Arc::new(dropped_tx),
+            timeout: bus::call_timeout(),
+            config: bus::config(),
+        })
+    }
+
+    /**
+     * Change the timeout of the calls of this client
+     * @param timeout   Jami::call_timeout() by default
+     * @return the client
+     */
+    pub fn with_timeout(mut self, timeout: Duration) -> AsyncJamiClient {
+        self.timeout = timeout;
+        self
+    }
+
+    /**
+     * Change the names of the daemon for this client
+     * @param config    Jami::config() by default
+     * @return the client
+     */
+    pub fn with_config(mut self, config: JamiConfig) -> AsyncJamiClient {
+        self.config = config;
+        self
+    }
+
+    /**
+     * @return the names of the daemon used by this client
+     */
+    pub fn config(&self) -> &JamiConfig {
+        &self.config
+    }
+
+    /**
+     * Call a method of the daemon
+     * @param path          Object, e.g. config().call_manager_path
+     * @param interface     Interface, e.g. config().call_manager_interface
+     * @param method        Method to call
+     * @param args          Arguments, as a tuple
+     * @return the reply, as a tuple
+     */
+    pub async fn call<A: AppendAll + Clone, R: ReadAll + 'static>(
+        &self,
+        path: &str,
+        interface: &str,
+        method: &str,
+        args: A,
+    ) -> Result<R, JamiError> {
+        let proxy = Proxy::new(&self.config.service, path, self.timeout, self.conn.clone());
+        logged_call_async(&proxy, interface, method, args)
+            .await
+            .map_err(|e| JamiError::method_call(method, e))
+    }
+
+    /**
+     * Call a method of the ConfigurationManager
+     * @param method        Method to call
+     * @param args          Arguments, as a tuple
+     * @return the reply, as a tuple
+     */
+    pub async fn configuration<A: AppendAll + Clone, R: ReadAll + 'static>(
+        &self,
+        method: &str,
+        args: A,
+    ) -> Result<R, JamiError> {
+        self.call(
+            &self.config.config_manager_path,
+            &self.config.config_manager_interface,
+            method,
+            args,
+        )
+        .await
+    }
+
+    /**
+     * Call a method of the CallManager
+     * @param method        Method to call
+     * @param args          Arguments, as a tuple
+     * @return the reply, as a tuple
+     */
+    pub async fn call_manager<A: AppendAll + Clone, R: ReadAll + 'static>(
+        &self,
+        method: &str,
+        args: A,
+    ) -> Result<R, JamiError> {
+        self.call(
+            &self.config.call_manager_path,
+            &self.config.call_manager_interface,
+            method,
+            args,
+        )
+        .await
+    }
+
+    /**
+     * Call a method of the VideoManager
+     * @param method        Method to call
+     * @param args          Arguments, as a tuple
+     * @return the reply, as a tuple
+     */
+    pub async fn video_manager<A: AppendAll + Clone, R: ReadAll + 'static>(
+        &self,
+        method: &str,
+        args: A,
+    ) -> Result<R, JamiError> {
+        self.call(
+            &self.config.video_manager_path,
+            &self.config.video_manager_interface,
+            method,
+            args,
+        )
+        .await
+    }
+
+    /**
+     * @return ids of the accounts, in the daemon's order
+     */
+    pub async fn get_account_ids(&self) -> Result<Vec<String>, JamiError> {
+        let (ids,): (Vec<String>,) = self.configuration("getAccountList", ()).await?;
+        Ok(ids)
+    }
+
+    /**
+     * Get an account with its details and volatile details
+     * @param id    Id of the account
+     * @return the account, NotFound if the daemon doesn't know it
+     */
+    pub async fn get_account(&self, id: &str) -> Result<Account, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (details,): (HashMap<String, String>,) =
+            self.configuration("getAccountDetails", (id,)).await?;
+        if details.is_empty() {
+            return Err(JamiError::NotFound(format!("account {}", id)));
+        }
+        let (volatile,): (HashMap<String, String>,) = self
+            .configuration("getVolatileAccountDetails", (id,))
+            .await?;
+        let mut account = Account::new(id);
+        account.update_details(&details);
+        account.update_volatile_details(&volatile);
+        Ok(account)
+    }
+
+    /**
+     * Get all accounts, fetching details concurrently (8 accounts at a time)
+     * @return one result per account, in the daemon's order.
+     *         Err only if the list itself can't be retrieved
+     */
+    pub async fn get_account_list(&self) -> Result<Vec<Result<Account, JamiError>>, JamiError> {
+        let ids = self.get_account_ids().await?;
+        let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_CALLS));
+        let handles: Vec<_> = ids
+            .into_iter()
+            .map(|id| {
+                let client = self.clone();
+                let semaphore = semaphore.clone();
+                tokio::spawn(async move {
+                    let _permit = semaphore.acquire().await;
+                    client.get_account(&id).await
+                })
+            })
+            .collect();
+        let mut accounts = Vec::new();
+        for handle in handles {
+            accounts.push(match handle.await {
+                Ok(account) => account,
+                Err(e) => Err(JamiError::OperationFailed(e.to_string())),
+            });
+        }
+        Ok(accounts)
+    }
+
+    /**
+     * Get account details
+     * @param id    Id of the account
+     * @return the account details, empty if the daemon doesn't know it
+     */
+    pub async fn get_account_details(
+        &self,
+        id: &str,
+    ) -> Result<HashMap<String, String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (details,) = self.configuration("getAccountDetails", (id,)).await?;
+        Ok(details)
+    }
+
+    /**
+     * Set account details
+     * @param id        Id of the account
+     * @param details   All the details of the account
+     */
+    pub async fn set_account_details(
+        &self,
+        id: &str,
+        details: HashMap<String, String>,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        self.configuration("setAccountDetails", (id, details)).await
+    }
+
+    /**
+     * Get volatile account details (registration status, registered name, etc.)
+     * @param id    Id of the account
+     * @return the volatile details
+     */
+    pub async fn get_volatile_account_details(
+        &self,
+        id: &str,
+    ) -> Result<HashMap<String, String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (details,) = self
+            .configuration("getVolatileAccountDetails", (id,))
+            .await?;
+        Ok(details)
+    }
+
+    /**
+     * Lookup a name, the result comes with Event::RegisteredNameFound
+     * @param account
+     * @param name_service
+     * @param name
+     * @return if the daemon started the lookup
+     */
+    pub async fn lookup_name(
+        &self,
+        account: &str,
+        name_service: &str,
+        name: &str,
+    ) -> Result<bool, JamiError> {
+        let (result,) = self
+            .configuration("lookupName", (account, name_service, name))
+            .await?;
+        Ok(result)
+    }
+
+    /**
+     * Lookup an address, the result comes with Event::RegisteredNameFound
+     * @param account
+     * @param name_service
+     * @param address
+     * @return if the daemon started the lookup
+     */
+    pub async fn lookup_address(
+        &self,
+        account: &str,
+        name_service: &str,
+        address: &str,
+    ) -> Result<bool, JamiError> {
+        let (result,) = self
+            .configuration("lookupAddress", (account, name_service, address))
+            .await?;
+        Ok(result)
+    }
+
+    /**
+     * Get contacts of an account
+     * @param id    Id of the account
+     * @return the contacts (id, added, confirmed, etc.)
+     */
+    pub async fn get_contacts(&self, id: &str) -> Result<Vec<HashMap<String, String>>, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (contacts,) = self.configuration("getContacts", (id,)).await?;
+        Ok(contacts)
+    }
+
+    /**
+     * Add a new contact
+     * @param id    Id of the account
+     * @param uri   Uri of the contact
+     */
+    pub async fn add_contact(&self, id: &str, uri: &str) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::peer("uri", uri)?;
+        let uri = uri::normalize_peer(uri);
+        self.configuration("addContact", (id, uri)).await
+    }
+
+    /**
+     * Remove a contact
+     * @param id    Id of the account
+     * @param uri   Uri of the contact
+     * @param ban   Also ban the contact
+     */
+    pub async fn remove_contact(&self, id: &str, uri: &str, ban: bool) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::peer("uri", uri)?;
+        let uri = uri::normalize_peer(uri);
+        self.configuration("removeContact", (id, uri, ban)).await
+    }
+
+    /**
+     * Get trusts requests from an account
+     * @param id    Id of the account
+     * @return the senders of the requests
+     */
+    pub async fn get_trust_requests(&self, id: &str) -> Result<Vec<String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (requests,): (Vec<HashMap<String, String>>,) =
+            self.configuration("getTrustRequests", (id,)).await?;
+        Ok(requests
+            .into_iter()
+            .filter_map(|mut tr| tr.remove("from"))
+            .collect())
+    }
+
+    /**
+     * Send a trust request to someone
+     * @param id        Id of the account
+     * @param to        Contact uri
+     * @param payloads  VCard
+     */
+    pub async fn send_trust_request(
+        &self,
+        id: &str,
+        to: &str,
+        payloads: Vec<u8>,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::peer("uri", to)?;
+        let to = uri::normalize_peer(to);
+        self.configuration("sendTrustRequest", (id, to, payloads))
+            .await
+    }
+
+    /**
+     * Accept a trust request
+     * @param id        Id of the account
+     * @param from      Contact uri
+     * @return if the daemon accepted it
+     */
+    pub async fn accept_trust_request(&self, id: &str, from: &str) -> Result<bool, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::peer("uri", from)?;
+        let (result,) = self.configuration("acceptTrustRequest", (id, from)).await?;
+        Ok(result)
+    }
+
+    /**
+     * Discard a trust request
+     * @param id        Id of the account
+     * @param from      Contact uri
+     * @return if the daemon discarded it
+     */
+    pub async fn discard_trust_request(&self, id: &str, from: &str) -> Result<bool, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::peer("uri", from)?;
+        let (result,) = self
+            .configuration("discardTrustRequest", (id, from))
+            .await?;
+        Ok(result)
+    }
+
+    /**
+     * Get current conversations for account
+     * @param id    Id of the account
+     * @return current conversations
+     */
+    pub async fn get_conversations(&self, id: &str) -> Result<Vec<String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (conversations,) = self.configuration("getConversations", (id,)).await?;
+        Ok(conversations)
+    }
+
+    /**
+     * Start conversation
+     * @param id    Id of the account
+     * @return the id of the conversation
+     */
+    pub async fn start_conversation(&self, id: &str) -> Result<String, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (conv_id,): (String,) = self.configuration("startConversation", (id,)).await?;
+        if conv_id.is_empty() {
+            return Err(JamiError::OperationFailed(String::from(
+                "startConversation",
+            )));
+        }
+        Ok(conv_id)
+    }
+
+    /**
+     * Remove a conversation for an account
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @return if the daemon removed it
+     */
+    pub async fn rm_conversation(&self, id: &str, conv_id: &str) -> Result<bool, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        let (removed,) = self
+            .configuration("removeConversation", (id, conv_id))
+            .await?;
+        Ok(removed)
+    }
+
+    /**
+     * Get current members for a conversation
+     * @param id        Id of the account
+     * @param convid    Id of the conversation
+     * @return current members
+     */
+    pub async fn get_members(
+        &self,
+        id: &str,
+        convid: &str,
+    ) -> Result<Vec<HashMap<String, String>>, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", convid)?;
+        let (members,) = self
+            .configuration("getConversationMembers", (id, convid))
+            .await?;
+        Ok(members)
+    }
+
+    /**
+     * Get conversation's infos
+     * @param id        Id of the account
+     * @param convid    Id of the conversation
+     * @return current infos
+     */
+    pub async fn get_conversation_infos(
+        &self,
+        id: &str,
+        convid: &str,
+    ) -> Result<HashMap<String, String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", convid)?;
+        let (infos,) = self
+            .configuration("conversationInfos", (id, convid))
+            .await?;
+        Ok(infos)
+    }
+
+    /**
+     * Get conversation requests of an account
+     * @param id    Id of the account
+     * @return the requests
+     */
+    pub async fn get_conversations_requests(
+        &self,
+        id: &str,
+    ) -> Result<Vec<HashMap<String, String>>, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (requests,) = self.configuration("getConversationRequests", (id,)).await?;
+        Ok(requests)
+    }
+
+    /**
+     * Decline a conversation request
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     */
+    pub async fn decline_request(&self, id: &str, conv_id: &str) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        self.configuration("declineConversationRequest", (id, conv_id))
+            .await
+    }
+
+    /**
+     * Accept a conversation request
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     */
+    pub async fn accept_request(&self, id: &str, conv_id: &str) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        self.configuration("acceptConversationRequest", (id, conv_id))
+            .await
+    }
+
+    /**
+     * Load a conversation, the messages come with Event::ConversationLoaded
+     * @param account
+     * @param conversation
+     * @param from              "" if latest else the commit id
+     * @param size              0 if all else max number of messages to get
+     * @return the id of the request
+     */
+    pub async fn load_conversation(
+        &self,
+        account: &str,
+        conversation: &str,
+        from: &str,
+        size: u32,
+    ) -> Result<u32, JamiError> {
+        validate::account_id("account_id", account)?;
+        validate::conversation_id("conversation_id", conversation)?;
+        let (request_id,) = self
+            .configuration(
+                "loadConversationMessages",
+                (account, conversation, from, size),
+            )
+            .await?;
+        Ok(request_id)
+    }
+
+    /**
+     * Send a text message to a conversation swarm of an account (cf Jami::try_send_message)
+     * @param account_id    Id of the account
+     * @param conv_id       Id of the conversation
+     * @param message       Message to be sent to conversation swarm
+     * @param reply_to      Commit answered, or empty
+     * @param flag          flag
+     * @return NotFound if the conversation doesn't exist
+     */
+    pub async fn send_message(
+        &self,
+        account_id: &str,
+        conv_id: &str,
+        message: &str,
+        reply_to: &str,
+        flag: i32,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", account_id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        // The daemon ignores messages for unknown conversations, without error
+        let conversations = self.get_conversations(account_id).await?;
+        if !conversations.iter().any(|c| c == conv_id) {
+            return Err(Jami::conversation_not_found(account_id, conv_id));
+        }
+        let proxy = Proxy::new(
+            &self.config.service,
+            &self.config.config_manager_path,
+            self.timeout,
+            self.conn.clone(),
+        );
+        let result: Result<(), _> = logged_call_async(
+            &proxy,
+            &self.config.config_manager_interface,
+            "sendMessage",
+            (account_id, conv_id, message, reply_to, flag),
+        )
+        .await;
+        match result {
+            Ok(()) => Ok(()),
+            // Removed between the two calls, for daemons reporting it
+            Err(e) if Jami::is_not_found_error(&e) => {
+                Err(Jami::conversation_not_found(account_id, conv_id))
+            }
+            Err(e) => Err(JamiError::method_call("sendMessage", e)),
+        }
+    }
+
+    /**
+     * Update the profile of an account, shared with contacts
+     * @param id            Id of the account
+     * @param display_name  New display name
+     * @param avatar        Path of the avatar if flag is 0, base64 content if flag is 1
+     * @param file_type     Type of the avatar (e.g. "png")
+     * @param flag          0: avatar is a path, 1: avatar is base64, 2: remove the avatar
+     */
+    pub async fn update_profile(
+        &self,
+        id: &str,
+        display_name: &str,
+        avatar: &str,
+        file_type: &str,
+        flag: i32,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        self.configuration("updateProfile", (id, display_name, avatar, file_type, flag))
+            .await
+    }
+
+    /**
+     * Register a name on the name server. Result is sent by nameRegistrationEnded
+     * @param id        Id of the account
+     * @param password  Password of the account
+     * @param name      Name to register
+     * @return if the registration started
+     */
+    pub async fn register_name(
+        &self,
+        id: &str,
+        password: &str,
+        name: &str,
+    ) -> Result<bool, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (started,) = self
+            .configuration("registerName", (id, password, name))
+            .await?;
+        Ok(started)
+    }
+
+    /**
+     * Get devices linked to an account
+     * @param id    Id of the account
+     * @return device id -> device name
+     */
+    pub async fn get_known_devices(&self, id: &str) -> Result<HashMap<String, String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (devices,) = self.configuration("getKnownRingDevices", (id,)).await?;
+        Ok(devices)
+    }
+
+    /**
+     * Export an account on the DHT to link a new device. The pin is sent by exportOnRingEnded
+     * @param id        Id of the account
+     * @param password  Password of the account
+     * @return if the export started
+     */
+    pub async fn export_on_ring(&self, id: &str, password: &str) -> Result<bool, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (started,) = self.configuration("exportOnRing", (id, password)).await?;
+        Ok(started)
+    }
+
+    /**
+     * Revoke a device. Result is sent by deviceRevocationEnded
+     * @param id        Id of the account
+     * @param device_id Device to revoke
+     * @param password  Password of the account ("" if none)
+     * @return if the revocation started
+     */
+    pub async fn revoke_device(
+        &self,
+        id: &str,
+        device_id: &str,
+        password: &str,
+    ) -> Result<bool, JamiError> {
+        validate::account_id("account_id", id)?;
+        let scheme = if password.is_empty() { "" } else { "password" };
+        let (started,) = self
+            .configuration("revokeDevice", (id, device_id, scheme, password))
+            .await?;
+        Ok(started)
+    }
+
+    /**
+     * Place an audio call (cf place_call_with_media for other medias)
+     * @param account_id    Id of the account
+     * @param uri           Peer to call (or swarm:/rdv: uri)
+     * @return the id of the call
+     */
+    pub async fn place_call(&self, account_id: &str, uri: &str) -> Result<String, JamiError> {
+        self.place_call_with_media(account_id, uri, call::default_media_list(false))
+            .await
+    }
+
+    /**
+     * Place a call
+     * @param account_id    Id of the account
+     * @param uri           Peer to call (or swarm:/rdv: uri)
+     * @param media_list    Medias to use (cf call::default_media_list)
+     * @return the id of the call, OperationFailed if the daemon refused it
+     */
+    pub async fn place_call_with_media(
+        &self,
+        account_id: &str,
+        uri: &str,
+        media_list: Vec<MediaAttribute>,
+    ) -> Result<String, JamiError> {
+        validate::account_id("account_id", account_id)?;
+        let uri = call::call_uri(uri)?;
+        let (call_id,): (String,) = self
+            .call_manager(
+                "placeCallWithMedia",
+                (account_id, &uri, MediaAttribute::to_maps(&media_list)),
+            )
+            .await?;
+        if call_id.is_empty() {
+            return Err(JamiError::OperationFailed(format!(
+                "unable to call {}",
+                uri
+            )));
+        }
+        Ok(call_id)
+    }
+
+    /**
+     * Hang up a call
+     * @param account_id    Id of the account
+     * @param call_id       Id of the call
+     * @return NotFound if the daemon doesn't know the call
+     */
+    pub async fn hang_up(&self, account_id: &str, call_id: &str) -> Result<(), JamiError> {
+        validate::account_id("account_id", account_id)?;
+        let (hung_up,): (bool,) = self.call_manager("hangUp", (account_id, call_id)).await?;
+        if !hung_up {
+            return Err(JamiError::NotFound(format!("call {}", call_id)));
+        }
+        Ok(())
+    }
+
+    /**
+     * Accept an incoming call
+     * @param account_id    Id of the account
+     * @param call_id       Id of the call
+     * @param media_list    Medias to use
+     * @return if successful
+     */
+    pub async fn accept_with_media(
+        &self,
+        account_id: &str,
+        call_id: &str,
+        media_list: Vec<MediaAttribute>,
+    ) -> Result<bool, JamiError> {
+        validate::account_id("account_id", account_id)?;
+        let (accepted,) = self
+            .call_manager(
+                "acceptWithMedia",
+                (account_id, call_id, MediaAttribute::to_maps(&media_list)),
+            )
+            .await?;
+        Ok(accepted)
+    }
+
+    /**
+     * Change medias of a call (e.g. add a video to an audio call)
+     * @param account_id    Id of the account
+     * @param call_id       Id of the call
+     * @param media_list    New medias
+     * @return if successful
+     */
+    pub async fn request_media_change(
+        &self,
+        account_id: &str,
+        call_id: &str,
+        media_list: Vec<MediaAttribute>,
+    ) -> Result<bool, JamiError> {
+        validate::account_id("account_id", account_id)?;
+        let (requested,) = self
+            .call_manager(
+                "requestMediaChange",
+                (account_id, call_id, MediaAttribute::to_maps(&media_list)),
+            )
+            .await?;
+        Ok(requested)
+    }
+
+    /**
+     * Answer to Event::MediaChangeRequested (e.g. the peer adds a video)
+     * @param account_id    Id of the account
+     * @param call_id       Id of the call
+     * @param media_list    Accepted medias
+     */
+    pub async fn answer_media_change_request(
+        &self,
+        account_id: &str,
+        call_id: &str,
+        media_list: Vec<MediaAttribute>,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", account_id)?;
+        self.call_manager(
+            "answerMediaChangeRequest",
+            (account_id, call_id, MediaAttribute::to_maps(&media_list)),
+        )
+        .await
+    }
+
+    /**
+     * Get the medias of a call
+     * @param account_id    Id of the account
+     * @param call_id       Id of the call
+     * @return the medias, empty if the call doesn't exist
+     */
+    pub async fn get_current_media_list(
+        &self,
+        account_id: &str,
+        call_id: &str,
+    ) -> Result<Vec<MediaAttribute>, JamiError> {
+        validate::account_id("account_id", account_id)?;
+        let (media_list,): (Vec<HashMap<String, String>>,) = self
+            .call_manager("currentMediaList", (account_id, call_id))
+            .await?;
+        Ok(MediaAttribute::from_maps(&media_list))
+    }
+
+    /**
+     * Get calls hosted in a conversation
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @return current calls
+     */
+    pub async fn get_active_calls(
+        &self,
+        id: &str,
+        conv_id: &str,
+    ) -> Result<Vec<CallInfo>, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        let (calls,): (Vec<HashMap<String, String>>,) =
+            self.configuration("getActiveCalls", (id, conv_id)).await?;
+        Ok(calls
+            .iter()
+            .map(|infos| CallInfo::from_map(conv_id, infos))
+            .collect())
+    }
+
+    /**
+     * Get the cameras known by the daemon
+     * @return the devices names
+     */
+    pub async fn get_video_devices(&self) -> Result<Vec<String>, JamiError> {
+        let (devices,) = self.video_manager("getDeviceList", ()).await?;
+        Ok(devices)
+    }
+
+    /**
+     * Change the video source of a call
+     * @param account_id    Id of the account
+     * @param call_id       Id of the call
+     * @param resource      New source, e.g. camera://<device>
+     * @return if successful
+     */
+    pub async fn switch_input(
+        &self,
+        account_id: &str,
+        call_id: &str,
+        resource: &str,
+    ) -> Result<bool, JamiError> {
+        validate::account_id("account_id", account_id)?;
+        let (switched,) = self
+            .call_manager("switchInput", (account_id, call_id, resource))
+            .await?;
+        Ok(switched)
+    }
+
+    /**
+     * Send a file to a conversation swarm of an account (cf Jami::send_file_tracked
+     * for the id of the transfer)
+     * @param account_id        Id of the account
+     * @param conv_id           Id of the conversation
+     * @param file_path         Path of the file to send
+     * @param file_display_name Label attached to file for display
+     * @param reply_to          Commit answered, or empty
+     */
+    pub async fn send_file(
+        &self,
+        account_id: &str,
+        conv_id: &str,
+        file_path: &str,
+        file_display_name: &str,
+        reply_to: &str,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", account_id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        validate::path("path", file_path)?;
+        self.configuration(
+            "sendFile",
+            (account_id, conv_id, file_path, file_display_name, reply_to),
+        )
+        .await
+    }
+
+    /**
+     * Accepts a file transfer, resuming it from a byte offset (0 to start from scratch)
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @param tid       Id of the transfer
+     * @param path      Where to write the file
+     * @param offset    Bytes already received, non-negative
+     * @return the code of the daemon (cf TransferError::from_code)
+     */
+    pub async fn accept_file_transfer(
+        &self,
+        id: &str,
+        conv_id: &str,
+        tid: u64,
+        path: &str,
+        offset: i64,
+    ) -> Result<u32, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        validate::path("path", path)?;
+        validate::offset("offset", offset)?;
+        let (code,) = self
+            .configuration("acceptFileTransfer", (id, conv_id, tid, path, offset))
+            .await?;
+        Ok(code)
+    }
+
+    /**
+     * Cancel a file transfer
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @param tid       Id of the transfer
+     * @return the code of the daemon (cf TransferError::from_code)
+     */
+    pub async fn cancel_file_transfer(
+        &self,
+        id: &str,
+        conv_id: &str,
+        tid: u64,
+    ) -> Result<u32, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        let (code,) = self
+            .configuration("cancelDataTransfer", (id, conv_id, tid))
+            .await?;
+        Ok(code)
+    }
+
+    /**
+     * Get informations about a transfer
+     * @param account_id    Id of the account
+     * @param conv_id       Id of the conversation
+     * @param tid           Id of the transfer
+     * @return the informations, TransferError::Unknown for a stale id
+     */
+    pub async fn data_transfer_info(
+        &self,
+        account_id: &str,
+        conv_id: &str,
+        tid: u64,
+    ) -> Result<DataTransferInfo, TransferError> {
+        validate::account_id("account_id", account_id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        let info = DataTransferInfo::default().tuple();
+        let (code, info) = self
+            .configuration("dataTransferInfo", (account_id, conv_id, tid, info))
+            .await?;
+        match TransferError::from_code(code) {
+            None => Ok(DataTransferInfo::from_tuple(info)),
+            Some(error) => Err(error),
+        }
+    }
+}
@@ -0,0 +1,477 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use dbus::blocking::Connection;
+use dbus::channel::Channel;
+use dbus::nonblock::{NonblockReply, Process, SyncConnection};
+use dbus_tokio::connection;
+use mio::unix::EventedFd;
+use mio::{Evented, Poll, PollOpt, Ready, Token};
+use std::cell::Cell;
+use std::error::Error;
+use std::future::Future;
+use std::io;
+use std::os::unix::io::RawFd;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{self, Context, Waker};
+use std::time::{Duration, Instant};
+use tokio::io::PollEvented;
+
+/**
+ * Bus where the daemon is, cf Jami::set_bus
+ **/
+#[derive(Clone, Debug, PartialEq)]
+pub enum BusType {
+    // Session bus of the user (DBUS_SESSION_BUS_ADDRESS)
+    Session,
+    // System bus
+    System,
+    // Any bus, e.g. unix:path=/run/jami/bus or tcp:host=127.0.0.1,port=4000
+    Address(String),
+}
+
+/**
+ * Names of the daemon on the bus, cf Jami::set_config. The default names are the
+ * ones of jamid (cx.ring.Ring), JamiConfig::named gives the names of a renamed daemon
+ * (e.g. net.jami.daemon)
+ **/
+#[derive(Clone, Debug, PartialEq)]
+pub struct JamiConfig {
+    // Well-known name of the daemon
+    pub service: String,
+    pub config_manager_path: String,
+    pub config_manager_interface: String,
+    pub call_manager_path: String,
+    pub call_manager_interface: String,
+    pub presence_manager_path: String,
+    pub presence_manager_interface: String,
+    pub video_manager_path: String,
+    pub video_manager_interface: String,
+}
+
+impl JamiConfig {
+    /**
+     * Names of a daemon with the same objects as jamid
+     * @param service       Name of the daemon and prefix of its interfaces, e.g. cx.ring.Ring
+     * @param path_prefix   Prefix of its objects, e.g. /cx/ring/Ring
+     * @return the config
+     */
+    pub fn named(service: &str, path_prefix: &str) -> JamiConfig {
+        JamiConfig {
+            service: service.to_string(),
+            config_manager_path: format!("{}/ConfigurationManager", path_prefix),
+            config_manager_interface: format!("{}.ConfigurationManager", service),
+            call_manager_path: format!("{}/CallManager", path_prefix),
+            call_manager_interface: format!("{}.CallManager", service),
+            presence_manager_path: format!("{}/PresenceManager", path_prefix),
+            presence_manager_interface: format!("{}.PresenceManager", service),
+            video_manager_path: format!("{}/VideoManager", path_prefix),
+            video_manager_interface: format!("{}.VideoManager", service),
+        }
+    }
+}
+
+impl Default for JamiConfig {
+    fn default() -> Self {
+        JamiConfig::named("cx.ring.Ring", "/cx/ring/Ring")
+    }
+}
+
+/**
+ * Names under which the daemon runs, cf Jami::daemon_flavor
+ **/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DaemonFlavor {
+    // cx.ring.Ring on /cx/ring/Ring
+    CxRing,
+    // net.jami.daemon on /net/jami/daemon, for newer builds
+    NetJami,
+    // Other names, given to Jami::set_config
+    Custom,
+}
+
+// Probed in this order (service, prefix of the objects)
+const FLAVORS: &[(DaemonFlavor, &str, &str)] = &[
+    (DaemonFlavor::CxRing, "cx.ring.Ring", "/cx/ring/Ring"),
+    (DaemonFlavor::NetJami, "net.jami.daemon", "/net/jami/daemon"),
+];
+
+impl DaemonFlavor {
+    /**
+     * @param config    Names of a daemon
+     * @return the flavor using these names
+     */
+    pub fn of(config: &JamiConfig) -> DaemonFlavor {
+        FLAVORS
+            .iter()
+            .find(|(_, service, path)| *config == JamiConfig::named(service, path))
+            .map(|(flavor, _, _)| *flavor)
+            .unwrap_or(DaemonFlavor::Custom)
+    }
+}
+
+/**
+ * Resolves when the connection of connect_async is lost
+ */
+pub(crate) type BusResource = Pin<Box<dyn Future<Output = Box<dyn Error + Send + Sync>> + Send>>;
+
+static BUS: Mutex<BusType> = Mutex::new(BusType::Session);
+// None to detect the names of the daemon
+static CONFIG: Mutex<Option<JamiConfig>> = Mutex::new(None);
+// Names found on a bus, kept until reset_config
+static DETECTED: Mutex<Option<(BusType, JamiConfig)>> = Mutex::new(None);
+// Last bus where no daemon was found, not probed again before MISS_DELAY
+static MISSED: Mutex<Option<(BusType, Instant)>> = Mutex::new(None);
+const MISS_DELAY: Duration = Duration::from_secs(1);
+// Max time to wait for NameHasOwner when detecting the daemon
+const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+static CALL_TIMEOUT: Mutex<Duration> = Mutex::new(Duration::from_millis(5000));
+thread_local! {
+    // Set by with_timeout, for the calls of this thread only
+    static SCOPED_TIMEOUT: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
+/**
+ * Change the bus used by new connections
+ * @param bus   The bus
+ */
+pub fn set_bus(bus: BusType) {
+    *BUS.lock().unwrap() = bus;
+}
+
+/**
+ * @return the bus used by new connections
+ */
+pub fn bus() -> BusType {
+    BUS.lock().unwrap().clone()
+}
+
+/**
+ * Change the names of the daemon used by new connections and event loops
+ * @param config    The names
+ */
+pub fn set_config(config: JamiConfig) {
+    *CONFIG.lock().unwrap() = Some(config);
+}
+
+/**
+ * Forget the names given to set_config or detected, to detect them again
+ */
+pub fn reset_config() {
+    *CONFIG.lock().unwrap() = None;
+    *DETECTED.lock().unwrap() = None;
+    *MISSED.lock().unwrap() = None;
+}
+
+/**
+ * @return the names of the daemon: the ones of set_config, else the ones
+ * owned on the bus (cf FLAVORS), else JamiConfig::default()
+ */
+pub fn config() -> JamiConfig {
+    if let Some(config) = CONFIG.lock().unwrap().clone() {
+        return config;
+    }
+    let bus = bus();
+    if let Some((detected_bus, config)) = &*DETECTED.lock().unwrap() {
+        if *detected_bus == bus {
+            return config.clone();
+        }
+    }
+    if let Some((missed_bus, at)) = &*MISSED.lock().unwrap() {
+        if *missed_bus == bus && at.elapsed() < MISS_DELAY {
+            return JamiConfig::default();
+        }
+    }
+    match detect(&bus) {
+        Some(config) => {
+            *DETECTED.lock().unwrap() = Some((bus, config.clone()));
+            config
+        }
+        // The daemon isn't running yet, probed again after MISS_DELAY
+        None => {
+            *MISSED.lock().unwrap() = Some((bus, Instant::now()));
+            JamiConfig::default()
+        }
+    }
+}
+
+/**
+ * Name of the daemon, without probing the bus (e.g. for error messages)
+ * @return the name given to set_config, else the detected one, else the default one
+ */
+pub fn service_name() -> String {
+    if let Some(config) = &*CONFIG.lock().unwrap() {
+        return config.service.clone();
+    }
+    let bus = bus();
+    match &*DETECTED.lock().unwrap() {
+        Some((detected_bus, config)) if *detected_bus == bus => config.service.clone(),
+        _ => JamiConfig::default().service,
+    }
+}
+
+/**
+ * Find which names of the daemon are owned on a bus
+ * @param bus   The bus
+ * @return the names of the running daemon
+ */
+fn detect(bus: &BusType) -> Option<JamiConfig> {
+    let conn = connect_to(bus).ok()?;
+    let proxy = conn.with_proxy(
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        PROBE_TIMEOUT,
+    );
+    FLAVORS
+        .iter()
+        .find(|(_, service, _)| {
+            let owned: Result<(bool,), _> =
+                proxy.method_call("org.freedesktop.DBus", "NameHasOwner", (*service,));
+            owned.map(|(owned,)| owned).unwrap_or(false)
+        })
+        .map(|(_, service, path)| JamiConfig::named(service, path))
+}
+
+/**
+ * Change the timeout of the calls to the daemon
+ * @param timeout   5s by default
+ */
+pub fn set_call_timeout(timeout: Duration) {
+    *CALL_TIMEOUT.lock().unwrap() = timeout;
+}
+
+/**
+ * @return the timeout of the calls to the daemon made by this thread
+ */
+pub fn call_timeout() -> Duration {
+    SCOPED_TIMEOUT
+        .with(|scoped| scoped.get())
+        .unwrap_or_else(|| *CALL_TIMEOUT.lock().unwrap())
+}
+
+/**
+ * Run f with another timeout for the calls to the daemon of this thread
+ * @param timeout   Timeout of the calls
+ * @param f         Calls to make
+ * @return the result of f
+ */
+pub fn with_timeout<R, F: FnOnce() -> R>(timeout: Duration, f: F) -> R {
+    // Restore the previous timeout, even if f panics
+    struct Restore(Option<Duration>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0;
+            SCOPED_TIMEOUT.with(|scoped| scoped.set(previous));
+        }
+    }
+    let _restore = Restore(SCOPED_TIMEOUT.with(|scoped| scoped.replace(Some(timeout))));
+    f()
+}
+
+/**
+ * Open a blocking connection to the bus
+ * @return the connection
+ */
+pub(crate) fn connect() -> Result<Connection, dbus::Error> {
+    connect_to(&bus())
+}
+
+/**
+ * Open a blocking connection to a given bus
+ * @param bus   The bus
+ * @return the connection
+ */
+pub(crate) fn connect_to(bus: &BusType) -> Result<Connection, dbus::Error> {
+    match bus {
+        BusType::Session => Connection::new_session(),
+        BusType::System => Connection::new_system(),
+        BusType::Address(address) => Ok(Connection::from(open(address)?)),
+    }
+}
+
+/**
+ * Open a non-blocking connection to the bus. Must be called from a tokio runtime
+ * @return the future to poll for the connection to work, and the connection
+ */
+pub(crate) fn connect_async() -> Result<(BusResource, Arc<SyncConnection>), dbus::Error> {
+    connect_async_to(&bus())
+}
+
+/**
+ * Open a non-blocking connection to a given bus. Must be called from a tokio runtime
+ * @param bus   The bus
+ * @return the future to poll for the connection to work, and the connection
+ */
+pub(crate) fn connect_async_to(
+    bus: &BusType,
+) -> Result<(BusResource, Arc<SyncConnection>), dbus::Error> {
+    match bus {
+        BusType::Session => {
+            let (resource, conn) = connection::new_session_sync()?;
+            Ok((lost(resource), conn))
+        }
+        BusType::System => {
+            let (resource, conn) = connection::new_system_sync()?;
+            Ok((lost(resource), conn))
+        }
+        BusType::Address(address) => {
+            // dbus-tokio only connects to the session and system buses, so the socket
+            // is watched here, in the runtime, like dbus-tokio does. Nothing runs once
+            // the resource is dropped
+            let mut channel = open(address)?;
+            channel.set_watch_enabled(true);
+            let socket = BusSocket(channel.watch().fd);
+            let mut conn = SyncConnection::from(channel);
+            conn.set_timeout_maker(Some(make_timeout));
+            // Messages that can't be sent at once are flushed by the resource
+            let waker: Arc<Mutex<Option<Waker>>> = Arc::default();
+            let wake = waker.clone();
+            conn.set_waker(Some(Box::new(move || {
+                if let Some(waker) = &*wake.lock().unwrap() {
+                    waker.wake_by_ref();
+                }
+                Ok(())
+            })));
+            let conn = Arc::new(conn);
+            let socket =
+                PollEvented::new(socket).map_err(|e| dbus::Error::new_failed(&e.to_string()))?;
+            let resource = AddressResource {
+                socket,
+                conn: conn.clone(),
+                waker,
+            };
+            Ok((Box::pin(resource), conn))
+        }
+    }
+}
+
+// Socket of an Address connection, owned (and closed) by libdbus
+struct BusSocket(RawFd);
+
+impl BusSocket {
+    // Whether bytes wait to be read (or the socket is closed), without reading them
+    fn readable(&self) -> io::Result<bool> {
+        let mut byte = 0u8;
+        let flags = libc::MSG_PEEK | libc::MSG_DONTWAIT;
+        // Safe: the fd is open while the connection lives, one byte is peeked in byte
+        let read =
+            unsafe { libc::recv(self.0, &mut byte as *mut u8 as *mut libc::c_void, 1, flags) };
+        if read >= 0 {
+            return Ok(true);
+        }
+        match io::Error::last_os_error() {
+            e if e.kind() == io::ErrorKind::WouldBlock => Ok(false),
+            e => Err(e),
+        }
+    }
+}
+
+impl Evented for BusSocket {
+    fn register(
+        &self,
+        poll: &Poll,
+        token: Token,
+        interest: Ready,
+        opts: PollOpt,
+    ) -> io::Result<()> {
+        EventedFd(&self.0).register(poll, token, interest, opts)
+    }
+
+    fn reregister(
+        &self,
+        poll: &Poll,
+        token: Token,
+        interest: Ready,
+        opts: PollOpt,
+    ) -> io::Result<()> {
+        EventedFd(&self.0).reregister(poll, token, interest, opts)
+    }
+
+    fn deregister(&self, poll: &Poll) -> io::Result<()> {
+        EventedFd(&self.0).deregister(poll)
+    }
+}
+
+// Resource of an Address connection: reads the socket when the runtime says it is
+// readable, flushes what is sent and dispatches the messages. Declared before the
+// connection, to stop watching the socket before libdbus closes it
+struct AddressResource {
+    socket: PollEvented<BusSocket>,
+    conn: Arc<SyncConnection>,
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+
+impl Future for AddressResource {
+    type Output = Box<dyn Error + Send + Sync>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> task::Poll<Self::Output> {
+        *self.waker.lock().unwrap() = Some(cx.waker().clone());
+        let channel: &Channel = (*self.conn).as_ref();
+        if let task::Poll::Ready(Err(e)) = self.socket.poll_read_ready(cx, Ready::readable()) {
+            return task::Poll::Ready(Box::new(e));
+        }
+        // libdbus reads a few KB at a time, so until the socket is empty
+        loop {
+            if channel.read_write(Some(Duration::default())).is_err() {
+                return task::Poll::Ready(Box::new(dbus::Error::new_failed("Disconnected")));
+            }
+            self.conn.process_all();
+            match self.socket.get_ref().readable() {
+                Ok(true) => continue,
+                Ok(false) => break,
+                Err(e) => return task::Poll::Ready(Box::new(e)),
+            }
+        }
+        if let Err(e) = self.socket.clear_read_ready(cx, Ready::readable()) {
+            return task::Poll::Ready(Box::new(e));
+        }
+        if channel.has_messages_to_send() {
+            // The socket is full, try again on the next turn of the runtime
+            cx.waker().wake_by_ref();
+        }
+        task::Poll::Pending
+    }
+}
+
+fn make_timeout(timeout: Instant) -> Pin<Box<dyn Future<Output = ()> + Send + Sync + 'static>> {
+    Box::pin(tokio::time::delay_until(timeout.into()))
+}
+
+fn lost<F: Future + Send + 'static>(resource: F) -> BusResource
+where
+    F::Output: std::fmt::Display,
+{
+    Box::pin(async move { Box::<dyn Error + Send + Sync>::from(resource.await.to_string()) })
+}
+
+// Authentication is done by libdbus: EXTERNAL on unix sockets, DBUS_COOKIE_SHA1
+// (shared ~/.dbus-keyrings) or ANONYMOUS on tcp, depending on the server
+fn open(address: &str) -> Result<Channel, dbus::Error> {
+    let mut channel = Channel::open_private(address)?;
+    channel.register()?;
+    Ok(channel)
+}
@@ -0,0 +1,223 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use super::error::JamiError;
+use super::uri::JamiUri;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/**
+ * Represent a call hosted in a swarm, just here to store informations.
+ **/
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct CallInfo {
+    // Id of the conference
+    pub id: String,
+    // Uri of the host
+    pub uri: String,
+    // Device of the host
+    pub device: String,
+    pub conversation_id: String,
+}
+
+impl CallInfo {
+    /**
+     * Build from an item of getActiveCalls
+     * @param conv_id   Related conversation
+     * @param infos     Item returned by the daemon
+     */
+    pub fn from_map(conv_id: &str, infos: &HashMap<String, String>) -> CallInfo {
+        CallInfo {
+            id: infos.get("id").cloned().unwrap_or_default(),
+            uri: infos.get("uri").cloned().unwrap_or_default(),
+            device: infos.get("device").cloned().unwrap_or_default(),
+            conversation_id: conv_id.to_string(),
+        }
+    }
+
+    /**
+     * Get calls still active from the history of a conversation.
+     * A hosted call posts an "application/call-history+json" commit with a confId
+     * and posts another one with a duration when it ends.
+     * @param conv_id   Related conversation
+     * @param messages  Messages (cf Event::ConversationLoaded), in any order
+     * @return calls started and not ended
+     */
+    pub fn from_messages(conv_id: &str, messages: &[HashMap<String, String>]) -> Vec<CallInfo> {
+        let mut started: Vec<CallInfo> = Vec::new();
+        let mut ended: Vec<String> = Vec::new();
+        for message in messages {
+            if message.get("type").map(|t| &**t) != Some("application/call-history+json") {
+                continue;
+            }
+            let conf_id = match message.get("confId") {
+                Some(conf_id) if !conf_id.is_empty() => conf_id.clone(),
+                _ => continue,
+            };
+            if message.contains_key("duration") {
+                ended.push(conf_id);
+            } else if !started.iter().any(|c| c.id == conf_id) {
+                started.push(CallInfo {
+                    id: conf_id,
+                    uri: message.get("uri").cloned().unwrap_or_default(),
+                    device: message.get("device").cloned().unwrap_or_default(),
+                    conversation_id: conv_id.to_string(),
+                });
+            }
+        }
+        started.retain(|c| !ended.contains(&c.id));
+        started
+    }
+
+    /**
+     * Get the uri to use to join this call
+     * @return rdv:uri/device/conversation/id
+     */
+    pub fn join_uri(&self) -> String {
+        format!(
+            "rdv:{}/{}/{}/{}",
+            self.conversation_id, self.uri, self.device, self.id
+        )
+    }
+}
+
+/**
+ * Type of a media in a call
+ **/
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum MediaType {
+    Audio,
+    Video,
+    Other(String),
+}
+
+/**
+ * A media of a call, used to place/answer calls and sent by media events
+ **/
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct MediaAttribute {
+    pub media_type: MediaType,
+    pub enabled: bool,
+    pub muted: bool,
+    // "" for the default device, else e.g. camera://video0
+    pub source: String,
+    pub label: String,
+}
+
+impl MediaAttribute {
+    pub fn audio() -> Self {
+        Self {
+            media_type: MediaType::Audio,
+            enabled: true,
+            muted: false,
+            source: String::new(),
+            label: String::from("audio_0"),
+        }
+    }
+
+    pub fn video() -> Self {
+        Self {
+            media_type: MediaType::Video,
+            enabled: true,
+            muted: false,
+            source: String::new(),
+            label: String::from("video_0"),
+        }
+    }
+
+    /**
+     * Build from a media sent by the daemon
+     * @param infos     Media map
+     */
+    pub fn from_map(infos: &HashMap<String, String>) -> Self {
+        let media_type = match infos.get("MEDIA_TYPE").map(|t| &**t) {
+            Some("MEDIA_TYPE_AUDIO") => MediaType::Audio,
+            Some("MEDIA_TYPE_VIDEO") => MediaType::Video,
+            other => MediaType::Other(other.unwrap_or_default().to_string()),
+        };
+        Self {
+            media_type,
+            enabled: infos.get("ENABLED").map(|v| v == "true").unwrap_or(false),
+            muted: infos.get("MUTED").map(|v| v == "true").unwrap_or(false),
+            source: infos.get("SOURCE").cloned().unwrap_or_default(),
+            label: infos.get("LABEL").cloned().unwrap_or_default(),
+        }
+    }
+
+    /**
+     * Convert to the map expected by the daemon
+     */
+    pub fn to_map(&self) -> HashMap<String, String> {
+        let media_type = match &self.media_type {
+            MediaType::Audio => "MEDIA_TYPE_AUDIO",
+            MediaType::Video => "MEDIA_TYPE_VIDEO",
+            MediaType::Other(other) => other,
+        };
+        let mut infos = HashMap::new();
+        infos.insert(String::from("MEDIA_TYPE"), media_type.to_string());
+        infos.insert(String::from("ENABLED"), self.enabled.to_string());
+        infos.insert(String::from("MUTED"), self.muted.to_string());
+        infos.insert(String::from("SOURCE"), self.source.clone());
+        infos.insert(String::from("LABEL"), self.label.clone());
+        infos
+    }
+
+    pub fn from_maps(media_list: &[HashMap<String, String>]) -> Vec<Self> {
+        media_list.iter().map(MediaAttribute::from_map).collect()
+    }
+
+    pub fn to_maps(media_list: &[MediaAttribute]) -> Vec<HashMap<String, String>> {
+        media_list.iter().map(MediaAttribute::to_map).collect()
+    }
+}
+
+/**
+ * Build the media list used to place a call
+ * @param with_video    Add the default camera
+ * @return the media list
+ */
+pub fn default_media_list(with_video: bool) -> Vec<MediaAttribute> {
+    let mut media_list = vec![MediaAttribute::audio()];
+    if with_video {
+        media_list.push(MediaAttribute::video());
+    }
+    media_list
+}
+
+/**
+ * Check the peer of a call and add the jami: scheme to hashes and names
+ * @param uri   Peer (jami:, ring: or no scheme) or swarm:/rdv: uri
+ * @return the uri to give to placeCallWithMedia, InvalidArgument if invalid
+ */
+pub(crate) fn call_uri(uri: &str) -> Result<String, JamiError> {
+    if uri.starts_with("swarm:") || uri.starts_with("rdv:") {
+        return Ok(uri.to_string());
+    }
+    JamiUri::parse(uri)
+        .map(|uri| uri.with_scheme())
+        .map_err(|e| JamiError::InvalidArgument(String::from("uri"), e.to_string()))
+}
@@ -0,0 +1,436 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use dbus::arg::{AppendAll, ReadAll};
+use dbus::blocking::{self, BlockingSender};
+use dbus::nonblock::{self, NonblockReply};
+use std::ops::Deref;
+#[cfg(feature = "call-log")]
+use {
+    super::Event,
+    dbus::arg::messageitem::MessageItem,
+    dbus::strings::{Interface, Member},
+    dbus::Message,
+    log::debug,
+    std::collections::{HashMap, VecDeque},
+    std::fmt::Debug,
+    std::sync::atomic::{AtomicBool, Ordering},
+    std::sync::Mutex,
+    std::time::{Duration, Instant, SystemTime},
+    tokio::sync::mpsc,
+};
+
+// Records kept by the ring buffer
+#[cfg(feature = "call-log")]
+const CALL_LOG_SIZE: usize = 512;
+
+#[cfg(feature = "call-log")]
+static CALL_LOG: Mutex<VecDeque<CallRecord>> = Mutex::new(VecDeque::new());
+#[cfg(feature = "call-log")]
+static MIRROR_TO_LOG: AtomicBool = AtomicBool::new(false);
+
+// Arguments never stored, by method (message bodies, passwords, avatars, vCards)
+#[cfg(feature = "call-log")]
+const SENSITIVE_ARGS: &[(&str, &[usize])] = &[
+    ("sendMessage", &[2]),
+    ("sendTextMessage", &[2]),
+    ("sendTrustRequest", &[2]),
+    ("updateProfile", &[2]),
+    ("registerName", &[1]),
+    ("exportOnRing", &[1]),
+    ("exportToFile", &[3]),
+    ("revokeDevice", &[3]),
+];
+
+#[cfg(feature = "call-log")]
+const REDACTED: &str = "<redacted>";
+
+/**
+ * What a CallRecord is about
+ **/
+#[cfg(feature = "call-log")]
+#[derive(Clone, Debug, PartialEq)]
+pub enum CallKind {
+    MethodCall,
+    // Signal, as decoded by the event loop
+    Signal,
+}
+
+/**
+ * A call to the daemon or a signal, with redacted arguments (cf Jami::recent_calls)
+ **/
+#[cfg(feature = "call-log")]
+#[derive(Clone, Debug)]
+pub struct CallRecord {
+    pub kind: CallKind,
+    pub time: SystemTime,
+    // Empty for signals
+    pub interface: String,
+    // Method, or variant of Event for signals
+    pub member: String,
+    pub args: String,
+    // None for signals
+    pub duration: Option<Duration>,
+    pub error: Option<String>,
+}
+
+/**
+ * Blocking and non-blocking proxies calls recorded by the call-log feature.
+ * Without the feature, same as method_call
+ **/
+pub(crate) trait LoggedCall {
+    fn logged_call<R: ReadAll, A: AppendAll>(
+        &self,
+        interface: &str,
+        member: &str,
+        args: A,
+    ) -> Result<R, dbus::Error>;
+}
+
+impl<'a, T: BlockingSender, C: Deref<Target = T>> LoggedCall for blocking::Proxy<'a, C> {
+    #[cfg(not(feature = "call-log"))]
+    fn logged_call<R: ReadAll, A: AppendAll>(
+        &self,
+        interface: &str,
+        member: &str,
+        args: A,
+    ) -> Result<R, dbus::Error> {
+        self.method_call(interface, member, args)
+    }
+
+    #[cfg(feature = "call-log")]
+    fn logged_call<R: ReadAll, A: AppendAll>(
+        &self,
+        interface: &str,
+        member: &str,
+        args: A,
+    ) -> Result<R, dbus::Error> {
+        let mut msg = Message::method_call(
+            &self.destination,
+            &self.path,
+            &Interface::from(interface),
+            &Member::from(member),
+        );
+        msg.append_all(args);
+        let description = describe_call(member, &msg);
+        let start = Instant::now();
+        let result = self
+            .connection
+            .send_with_reply_and_block(msg, self.timeout)
+            .and_then(|reply| Ok(R::read(&mut reply.iter_init())?));
+        record_call(
+            interface,
+            member,
+            description,
+            start.elapsed(),
+            result.as_ref().err(),
+        );
+        result
+    }
+}
+
+/**
+ * Same as nonblock::Proxy::method_call, recorded by the call-log feature
+ */
+pub(crate) async fn logged_call_async<'a, T, C, R, A>(
+    proxy: &nonblock::Proxy<'a, C>,
+    interface: &str,
+    member: &str,
+    args: A,
+) -> Result<R, dbus::Error>
+where
+    T: NonblockReply + 'static,
+    C: Deref<Target = T>,
+    R: ReadAll + 'static,
+    A: AppendAll + Clone,
+{
+    #[cfg(feature = "call-log")]
+    {
+        let mut msg = Message::method_call(
+            &proxy.destination,
+            &proxy.path,
+            &Interface::from(interface),
+            &Member::from(member),
+        );
+        msg.append_all(args.clone());
+        let description = describe_call(member, &msg);
+        let start = Instant::now();
+        let result = proxy.method_call(interface, member, args).await;
+        record_call(
+            interface,
+            member,
+            description,
+            start.elapsed(),
+            result.as_ref().err(),
+        );
+        result
+    }
+    #[cfg(not(feature = "call-log"))]
+    proxy.method_call(interface, member, args).await
+}
+
+/**
+ * @return the last calls and signals, oldest first
+ */
+#[cfg(feature = "call-log")]
+pub fn recent_calls() -> Vec<CallRecord> {
+    CALL_LOG.lock().unwrap().iter().cloned().collect()
+}
+
+/**
+ * Also write the records with log::debug!
+ * @param mirror    If records are logged
+ */
+#[cfg(feature = "call-log")]
+pub fn set_mirror_to_log(mirror: bool) {
+    MIRROR_TO_LOG.store(mirror, Ordering::Relaxed);
+}
+
+/**
+ * Record the events sent by the event loop as signals
+ * @param tx    Where the event loop sends its events
+ * @return the sender to give to the event loop
+ */
+#[cfg(feature = "call-log")]
+pub(crate) fn record_events<T: 'static + Debug + Send>(
+    mut tx: mpsc::Sender<Event<T>>,
+) -> mpsc::Sender<Event<T>> {
+    let (recorded_tx, mut rx) = mpsc::channel(32);
+    tokio::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            let description = describe_event(&event);
+            let member = description
+                .split(&['(', ' ', '{'][..])
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            record(CallRecord {
+                kind: CallKind::Signal,
+                time: SystemTime::now(),
+                interface: String::new(),
+                member,
+                args: description,
+                duration: None,
+                error: None,
+            });
+            if tx.send(event).await.is_err() {
+                break;
+            }
+        }
+    });
+    recorded_tx
+}
+
+#[cfg(feature = "call-log")]
+fn record_call(
+    interface: &str,
+    member: &str,
+    args: String,
+    duration: Duration,
+    error: Option<&dbus::Error>,
+) {
+    record(CallRecord {
+        kind: CallKind::MethodCall,
+        time: SystemTime::now(),
+        interface: interface.to_string(),
+        member: member.to_string(),
+        args,
+        duration: Some(duration),
+        error: error.map(|e| e.to_string()),
+    });
+}
+
+#[cfg(feature = "call-log")]
+fn record(record: CallRecord) {
+    if MIRROR_TO_LOG.load(Ordering::Relaxed) {
+        debug!(
+            "{:?} {}.{}{} {:?} {:?}",
+            record.kind,
+            record.interface,
+            record.member,
+            record.args,
+            record.duration,
+            record.error
+        );
+    }
+    let mut log = CALL_LOG.lock().unwrap();
+    if log.len() == CALL_LOG_SIZE {
+        log.pop_front();
+    }
+    log.push_back(record);
+}
+
+#[cfg(feature = "call-log")]
+fn describe_call(member: &str, msg: &Message) -> String {
+    let sensitive = SENSITIVE_ARGS
+        .iter()
+        .find(|(method, _)| *method == member)
+        .map(|(_, args)| *args)
+        .unwrap_or(&[]);
+    let args: Vec<String> = msg
+        .get_items()
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| {
+            if sensitive.contains(&idx) {
+                REDACTED.to_string()
+            } else {
+                describe_item(item)
+            }
+        })
+        .collect();
+    format!("({})", args.join(", "))
+}
+
+#[cfg(feature = "call-log")]
+fn describe_item(item: &MessageItem) -> String {
+    match item {
+        MessageItem::Str(s) => format!("{:?}", redact_vcard(s)),
+        MessageItem::Array(items) => {
+            if items
+                .iter()
+                .all(|item| matches!(item, MessageItem::Byte(_)))
+                && !items.is_empty()
+            {
+                return format!("<{} bytes>", items.len());
+            }
+            let items: Vec<String> = items.iter().map(describe_item).collect();
+            format!("[{}]", items.join(", "))
+        }
+        MessageItem::Dict(entries) => {
+            let entries: Vec<String> = entries
+                .iter()
+                .map(|(key, value)| {
+                    let value = match key {
+                        MessageItem::Str(key) if is_sensitive_key(key) => REDACTED.to_string(),
+                        _ => describe_item(value),
+                    };
+                    format!("{}: {}", describe_item(key), value)
+                })
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+        MessageItem::Struct(items) => {
+            let items: Vec<String> = items.iter().map(describe_item).collect();
+            format!("({})", items.join(", "))
+        }
+        MessageItem::Variant(item) => describe_item(item),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(feature = "call-log")]
+fn describe_event<T: Debug>(event: &Event<T>) -> String {
+    match event {
+        Event::Message {
+            account_id,
+            conversation_id,
+            payloads,
+        } => format!(
+            "Message {{ account_id: {:?}, conversation_id: {:?}, payloads: {:?} }}",
+            account_id,
+            conversation_id,
+            redact_map(payloads)
+        ),
+        Event::AccountMessage {
+            account_id,
+            message_id,
+            from,
+            payloads,
+        } => format!(
+            "AccountMessage {{ account_id: {:?}, message_id: {:?}, from: {:?}, payloads: {:?} }}",
+            account_id,
+            message_id,
+            from,
+            payloads.keys().collect::<Vec<_>>()
+        ),
+        Event::ConversationLoaded(id, account_id, conversation_id, messages) => format!(
+            "ConversationLoaded({}, {:?}, {:?}, {:?})",
+            id,
+            account_id,
+            conversation_id,
+            messages.iter().map(redact_map).collect::<Vec<_>>()
+        ),
+        Event::IncomingContactRequest {
+            account_id,
+            from,
+            conversation_id,
+            payload,
+            received,
+            ..
+        } => format!(
+            "IncomingContactRequest {{ account_id: {:?}, from: {:?}, conversation_id: {:?}, payload: <{} bytes>, received: {} }}",
+            account_id,
+            from,
+            conversation_id,
+            payload.len(),
+            received
+        ),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(feature = "call-log")]
+fn is_sensitive_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    key.contains("password") || key.ends_with("pin") || key == "body"
+}
+
+#[cfg(feature = "call-log")]
+fn redact_map(map: &HashMap<String, String>) -> HashMap<String, String> {
+    map.iter()
+        .map(|(key, value)| {
+            let value = if is_sensitive_key(key) {
+                REDACTED.to_string()
+            } else {
+                redact_vcard(value)
+            };
+            (key.clone(), value)
+        })
+        .collect()
+}
+
+// Drop the photo of a vCard
+#[cfg(feature = "call-log")]
+fn redact_vcard(value: &str) -> String {
+    if !value.contains("BEGIN:VCARD") {
+        return value.to_string();
+    }
+    let mut in_photo = false;
+    let mut lines = Vec::new();
+    for line in value.lines() {
+        if in_photo && line.starts_with(' ') {
+            continue;
+        }
+        in_photo = line.starts_with("PHOTO");
+        if in_photo {
+            lines.push(format!("PHOTO:{}", REDACTED));
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+    lines.join("\n")
+}
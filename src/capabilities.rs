@@ -0,0 +1,106 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use serde::{Deserialize, Serialize};
+
+/**
+ * First daemon version supporting each feature. Can be changed for forks
+ * not following upstream versions
+ **/
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct CapabilityThresholds {
+    pub swarm: (u32, u32, u32),
+    pub group_calls: (u32, u32, u32),
+    pub edits: (u32, u32, u32),
+    pub reactions: (u32, u32, u32),
+}
+
+impl Default for CapabilityThresholds {
+    fn default() -> Self {
+        Self {
+            swarm: (10, 0, 0),
+            group_calls: (13, 0, 0),
+            edits: (13, 7, 0),
+            reactions: (13, 9, 0),
+        }
+    }
+}
+
+/**
+ * Features supported by the daemon (cf Jami::capabilities)
+ **/
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Capabilities {
+    // As sent by the daemon ("" if unknown)
+    pub version: String,
+    pub swarm: bool,
+    // Calls hosted in swarms
+    pub group_calls: bool,
+    pub edits: bool,
+    pub reactions: bool,
+}
+
+impl Capabilities {
+    /**
+     * @param version       Version of the daemon, e.g. 13.7.0
+     * @param thresholds    First version of each feature
+     * @return the features supported, none if the version can't be parsed
+     */
+    pub fn from_version(version: &str, thresholds: &CapabilityThresholds) -> Capabilities {
+        let parsed = match parse_version(version) {
+            Some(parsed) => parsed,
+            None => {
+                return Capabilities {
+                    version: version.to_string(),
+                    ..Default::default()
+                }
+            }
+        };
+        Capabilities {
+            version: version.to_string(),
+            swarm: parsed >= thresholds.swarm,
+            group_calls: parsed >= thresholds.group_calls,
+            edits: parsed >= thresholds.edits,
+            reactions: parsed >= thresholds.reactions,
+        }
+    }
+}
+
+/**
+ * Parse the start of a version (13.7.0, 13.7, 13.7.0-rc1...)
+ * @param version   Version to parse
+ * @return (major, minor, patch)
+ */
+pub fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
+    let mut numbers = version.trim().split('.').map(|part| {
+        let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+        digits.parse::<u32>().ok()
+    });
+    let major = numbers.next()??;
+    let minor = numbers.next().flatten().unwrap_or(0);
+    let patch = numbers.next().flatten().unwrap_or(0);
+    Some((major, minor, patch))
+}
@@ -0,0 +1,591 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use super::account::Account;
+use super::bus::{self, BusType, JamiConfig};
+use super::call::{self, MediaAttribute};
+use super::calllog::LoggedCall;
+use super::error::JamiError;
+use super::uri;
+use super::validate;
+
+use dbus::arg::{AppendAll, ReadAll};
+use dbus::blocking::Connection;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Client used by Jami's functions, opened on the first call (cf JamiClient::shared)
+static SHARED_CLIENT: Mutex<Option<(BusType, JamiClient)>> = Mutex::new(None);
+
+/**
+ * Connection to the daemon shared by several calls. Cheap to clone, clones share
+ * the connection
+ **/
+#[derive(Clone)]
+pub struct JamiClient {
+    conn: Arc<Mutex<Connection>>,
+    timeout: Duration,
+    config: JamiConfig,
+}
+
+impl JamiClient {
+    /**
+     * Connect to the bus (cf Jami::set_bus)
+     * @return the client
+     */
+    pub fn new() -> Result<JamiClient, JamiError> {
+        let conn = bus::connect().map_err(JamiError::BusConnection)?;
+        Ok(JamiClient::from_connection(conn))
+    }
+
+    /**
+     * Connect to a given bus, whatever Jami::set_bus says
+     * @param bus       e.g. BusType::System for a daemon running as a system service
+     * @return the client
+     */
+    pub fn connect_to(bus: &BusType) -> Result<JamiClient, JamiError> {
+        let conn = bus::connect_to(bus).map_err(JamiError::BusConnection)?;
+        Ok(JamiClient::from_connection(conn))
+    }
+
+    /**
+     * Use an existing connection
+     * @param conn      Connection to a bus where cx.ring.Ring is available
+     * @return the client
+     */
+    pub fn from_connection(conn: Connection) -> JamiClient {
+        JamiClient {
+            conn: Arc::new(Mutex::new(conn)),
+            timeout: bus::call_timeout(),
+            config: bus::config(),
+        }
+    }
+
+    /**
+     * Get the client used by Jami's functions. It is opened on the first call, and
+     * again if it was disconnected or if the bus changed (cf Jami::set_bus)
+     * @return the client, with the timeout of this thread (cf Jami::with_timeout) and
+     *         the names of Jami::set_config
+     */
+    pub(crate) fn shared() -> Result<JamiClient, JamiError> {
+        let mut shared = SHARED_CLIENT.lock().unwrap_or_else(|e| e.into_inner());
+        let current = bus::bus();
+        let reusable = match &*shared {
+            Some((bus, client)) => *bus == current && client.is_connected(),
+            None => false,
+        };
+        if !reusable {
+            *shared = Some((current.clone(), JamiClient::new()?));
+        }
+        let (_, client) = shared.as_ref().unwrap();
+        Ok(client
+            .clone()
+            .with_timeout(bus::call_timeout())
+            .with_config(bus::config()))
+    }
+
+    /**
+     * @return if the connection to the bus is still open
+     */
+    pub fn is_connected(&self) -> bool {
+        self.conn.lock().unwrap().channel().is_connected()
+    }
+
+    /**
+     * Change the timeout of the calls of this client
+     * @param timeout   Jami::call_timeout() by default
+     * @return the client
+     */
+    pub fn with_timeout(mut self, timeout: Duration) -> JamiClient {
+        self.timeout = timeout;
+        self
+    }
+
+    /**
+     * Change the names of the daemon for this client
+     * @param config    Jami::config() by default
+     * @return the client
+     */
+    pub fn with_config(mut self, config: JamiConfig) -> JamiClient {
+        self.config = config;
+        self
+    }
+
+    /**
+     * @return the names of the daemon used by this client
+     */
+    pub fn config(&self) -> &JamiConfig {
+        &self.config
+    }
+
+    /**
+     * Call a method of the daemon
+     * @param path          Object, e.g. config().call_manager_path
+     * @param interface     Interface, e.g. config().call_manager_interface
+     * @param method        Method to call
+     * @param args          Arguments, as a tuple
+     * @return the reply, as a tuple
+     */
+    pub fn call<A: AppendAll, R: ReadAll>(
+        &self,
+        path: &str,
+        interface: &str,
+        method: &str,
+        args: A,
+    ) -> Result<R, JamiError> {
+        let conn = self.conn.lock().unwrap();
+        let proxy = conn.with_proxy(&self.config.service, path, self.timeout);
+        proxy
+            .logged_call(interface, method, args)
+            .map_err(|e| JamiError::method_call(method, e))
+    }
+
+    /**
+     * Call a method of the ConfigurationManager
+     * @param method        Method to call
+     * @param args          Arguments, as a tuple
+     * @return the reply, as a tuple
+     */
+    pub fn configuration<A: AppendAll, R: ReadAll>(
+        &self,
+        method: &str,
+        args: A,
+    ) -> Result<R, JamiError> {
+        self.call(
+            &self.config.config_manager_path,
+            &self.config.config_manager_interface,
+            method,
+            args,
+        )
+    }
+
+    /**
+     * Call a method of the CallManager
+     * @param method        Method to call
+     * @param args          Arguments, as a tuple
+     * @return the reply, as a tuple
+     */
+    pub fn call_manager<A: AppendAll, R: ReadAll>(
+        &self,
+        method: &str,
+        args: A,
+    ) -> Result<R, JamiError> {
+        self.call(
+            &self.config.call_manager_path,
+            &self.config.call_manager_interface,
+            method,
+            args,
+        )
+    }
+
+    /**
+     * Call a method of the PresenceManager
+     * @param method        Method to call
+     * @param args          Arguments, as a tuple
+     * @return the reply, as a tuple
+     */
+    pub fn presence_manager<A: AppendAll, R: ReadAll>(
+        &self,
+        method: &str,
+        args: A,
+    ) -> Result<R, JamiError> {
+        self.call(
+            &self.config.presence_manager_path,
+            &self.config.presence_manager_interface,
+            method,
+            args,
+        )
+    }
+
+    /**
+     * Call a method of the VideoManager
+     * @param method        Method to call
+     * @param args          Arguments, as a tuple
+     * @return the reply, as a tuple
+     */
+    pub fn video_manager<A: AppendAll, R: ReadAll>(
+        &self,
+        method: &str,
+        args: A,
+    ) -> Result<R, JamiError> {
+        self.call(
+            &self.config.video_manager_path,
+            &self.config.video_manager_interface,
+            method,
+            args,
+        )
+    }
+}
+
+/**
+ * Handle returned by Jami::connect
+ **/
+pub type JamiHandle = JamiClient;
+
+/**
+ * The account, contact, trust request, conversation and call functions of Jami,
+ * on the connection of the client. Other daemon methods go through call(),
+ * configuration() and call_manager()
+ **/
+impl JamiClient {
+    /**
+     * Get current ring accounts
+     * @return current accounts
+     */
+    pub fn try_get_account_list(&self) -> Result<Vec<Account>, JamiError> {
+        let (accounts,): (Vec<String>,) = self.configuration("getAccountList", ())?;
+        let mut account_list: Vec<Account> = Vec::new();
+        for account in accounts {
+            // Removed meanwhile
+            if let Some(account) = self.try_get_account(&account)? {
+                account_list.push(account);
+            }
+        }
+        Ok(account_list)
+    }
+
+    /**
+     * Build a new account with an id from the daemon
+     * @param id the account id to build
+     * @return the account retrieven (None if the daemon doesn't know it)
+     */
+    pub fn try_get_account(&self, id: &str) -> Result<Option<Account>, JamiError> {
+        let (details,): (HashMap<String, String>,) =
+            self.configuration("getAccountDetails", (id,))?;
+        if details.is_empty() {
+            return Ok(None);
+        }
+
+        let mut account = Account::new(id);
+        account.update_details(&details);
+        // registeredName is often only in volatile details
+        account.update_volatile_details(&self.try_get_volatile_account_details(id)?);
+        Ok(Some(account))
+    }
+
+    /**
+     * Get account details
+     * @param id the account id
+     * @return the account details
+     */
+    pub fn try_get_account_details(&self, id: &str) -> Result<HashMap<String, String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (details,): (HashMap<String, String>,) =
+            self.configuration("getAccountDetails", (id,))?;
+        Ok(details)
+    }
+
+    /**
+     * Set account details
+     * @param id        the account id
+     * @param details   all the details of the account
+     */
+    pub fn try_set_account_details(
+        &self,
+        id: &str,
+        details: HashMap<String, String>,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        self.configuration("setAccountDetails", (id, details))
+    }
+
+    /**
+     * Get volatile account details
+     * @param id the account id
+     * @return the volatile details
+     */
+    pub fn try_get_volatile_account_details(
+        &self,
+        id: &str,
+    ) -> Result<HashMap<String, String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (details,): (HashMap<String, String>,) =
+            self.configuration("getVolatileAccountDetails", (id,))?;
+        Ok(details)
+    }
+
+    /**
+     * Asynchronously lookup a name. Result is sent by registeredNameFound
+     * @param account
+     * @param name_service
+     * @param name
+     * @return if the lookup started
+     */
+    pub fn try_lookup_name(
+        &self,
+        account: &str,
+        name_service: &str,
+        name: &str,
+    ) -> Result<bool, JamiError> {
+        let (started,): (bool,) =
+            self.configuration("lookupName", (account, name_service, name))?;
+        Ok(started)
+    }
+
+    /**
+     * Asynchronously lookup an address. Result is sent by registeredNameFound
+     * @param account
+     * @param name_service
+     * @param address
+     * @return if the lookup started
+     */
+    pub fn try_lookup_address(
+        &self,
+        account: &str,
+        name_service: &str,
+        address: &str,
+    ) -> Result<bool, JamiError> {
+        let (started,): (bool,) =
+            self.configuration("lookupAddress", (account, name_service, address))?;
+        Ok(started)
+    }
+
+    /**
+     * Get contacts of an account
+     * @param id        Account id
+     * @return the contacts (id, added, confirmed, etc.)
+     */
+    pub fn try_get_contacts(&self, id: &str) -> Result<Vec<HashMap<String, String>>, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (contacts,): (Vec<HashMap<String, String>>,) =
+            self.configuration("getContacts", (id,))?;
+        Ok(contacts)
+    }
+
+    /**
+     * Add a new contact
+     * @param id        Account id
+     * @param uri       Uri of the contact
+     */
+    pub fn try_add_contact(&self, id: &str, uri: &str) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::peer("uri", uri)?;
+        let uri = uri::normalize_peer(uri);
+        self.configuration("addContact", (id, uri))
+    }
+
+    /**
+     * Remove a contact
+     * @param id        Account id
+     * @param uri       Uri of the contact
+     * @param ban       Also ban the contact
+     */
+    pub fn try_remove_contact(&self, id: &str, uri: &str, ban: bool) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::peer("uri", uri)?;
+        let uri = uri::normalize_peer(uri);
+        self.configuration("removeContact", (id, uri, ban))
+    }
+
+    /**
+     * Get trusts requests from an account
+     * @param id        Account id
+     * @return the list of trusts requests senders
+     */
+    pub fn try_get_trust_requests(&self, id: &str) -> Result<Vec<String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (requests,): (Vec<HashMap<String, String>>,) =
+            self.configuration("getTrustRequests", (id,))?;
+        Ok(requests
+            .into_iter()
+            .filter_map(|mut tr| tr.remove("from"))
+            .collect())
+    }
+
+    /**
+     * Send a trust request to someone
+     * @param id        Account id
+     * @param to        Contact uri
+     * @param payloads  VCard
+     */
+    pub fn try_send_trust_request(
+        &self,
+        id: &str,
+        to: &str,
+        payloads: Vec<u8>,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::peer("uri", to)?;
+        let to = uri::normalize_peer(to);
+        self.configuration("sendTrustRequest", (id, to, payloads))
+    }
+
+    /**
+     * Accept a trust request
+     * @param id        Account id
+     * @param from      Contact uri
+     * @return if the daemon accepted it
+     */
+    pub fn try_accept_trust_request(&self, id: &str, from: &str) -> Result<bool, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::peer("uri", from)?;
+        let (result,): (bool,) = self.configuration("acceptTrustRequest", (id, from))?;
+        Ok(result)
+    }
+
+    /**
+     * Discard a trust request
+     * @param id        Account id
+     * @param from      Contact uri
+     * @return if the daemon discarded it
+     */
+    pub fn try_discard_trust_request(&self, id: &str, from: &str) -> Result<bool, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::peer("uri", from)?;
+        let (result,): (bool,) = self.configuration("discardTrustRequest", (id, from))?;
+        Ok(result)
+    }
+
+    /**
+     * Get current conversations for account
+     * @param id        Id of the account
+     * @return current conversations
+     */
+    pub fn try_get_conversations(&self, id: &str) -> Result<Vec<String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (conversations,): (Vec<String>,) = self.configuration("getConversations", (id,))?;
+        Ok(conversations)
+    }
+
+    /**
+     * Start conversation
+     * @param id        Id of the account
+     * @return the id of the conversation
+     */
+    pub fn try_start_conversation(&self, id: &str) -> Result<String, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (conv_id,): (String,) = self.configuration("startConversation", (id,))?;
+        if conv_id.is_empty() {
+            return Err(JamiError::OperationFailed(String::from(
+                "startConversation",
+            )));
+        }
+        Ok(conv_id)
+    }
+
+    /**
+     * Remove a conversation for an account
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @return if the daemon removed it
+     */
+    pub fn try_rm_conversation(&self, id: &str, conv_id: &str) -> Result<bool, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        let (removed,): (bool,) = self.configuration("removeConversation", (id, conv_id))?;
+        Ok(removed)
+    }
+
+    /**
+     * Get current members for a conversation
+     * @param id        Id of the account
+     * @param convid    Id of the conversation
+     * @return current members
+     */
+    pub fn try_get_members(
+        &self,
+        id: &str,
+        convid: &str,
+    ) -> Result<Vec<HashMap<String, String>>, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", convid)?;
+        let (members,): (Vec<HashMap<String, String>>,) =
+            self.configuration("getConversationMembers", (id, convid))?;
+        Ok(members)
+    }
+
+    /**
+     * Get conversation's infos
+     * @param id        Id of the account
+     * @param convid    Id of the conversation
+     * @return current infos
+     */
+    pub fn try_get_conversation_infos(
+        &self,
+        id: &str,
+        convid: &str,
+    ) -> Result<HashMap<String, String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", convid)?;
+        let (infos,): (HashMap<String, String>,) =
+            self.configuration("conversationInfos", (id, convid))?;
+        Ok(infos)
+    }
+
+    /**
+     * Place an audio call (cf place_call_with_media for other medias)
+     * @param account_id    Id of the account
+     * @param uri           Peer to call (or swarm:/rdv: uri)
+     * @return the id of the call
+     */
+    pub fn place_call(&self, account_id: &str, uri: &str) -> Result<String, JamiError> {
+        self.place_call_with_media(account_id, uri, call::default_media_list(false))
+    }
+
+    /**
+     * Place a call
+     * @param account_id    Id of the account
+     * @param uri           Peer to call (or swarm:/rdv: uri)
+     * @param media_list    Medias to use (cf call::default_media_list)
+     * @return the id of the call, OperationFailed if the daemon refused it
+     */
+    pub fn place_call_with_media(
+        &self,
+        account_id: &str,
+        uri: &str,
+        media_list: Vec<MediaAttribute>,
+    ) -> Result<String, JamiError> {
+        validate::account_id("account_id", account_id)?;
+        let uri = call::call_uri(uri)?;
+        let (call_id,): (String,) = self.call_manager(
+            "placeCallWithMedia",
+            (account_id, &uri, MediaAttribute::to_maps(&media_list)),
+        )?;
+        if call_id.is_empty() {
+            return Err(JamiError::OperationFailed(format!(
+                "unable to call {}",
+                uri
+            )));
+        }
+        Ok(call_id)
+    }
+
+    /**
+     * Hang up a call
+     * @param account_id    Id of the account
+     * @param call_id       Id of the call
+     * @return NotFound if the daemon doesn't know the call
+     */
+    pub fn hang_up(&self, account_id: &str, call_id: &str) -> Result<(), JamiError> {
+        validate::account_id("account_id", account_id)?;
+        let (hung_up,): (bool,) = self.call_manager("hangUp", (account_id, call_id))?;
+        if !hung_up {
+            return Err(JamiError::NotFound(format!("call {}", call_id)));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,794 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use super::bus;
+use super::client::JamiClient;
+use super::error::JamiError;
+use super::{Event, Jami};
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/**
+ * Delivery status of a message (cf Event::TextMessageStatusChanged)
+ **/
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum MessageStatus {
+    Unknown,
+    // Queued by the daemon until the peer is reachable
+    Sending,
+    Sent,
+    Displayed,
+    // Can be sent again
+    Failure,
+    Cancelled,
+}
+
+impl From<i32> for MessageStatus {
+    fn from(status: i32) -> Self {
+        match status {
+            1 => MessageStatus::Sending,
+            2 => MessageStatus::Sent,
+            3 => MessageStatus::Displayed,
+            4 => MessageStatus::Failure,
+            5 => MessageStatus::Cancelled,
+            _ => MessageStatus::Unknown,
+        }
+    }
+}
+
+impl MessageStatus {
+    /**
+     * @return if the message waits for the peer
+     */
+    pub fn is_queued(&self) -> bool {
+        *self == MessageStatus::Sending
+    }
+
+    /**
+     * @return if the message reached the peer
+     */
+    pub fn is_sent(&self) -> bool {
+        *self == MessageStatus::Sent || *self == MessageStatus::Displayed
+    }
+
+    /**
+     * @return if the message should be sent again
+     */
+    pub fn is_failed(&self) -> bool {
+        *self == MessageStatus::Failure
+    }
+}
+
+/**
+ * Who can join a conversation ("mode" of the conversation infos)
+ **/
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ConversationMode {
+    // Created with a contact, cf Jami::add_contact
+    OneToOne,
+    // Only admins can invite
+    AdminInvitesOnly,
+    // Any member can invite (default of startConversation)
+    InvitesOnly,
+    // Anyone can join
+    Public,
+}
+
+impl From<i32> for ConversationMode {
+    fn from(mode: i32) -> Self {
+        match mode {
+            0 => ConversationMode::OneToOne,
+            1 => ConversationMode::AdminInvitesOnly,
+            3 => ConversationMode::Public,
+            _ => ConversationMode::InvitesOnly,
+        }
+    }
+}
+
+impl ConversationMode {
+    /**
+     * @return the value used by the daemon
+     */
+    pub fn value(&self) -> i32 {
+        match self {
+            ConversationMode::OneToOne => 0,
+            ConversationMode::AdminInvitesOnly => 1,
+            ConversationMode::InvitesOnly => 2,
+            ConversationMode::Public => 3,
+        }
+    }
+
+    /**
+     * Get the mode from the infos of a conversation
+     * @param infos     cf Jami::get_conversation_infos
+     * @return the mode, None if unknown
+     */
+    pub fn from_infos(infos: &HashMap<String, String>) -> Option<ConversationMode> {
+        infos
+            .get("mode")
+            .and_then(|mode| mode.parse::<i32>().ok())
+            .map(ConversationMode::from)
+    }
+}
+
+/**
+ * Outcome of Jami::broadcast_message
+ **/
+#[derive(Debug, Default)]
+pub struct BroadcastResult {
+    // Per conversation, in the order given, without duplicates: id of the commit or error
+    pub outcomes: Vec<(String, Result<String, JamiError>)>,
+    // Conversations which got the message
+    pub succeeded: usize,
+}
+
+impl BroadcastResult {
+    /**
+     * @return if every conversation got the message
+     */
+    pub fn all_succeeded(&self) -> bool {
+        self.succeeded == self.outcomes.len()
+    }
+}
+
+/**
+ * Location shared by a peer (application/geo payload)
+ **/
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeoLocation {
+    pub lat: f64,
+    pub lon: f64,
+    // Milliseconds since epoch
+    pub time: u64,
+    // Live sharing: positions are sent again until stopped or until the duration ends.
+    // None for a one-shot position
+    pub duration: Option<Duration>,
+    // End of a live sharing (lat/lon are 0)
+    pub stop: bool,
+}
+
+impl GeoLocation {
+    /**
+     * Parse the JSON sent as application/geo
+     * @param json  e.g. {"type":"Position","lat":1.0,"long":2.0,"time":123}
+     * @return the location, None if invalid
+     */
+    pub fn from_json(json: &str) -> Option<GeoLocation> {
+        let value: serde_json::Value = serde_json::from_str(json).ok()?;
+        let time = value["time"].as_u64().unwrap_or(0);
+        match value["type"].as_str()? {
+            "Stop" => Some(GeoLocation {
+                lat: 0.,
+                lon: 0.,
+                time,
+                duration: None,
+                stop: true,
+            }),
+            "Position" => Some(GeoLocation {
+                lat: value["lat"].as_f64()?,
+                lon: value["long"].as_f64()?,
+                time,
+                duration: value["duration"].as_u64().map(Duration::from_secs),
+                stop: false,
+            }),
+            _ => None,
+        }
+    }
+
+    /**
+     * @return the JSON to send as application/geo
+     */
+    pub fn to_json(&self) -> String {
+        let mut value = if self.stop {
+            serde_json::json!({ "type": "Stop" })
+        } else {
+            serde_json::json!({ "type": "Position", "lat": self.lat, "long": self.lon })
+        };
+        value["time"] = self.time.into();
+        if let Some(duration) = self.duration {
+            value["duration"] = duration.as_secs().into();
+        }
+        value.to_string()
+    }
+
+    /**
+     * @return if more positions will follow
+     */
+    pub fn is_live(&self) -> bool {
+        self.duration.is_some() && !self.stop
+    }
+}
+
+/**
+ * Reaction to a message (a commit with a react-to field)
+ **/
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Reaction {
+    // Id of the commit of the reaction
+    pub id: String,
+    pub author: String,
+    pub body: String,
+}
+
+/**
+ * A message of a conversation, with edits, deletion and reactions applied
+ **/
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ConversationMessage {
+    pub id: String,
+    pub author: String,
+    // type of the commit (text/plain, member, application/data-transfer+json, ...)
+    pub kind: String,
+    pub body: String,
+    pub timestamp: u64,
+    // Id of the message this one answers (reply-to)
+    #[serde(default)]
+    pub reply_to: Option<String>,
+    // If the body was replaced by an application/edited-message
+    pub edited: bool,
+    // If the message was deleted (edited with an empty body)
+    pub deleted: bool,
+    pub reactions: Vec<Reaction>,
+    // peer -> status (cf Event::MessageStatusChanged)
+    pub status: HashMap<String, i32>,
+    // Shared location (application/geo)
+    #[serde(skip)]
+    pub geo: Option<GeoLocation>,
+    // Commit as sent by the daemon
+    pub payloads: HashMap<String, String>,
+}
+
+impl ConversationMessage {
+    /**
+     * Build from a commit sent by the daemon
+     * @param payloads  Commit (Event::Message or ConversationLoaded)
+     */
+    pub fn from_payloads(payloads: &HashMap<String, String>) -> ConversationMessage {
+        ConversationMessage {
+            id: payloads.get("id").cloned().unwrap_or_default(),
+            author: payloads.get("author").cloned().unwrap_or_default(),
+            kind: payloads.get("type").cloned().unwrap_or_default(),
+            body: payloads.get("body").cloned().unwrap_or_default(),
+            timestamp: payloads
+                .get("timestamp")
+                .and_then(|t| t.parse().ok())
+                .unwrap_or(0),
+            geo: payloads
+                .get("application/geo")
+                .and_then(|json| GeoLocation::from_json(json)),
+            reply_to: payloads
+                .get("reply-to")
+                .filter(|id| !id.is_empty())
+                .cloned(),
+            payloads: payloads.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/**
+ * In-memory state of one conversation, maintained from the events of the daemon.
+ * Edits and reactions are applied to their target, even if the target arrives
+ * later (when older messages are loaded)
+ **/
+#[derive(Clone, Debug, Default)]
+pub struct ConversationState {
+    pub account_id: String,
+    pub conversation_id: String,
+    // Ordered by timestamp, oldest first
+    messages: Vec<ConversationMessage>,
+    // member uri -> last event (cf Event::ConversationMemberEvent)
+    members: HashMap<String, i32>,
+    // Commits already applied
+    seen: HashSet<String>,
+    // reaction id -> id of the message it reacts to
+    reactions: HashMap<String, String>,
+    // target id -> edits and reactions waiting for their target
+    pending: HashMap<String, Vec<HashMap<String, String>>>,
+    // message id -> statuses received before the message
+    pending_status: HashMap<String, HashMap<String, i32>>,
+    // message id -> timestamp of the edit applied, as pages can arrive out of order
+    last_edit: HashMap<String, u64>,
+}
+
+impl ConversationState {
+    /**
+     * @param account_id        Account of the conversation
+     * @param conversation_id   Conversation to follow
+     */
+    pub fn new(account_id: &str, conversation_id: &str) -> ConversationState {
+        ConversationState {
+            account_id: account_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /**
+     * Update the state from an event. Events for other conversations are ignored
+     * @param event     Event from the event loop
+     * @return if the state changed
+     */
+    pub fn apply<I>(&mut self, event: &Event<I>) -> bool {
+        match event {
+            Event::Message {
+                account_id,
+                conversation_id,
+                payloads,
+            } => {
+                if !self.is_for(account_id, conversation_id) {
+                    return false;
+                }
+                self.apply_commit(payloads)
+            }
+            Event::ConversationLoaded(_, account_id, conversation_id, messages) => {
+                if !self.is_for(account_id, conversation_id) {
+                    return false;
+                }
+                // The daemon sends the newest first
+                let mut changed = false;
+                for payloads in messages.iter().rev() {
+                    changed |= self.apply_commit(payloads);
+                }
+                changed
+            }
+            Event::ConversationMemberEvent {
+                account_id,
+                conversation_id,
+                member_uri,
+                event,
+            } => {
+                if !self.is_for(account_id, conversation_id) {
+                    return false;
+                }
+                self.members.insert(member_uri.clone(), *event) != Some(*event)
+            }
+            Event::MessageStatusChanged {
+                account_id,
+                conversation_id,
+                peer,
+                message_id,
+                status,
+            } => {
+                if !self.is_for(account_id, conversation_id) {
+                    return false;
+                }
+                match self.messages.iter_mut().find(|m| &m.id == message_id) {
+                    Some(message) => message.status.insert(peer.clone(), *status) != Some(*status),
+                    None => {
+                        self.pending_status
+                            .entry(message_id.clone())
+                            .or_default()
+                            .insert(peer.clone(), *status);
+                        false
+                    }
+                }
+            }
+            Event::ConversationRemoved(account_id, conversation_id) => {
+                if !self.is_for(account_id, conversation_id) {
+                    return false;
+                }
+                *self = ConversationState::new(&self.account_id, &self.conversation_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /**
+     * @return messages, oldest first. Edits and reactions are not listed
+     */
+    pub fn messages(&self) -> &[ConversationMessage] {
+        &self.messages
+    }
+
+    /**
+     * @return uris of the members that didn't leave and are not banned
+     */
+    pub fn members(&self) -> Vec<String> {
+        let mut members: Vec<String> = self
+            .members
+            .iter()
+            .filter(|(_, event)| **event == 0 || **event == 1)
+            .map(|(uri, _)| uri.clone())
+            .collect();
+        members.sort();
+        members
+    }
+
+    fn is_for(&self, account_id: &str, conversation_id: &str) -> bool {
+        self.account_id == account_id && self.conversation_id == conversation_id
+    }
+
+    /**
+     * Apply a commit: new message, edit, deletion or reaction
+     * @param payloads  The commit
+     * @return if the state changed
+     */
+    fn apply_commit(&mut self, payloads: &HashMap<String, String>) -> bool {
+        let id = payloads.get("id").cloned().unwrap_or_default();
+        if id.is_empty() || !self.seen.insert(id.clone()) {
+            return false;
+        }
+        let target = if payloads.get("type").map(|t| &**t) == Some("application/edited-message") {
+            payloads.get("edit")
+        } else {
+            payloads.get("react-to")
+        };
+        if let Some(target) = target {
+            return self.apply_to_target(target.clone(), payloads);
+        }
+
+        let mut message = ConversationMessage::from_payloads(payloads);
+        if let Some(status) = self.pending_status.remove(&id) {
+            message.status = status;
+        }
+        // Keep the order of arrival for equal timestamps
+        let index = self
+            .messages
+            .iter()
+            .position(|m| m.timestamp > message.timestamp)
+            .unwrap_or(self.messages.len());
+        self.messages.insert(index, message);
+        for pending in self.pending.remove(&id).unwrap_or_default() {
+            self.apply_to_target(id.clone(), &pending);
+        }
+        true
+    }
+
+    /**
+     * Apply an edit or a reaction to its target, or keep it until the target is loaded
+     * @param target    Id of the edited message, or of the reaction to remove
+     * @param payloads  The edit or the reaction
+     * @return if the state changed
+     */
+    fn apply_to_target(&mut self, target: String, payloads: &HashMap<String, String>) -> bool {
+        let body = payloads.get("body").cloned().unwrap_or_default();
+        let is_reaction = payloads.contains_key("react-to");
+
+        // Editing a reaction with an empty body removes it
+        if !is_reaction {
+            if let Some(message_id) = self.reactions.get(&target).cloned() {
+                if let Some(message) = self.messages.iter_mut().find(|m| m.id == message_id) {
+                    if body.is_empty() {
+                        message.reactions.retain(|r| r.id != target);
+                        self.reactions.remove(&target);
+                    } else if let Some(reaction) =
+                        message.reactions.iter_mut().find(|r| r.id == target)
+                    {
+                        reaction.body = body;
+                    }
+                    return true;
+                }
+            }
+        }
+
+        let message = match self.messages.iter_mut().find(|m| m.id == target) {
+            Some(message) => message,
+            None => {
+                self.pending
+                    .entry(target)
+                    .or_default()
+                    .push(payloads.clone());
+                return false;
+            }
+        };
+        if is_reaction {
+            let id = payloads.get("id").cloned().unwrap_or_default();
+            message.reactions.push(Reaction {
+                id: id.clone(),
+                author: payloads.get("author").cloned().unwrap_or_default(),
+                body,
+            });
+            self.reactions.insert(id.clone(), target);
+            // The reaction may have been removed before being applied
+            for pending in self.pending.remove(&id).unwrap_or_default() {
+                self.apply_to_target(id.clone(), &pending);
+            }
+        } else if body.is_empty() {
+            message.deleted = true;
+            message.body.clear();
+            message.reactions.clear();
+        } else {
+            let timestamp = payloads
+                .get("timestamp")
+                .and_then(|t| t.parse().ok())
+                .unwrap_or(0);
+            let last_edit = self.last_edit.entry(target).or_insert(0);
+            if message.deleted || timestamp < *last_edit {
+                return false;
+            }
+            *last_edit = timestamp;
+            message.body = body;
+            message.edited = true;
+        }
+        true
+    }
+}
+
+/**
+ * Handle on a conversation of an account, to avoid passing (account id, conversation id)
+ * pairs around. Infos and members are cached until refresh()
+ **/
+pub struct Conversation {
+    client: JamiClient,
+    account_id: String,
+    conversation_id: String,
+    infos: Mutex<Option<HashMap<String, String>>>,
+    members: Mutex<Option<Vec<HashMap<String, String>>>>,
+}
+
+impl Conversation {
+    /**
+     * @param client            Connection to the daemon
+     * @param account_id        Account of the conversation
+     * @param conversation_id   Id of the conversation
+     */
+    pub fn new(client: JamiClient, account_id: &str, conversation_id: &str) -> Conversation {
+        Conversation {
+            client,
+            account_id: account_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            infos: Mutex::new(None),
+            members: Mutex::new(None),
+        }
+    }
+
+    pub fn account_id(&self) -> &str {
+        &self.account_id
+    }
+
+    pub fn id(&self) -> &str {
+        &self.conversation_id
+    }
+
+    /**
+     * Send a text message
+     * @param body      Text to send
+     */
+    pub fn send(&self, body: &str) -> Result<(), JamiError> {
+        self.client.configuration(
+            "sendMessage",
+            (&self.account_id, &self.conversation_id, body, "", 0i32),
+        )
+    }
+
+    /**
+     * Send a file
+     * @param path      Path of the file, the file name is used as display name
+     */
+    pub fn send_file(&self, path: &str) -> Result<(), JamiError> {
+        let name = std::path::Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.client.configuration(
+            "sendFile",
+            (&self.account_id, &self.conversation_id, path, name, ""),
+        )
+    }
+
+    /**
+     * Get the members (uri, role, lastDisplayed). Cached until refresh()
+     */
+    pub fn members(&self) -> Result<Vec<HashMap<String, String>>, JamiError> {
+        let mut members = self.members.lock().unwrap();
+        if let Some(members) = &*members {
+            return Ok(members.clone());
+        }
+        let (result,): (Vec<HashMap<String, String>>,) = self.client.configuration(
+            "getConversationMembers",
+            (&self.account_id, &self.conversation_id),
+        )?;
+        *members = Some(result.clone());
+        Ok(result)
+    }
+
+    /**
+     * Get the infos (title, description, avatar, mode...). Cached until refresh()
+     */
+    pub fn info(&self) -> Result<HashMap<String, String>, JamiError> {
+        let mut infos = self.infos.lock().unwrap();
+        if let Some(infos) = &*infos {
+            return Ok(infos.clone());
+        }
+        let (result,): (HashMap<String, String>,) = self.client.configuration(
+            "conversationInfos",
+            (&self.account_id, &self.conversation_id),
+        )?;
+        *infos = Some(result.clone());
+        Ok(result)
+    }
+
+    /**
+     * Drop cached infos and members
+     */
+    pub fn refresh(&self) {
+        *self.infos.lock().unwrap() = None;
+        *self.members.lock().unwrap() = None;
+    }
+
+    /**
+     * Load messages
+     * @param from      "" if latest else the commit id
+     * @param size      0 if all else max number of messages to get
+     * @return the messages, newest first
+     */
+    pub async fn load(&self, from: &str, size: u32) -> Result<Vec<ConversationMessage>, JamiError> {
+        let messages = Jami::load_conversation_await(
+            &self.account_id,
+            &self.conversation_id,
+            from,
+            size,
+            bus::call_timeout(),
+        )
+        .await?;
+        Ok(messages
+            .iter()
+            .map(ConversationMessage::from_payloads)
+            .collect())
+    }
+
+    /**
+     * Add a member
+     * @param uri       Peer to invite
+     */
+    pub fn invite(&self, uri: &str) -> Result<(), JamiError> {
+        self.client.configuration::<_, ()>(
+            "addConversationMember",
+            (&self.account_id, &self.conversation_id, uri),
+        )?;
+        *self.members.lock().unwrap() = None;
+        Ok(())
+    }
+
+    /**
+     * Remove a member
+     * @param uri       Member to remove
+     */
+    pub fn kick(&self, uri: &str) -> Result<(), JamiError> {
+        self.client.configuration::<_, ()>(
+            "removeConversationMember",
+            (&self.account_id, &self.conversation_id, uri),
+        )?;
+        *self.members.lock().unwrap() = None;
+        Ok(())
+    }
+
+    /**
+     * Leave and remove the conversation
+     */
+    pub fn leave(&self) -> Result<(), JamiError> {
+        let (removed,): (bool,) = self.client.configuration(
+            "removeConversation",
+            (&self.account_id, &self.conversation_id),
+        )?;
+        if !removed {
+            return Err(JamiError::OperationFailed(format!(
+                "Unable to remove {}",
+                self.conversation_id
+            )));
+        }
+        Ok(())
+    }
+}
+
+// (account, conversation, from, size)
+type PageKey = (String, String, String, u32);
+
+/**
+ * LRU cache of the pages loaded by Jami::load_conversation_await. A page loaded
+ * from a commit never changes, pages from the head (empty from) are dropped
+ * when a message is received in the conversation
+ **/
+struct PageCache {
+    capacity: usize,
+    // Most recently used last
+    pages: Vec<(PageKey, Vec<HashMap<String, String>>)>,
+}
+
+static PAGE_CACHE: Mutex<PageCache> = Mutex::new(PageCache {
+    capacity: 64,
+    pages: Vec::new(),
+});
+
+/**
+ * Change how many pages are kept by the cache of load_conversation_await
+ * @param capacity  Number of pages, 0 to disable the cache
+ */
+pub fn set_page_cache_size(capacity: usize) {
+    let mut cache = PAGE_CACHE.lock().unwrap();
+    cache.capacity = capacity;
+    let excess = cache.pages.len().saturating_sub(capacity);
+    cache.pages.drain(..excess);
+}
+
+/**
+ * Empty the cache of load_conversation_await
+ */
+pub fn clear_page_cache() {
+    PAGE_CACHE.lock().unwrap().pages.clear();
+}
+
+pub(crate) fn cached_page(key: &PageKey) -> Option<Vec<HashMap<String, String>>> {
+    let mut cache = PAGE_CACHE.lock().unwrap();
+    let index = cache.pages.iter().position(|(k, _)| k == key)?;
+    let page = cache.pages.remove(index);
+    let messages = page.1.clone();
+    cache.pages.push(page);
+    Some(messages)
+}
+
+pub(crate) fn cache_page(key: PageKey, messages: Vec<HashMap<String, String>>) {
+    let mut cache = PAGE_CACHE.lock().unwrap();
+    if cache.capacity == 0 {
+        return;
+    }
+    cache.pages.retain(|(k, _)| *k != key);
+    if cache.pages.len() >= cache.capacity {
+        cache.pages.remove(0);
+    }
+    cache.pages.push((key, messages));
+}
+
+// A commit of a cached page, without loading it again
+pub(crate) fn cached_message(
+    account_id: &str,
+    conversation_id: &str,
+    message_id: &str,
+) -> Option<HashMap<String, String>> {
+    let cache = PAGE_CACHE.lock().unwrap();
+    cache
+        .pages
+        .iter()
+        .filter(|((account, conversation, _, _), _)| {
+            account == account_id && conversation == conversation_id
+        })
+        .flat_map(|(_, messages)| messages.iter())
+        .find(|message| message.get("id").map(|id| &**id) == Some(message_id))
+        .cloned()
+}
+
+/**
+ * Drop the pages which depend on the head of a conversation
+ * @param account_id        Account
+ * @param conversation_id   Conversation which got a new commit
+ * @param removed           Drop every page, the conversation is removed
+ */
+pub(crate) fn invalidate_pages(account_id: &str, conversation_id: &str, removed: bool) {
+    PAGE_CACHE
+        .lock()
+        .unwrap()
+        .pages
+        .retain(|((account, conversation, from, _), _)| {
+            account != account_id
+                || conversation != conversation_id
+                || (!removed && !from.is_empty())
+        });
+}
@@ -0,0 +1,244 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use super::account::AccountType;
+use super::bus;
+
+use std::error::Error;
+use std::fmt;
+
+/**
+ * Errors returned by the daemon's wrappers
+ **/
+#[derive(Debug)]
+pub enum JamiError {
+    // Unable to connect to the bus
+    BusConnection(dbus::Error),
+    // The bus is reachable, but the daemon isn't running on it
+    DaemonUnavailable,
+    // A call to the daemon failed
+    MethodCall {
+        method: String,
+        source: dbus::Error,
+    },
+    // The connection to the bus was lost while waiting
+    ConnectionLost(String),
+    // The expected answer didn't arrive in time
+    Timeout,
+    // The daemon answered something unexpected
+    InvalidReply(String),
+    // The daemon reported a failure
+    OperationFailed(String),
+    // A parameter is invalid (name of the parameter, reason)
+    InvalidArgument(String, String),
+    // The daemon doesn't know the object (e.g. a removed conversation)
+    NotFound(String),
+    // Operation not supported by this type of account
+    WrongAccountType {
+        expected: AccountType,
+        actual: AccountType,
+    },
+}
+
+// Used for println!
+impl fmt::Display for JamiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JamiError::BusConnection(e) => write!(f, "Unable to connect to D-Bus: {}", e),
+            JamiError::DaemonUnavailable => {
+                write!(f, "The daemon ({}) is not running", bus::service_name())
+            }
+            JamiError::MethodCall { method, source } => {
+                write!(f, "Call to {} failed: {}", method, source)
+            }
+            JamiError::ConnectionLost(e) => write!(f, "Lost connection to D-Bus: {}", e),
+            JamiError::Timeout => write!(f, "Timeout"),
+            JamiError::InvalidReply(e) => write!(f, "Invalid reply: {}", e),
+            JamiError::OperationFailed(e) => write!(f, "Operation failed: {}", e),
+            JamiError::InvalidArgument(param, reason) => {
+                write!(f, "Invalid argument {}: {}", param, reason)
+            }
+            JamiError::NotFound(what) => write!(f, "Not found: {}", what),
+            JamiError::WrongAccountType { expected, actual } => write!(
+                f,
+                "Operation only supported by {} accounts (account is {})",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl Error for JamiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            JamiError::BusConnection(e) => Some(e),
+            JamiError::MethodCall { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl JamiError {
+    /**
+     * Error of a failed call: DaemonUnavailable if nobody owns the name of the daemon
+     * (cf Jami::set_config), Timeout if the daemon didn't answer in time
+     * (cf Jami::set_call_timeout), InvalidReply if the reply doesn't have the expected signature
+     * @param method    Method called
+     * @param source    Error returned by the bus
+     */
+    pub fn method_call(method: &str, source: dbus::Error) -> Self {
+        let message = source.message().unwrap_or_default();
+        match source.name() {
+            Some("org.freedesktop.DBus.Error.ServiceUnknown")
+            | Some("org.freedesktop.DBus.Error.NameHasNoOwner") => JamiError::DaemonUnavailable,
+            // Also sent by the bus when the daemon exits before answering
+            Some("org.freedesktop.DBus.Error.NoReply")
+                if message.starts_with("Message recipient disconnected") =>
+            {
+                JamiError::DaemonUnavailable
+            }
+            Some("org.freedesktop.DBus.Error.NoReply")
+            | Some("org.freedesktop.DBus.Error.Timeout") => JamiError::Timeout,
+            // dbus converts reading errors (TypeMismatchError) to Failed
+            Some("org.freedesktop.DBus.Error.Failed")
+                if message.starts_with("D-Bus argument type mismatch") =>
+            {
+                JamiError::InvalidReply(format!("{}: {}", method, message))
+            }
+            _ => JamiError::MethodCall {
+                method: method.to_string(),
+                source,
+            },
+        }
+    }
+}
+
+/**
+ * Why a name registration failed (nameRegistrationEnded)
+ **/
+#[derive(Debug)]
+pub enum NameRegistrationError {
+    // The name is already registered by someone else
+    AlreadyTaken,
+    // The name is refused (reason)
+    Invalid(String),
+    // The name server is unreachable
+    Network,
+    // Wrong password for the account
+    WrongPassword,
+    // The registration couldn't be done
+    Failed(JamiError),
+}
+
+// Used for println!
+impl fmt::Display for NameRegistrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NameRegistrationError::AlreadyTaken => write!(f, "Name already taken"),
+            NameRegistrationError::Invalid(reason) => write!(f, "Invalid name: {}", reason),
+            NameRegistrationError::Network => write!(f, "Unable to reach the name server"),
+            NameRegistrationError::WrongPassword => write!(f, "Wrong password"),
+            NameRegistrationError::Failed(e) => write!(f, "Name registration failed: {}", e),
+        }
+    }
+}
+
+impl Error for NameRegistrationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NameRegistrationError::Failed(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<JamiError> for NameRegistrationError {
+    fn from(e: JamiError) -> Self {
+        NameRegistrationError::Failed(e)
+    }
+}
+
+/**
+ * Why the daemon gave no information on a file transfer (DataTransferError)
+ **/
+#[derive(Debug)]
+pub enum TransferError {
+    // The daemon doesn't know the transfer, e.g. an id from before its restart
+    Unknown,
+    // The file couldn't be read or written
+    Io,
+    // The account or the conversation is invalid
+    InvalidArgument,
+    // Code not known by jami-rs
+    Other(u32),
+    // The call failed
+    Failed(JamiError),
+}
+
+impl TransferError {
+    /**
+     * Map the code returned by dataTransferInfo, acceptFileTransfer, etc.
+     * @param code  DataTransferError of the daemon
+     * @return None for success (0)
+     */
+    pub fn from_code(code: u32) -> Option<TransferError> {
+        match code {
+            0 => None,
+            1 => Some(TransferError::Unknown),
+            2 => Some(TransferError::Io),
+            3 => Some(TransferError::InvalidArgument),
+            code => Some(TransferError::Other(code)),
+        }
+    }
+}
+
+// Used for println!
+impl fmt::Display for TransferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransferError::Unknown => write!(f, "Unknown transfer"),
+            TransferError::Io => write!(f, "I/O error on the transferred file"),
+            TransferError::InvalidArgument => write!(f, "Invalid account or conversation"),
+            TransferError::Other(code) => write!(f, "Transfer error {}", code),
+            TransferError::Failed(e) => write!(f, "Transfer call failed: {}", e),
+        }
+    }
+}
+
+impl Error for TransferError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TransferError::Failed(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<JamiError> for TransferError {
+    fn from(e: JamiError) -> Self {
+        TransferError::Failed(e)
+    }
+}
@@ -0,0 +1,183 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use super::{Event, EventLoopOptions, Jami};
+
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc, watch};
+
+/**
+ * Stop an event loop (cf Jami::handle_events_until). The loop waits on it
+ * without polling, where an Arc<AtomicBool> is checked periodically.
+ * Cheap to clone, all clones stop the same loops
+ **/
+#[derive(Clone)]
+pub struct StopHandle {
+    tx: Arc<watch::Sender<bool>>,
+    rx: watch::Receiver<bool>,
+}
+
+impl Default for StopHandle {
+    fn default() -> Self {
+        StopHandle::new()
+    }
+}
+
+impl StopHandle {
+    pub fn new() -> StopHandle {
+        let (tx, rx) = watch::channel(false);
+        StopHandle {
+            tx: Arc::new(tx),
+            rx,
+        }
+    }
+
+    /**
+     * Stop the loops using this handle
+     */
+    pub fn stop(&self) {
+        let _ = self.tx.broadcast(true);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        *self.rx.borrow()
+    }
+
+    /**
+     * Resolve when stop() is called
+     */
+    pub async fn stopped(&self) {
+        let mut rx = self.rx.clone();
+        while !*rx.borrow() {
+            if rx.recv().await.is_none() {
+                return;
+            }
+        }
+    }
+}
+
+/**
+ * Share the events of the daemon between several subscribers (e.g. Account::watch).
+ * Cheap to clone, all clones use the same channel
+ **/
+#[derive(Clone)]
+pub struct EventLoopHandle {
+    tx: broadcast::Sender<Event<()>>,
+}
+
+impl EventLoopHandle {
+    /**
+     * @param capacity  Events kept for slow subscribers, older ones are dropped
+     */
+    pub fn new(capacity: usize) -> EventLoopHandle {
+        let (tx, _) = broadcast::channel(capacity);
+        EventLoopHandle { tx }
+    }
+
+    /**
+     * Run the event loop and broadcast its events. Must be called from a tokio runtime
+     * @param stop      Stop the event loop when set
+     * @param options   Options of the event loop
+     */
+    pub fn start(&self, stop: Arc<AtomicBool>, options: EventLoopOptions) {
+        let (tx, mut rx) = mpsc::channel(32);
+        tokio::spawn(async move {
+            let _ = Jami::handle_events_with_options::<()>(tx, stop, options).await;
+        });
+        let handle = self.clone();
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                handle.send(event);
+            }
+        });
+    }
+
+    /**
+     * Broadcast an event to the subscribers
+     * @param event     Event to send
+     */
+    pub fn send(&self, event: Event<()>) {
+        // Fails only if there is no subscriber
+        let _ = self.tx.send(event);
+    }
+
+    /**
+     * @return a receiver of the events sent after this call
+     */
+    pub fn subscribe(&self) -> broadcast::Receiver<Event<()>> {
+        self.tx.subscribe()
+    }
+}
+
+/**
+ * Last commit received for each conversation (cf EventLoopOptions::heads).
+ * Save snapshot() on exit and pass it to Jami::conversations_since on startup
+ * to only load the new messages
+ **/
+#[derive(Clone, Debug, Default)]
+pub struct ConversationHeads {
+    heads: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl ConversationHeads {
+    /**
+     * Restore saved heads
+     * @param heads     conversation id -> commit id
+     */
+    pub fn from_map(heads: HashMap<String, String>) -> ConversationHeads {
+        ConversationHeads {
+            heads: Arc::new(Mutex::new(heads)),
+        }
+    }
+
+    /**
+     * @param conversation_id
+     * @param commit            Last commit handled
+     */
+    pub fn update(&self, conversation_id: &str, commit: &str) {
+        self.heads
+            .lock()
+            .unwrap()
+            .insert(conversation_id.to_string(), commit.to_string());
+    }
+
+    /**
+     * @param conversation_id
+     * @return the last commit of the conversation, if any
+     */
+    pub fn get(&self, conversation_id: &str) -> Option<String> {
+        self.heads.lock().unwrap().get(conversation_id).cloned()
+    }
+
+    /**
+     * @return conversation id -> commit id, to be persisted
+     */
+    pub fn snapshot(&self) -> HashMap<String, String> {
+        self.heads.lock().unwrap().clone()
+    }
+}
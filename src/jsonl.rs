@@ -0,0 +1,443 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use super::eventloop::EventLoopHandle;
+use super::{Event, EventLoopOptions, Jami};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+
+/**
+ * A command read by Jami::run_jsonl, one JSON object per line, e.g.
+ * {"id": 1, "command": "send_message", "account_id": "...", "conversation_id": "...", "body": "hi"}
+ * "id" is optional and copied into the reply:
+ * {"reply": "send_message", "id": 1, "ok": true} or {"reply": ..., "ok": false, "error": "..."}
+ **/
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Command {
+    SendMessage {
+        account_id: String,
+        conversation_id: String,
+        body: String,
+        #[serde(default)]
+        reply_to: String,
+    },
+    AcceptRequest {
+        account_id: String,
+        conversation_id: String,
+    },
+    DeclineRequest {
+        account_id: String,
+        conversation_id: String,
+    },
+    AcceptTrustRequest {
+        account_id: String,
+        from: String,
+    },
+    DiscardTrustRequest {
+        account_id: String,
+        from: String,
+    },
+    // Stop run_jsonl
+    Stop,
+}
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Option<Value>,
+    #[serde(flatten)]
+    command: Command,
+}
+
+impl Command {
+    fn name(&self) -> &'static str {
+        match self {
+            Command::SendMessage { .. } => "send_message",
+            Command::AcceptRequest { .. } => "accept_request",
+            Command::DeclineRequest { .. } => "decline_request",
+            Command::AcceptTrustRequest { .. } => "accept_trust_request",
+            Command::DiscardTrustRequest { .. } => "discard_trust_request",
+            Command::Stop => "stop",
+        }
+    }
+
+    /**
+     * Run the command
+     * @return the error to report, if any
+     */
+    pub fn execute(&self) -> Result<(), String> {
+        let refused = |what: &str| Err(format!("{} refused by the daemon", what));
+        match self {
+            Command::SendMessage {
+                account_id,
+                conversation_id,
+                body,
+                reply_to,
+            } => Jami::try_send_message(account_id, conversation_id, body, reply_to, 0)
+                .map_err(|e| e.to_string()),
+            Command::AcceptRequest {
+                account_id,
+                conversation_id,
+            } => Jami::try_accept_request(account_id, conversation_id).map_err(|e| e.to_string()),
+            Command::DeclineRequest {
+                account_id,
+                conversation_id,
+            } => Jami::try_decline_request(account_id, conversation_id).map_err(|e| e.to_string()),
+            Command::AcceptTrustRequest { account_id, from } => {
+                match Jami::try_accept_trust_request(account_id, from) {
+                    Ok(true) => Ok(()),
+                    Ok(false) => refused("acceptTrustRequest"),
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            Command::DiscardTrustRequest { account_id, from } => {
+                match Jami::try_discard_trust_request(account_id, from) {
+                    Ok(true) => Ok(()),
+                    Ok(false) => refused("discardTrustRequest"),
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            Command::Stop => Ok(()),
+        }
+    }
+}
+
+/**
+ * Convert an event to its JSON line: {"event": "<snake_case name>", ...fields}.
+ * This is the stable schema of run_jsonl, fields are only added, never renamed
+ * @param event     Event of the event loop
+ * @return the object, None for events local to the process (Input, Resize)
+ */
+pub fn event_to_json<I>(event: &Event<I>) -> Option<Value> {
+    let value = match event {
+        Event::Input(_) | Event::Resize => return None,
+        Event::Message {
+            account_id,
+            conversation_id,
+            payloads,
+        } => json!({
+            "event": "message",
+            "account_id": account_id,
+            "conversation_id": conversation_id,
+            "payloads": payloads,
+        }),
+        Event::ConversationReady(account_id, conversation_id) => json!({
+            "event": "conversation_ready",
+            "account_id": account_id,
+            "conversation_id": conversation_id,
+        }),
+        Event::ConversationRemoved(account_id, conversation_id) => json!({
+            "event": "conversation_removed",
+            "account_id": account_id,
+            "conversation_id": conversation_id,
+        }),
+        Event::ConversationRequest(account_id, conversation_id) => json!({
+            "event": "conversation_request",
+            "account_id": account_id,
+            "conversation_id": conversation_id,
+        }),
+        Event::RegistrationStateChanged {
+            account_id,
+            raw_state,
+            code,
+            detail,
+            ..
+        } => json!({
+            "event": "registration_state_changed",
+            "account_id": account_id,
+            "state": raw_state,
+            "code": code,
+            "detail": detail,
+        }),
+        Event::RegistrationStalled(account_id) => json!({
+            "event": "registration_stalled",
+            "account_id": account_id,
+        }),
+        Event::ProfileReceived(account_id, from, path) => json!({
+            "event": "profile_received",
+            "account_id": account_id,
+            "from": from,
+            "path": path,
+        }),
+        Event::AccountMessage {
+            account_id,
+            message_id,
+            from,
+            payloads,
+        } => json!({
+            "event": "account_message",
+            "account_id": account_id,
+            "message_id": message_id,
+            "from": from,
+            "payloads": payloads,
+        }),
+        Event::RegisteredNameFound(account_id, status, address, name) => json!({
+            "event": "registered_name_found",
+            "account_id": account_id,
+            "status": status,
+            "address": address,
+            "name": name,
+        }),
+        Event::AccountsChanged() => json!({ "event": "accounts_changed" }),
+        Event::ConversationLoaded(request_id, account_id, conversation_id, messages) => json!({
+            "event": "conversation_loaded",
+            "request_id": request_id,
+            "account_id": account_id,
+            "conversation_id": conversation_id,
+            "messages": messages,
+        }),
+        Event::DataTransferEvent(account_id, conversation_id, file_id, code) => json!({
+            "event": "data_transfer",
+            "account_id": account_id,
+            "conversation_id": conversation_id,
+            "file_id": file_id,
+            "code": code,
+        }),
+        Event::IncomingContactRequest {
+            account_id,
+            from,
+            conversation_id,
+            profile,
+            received,
+            ..
+        } => json!({
+            "event": "incoming_contact_request",
+            "account_id": account_id,
+            "from": from,
+            "conversation_id": conversation_id,
+            "display_name": profile.as_ref().map(|profile| &profile.display_name),
+            "received": received,
+        }),
+        Event::MemberPresenceChanged(account_id, uri, online) => json!({
+            "event": "member_presence_changed",
+            "account_id": account_id,
+            "uri": uri,
+            "online": online,
+        }),
+        Event::ComposingStatusChanged {
+            account_id,
+            conversation_id,
+            from,
+            status,
+        } => json!({
+            "event": "composing_status_changed",
+            "account_id": account_id,
+            "conversation_id": conversation_id,
+            "from": from,
+            "status": status,
+        }),
+        Event::ConversationMemberEvent {
+            account_id,
+            conversation_id,
+            member_uri,
+            event,
+        } => json!({
+            "event": "conversation_member_event",
+            "account_id": account_id,
+            "conversation_id": conversation_id,
+            "member_uri": member_uri,
+            "code": event,
+        }),
+        Event::MessageStatusChanged {
+            account_id,
+            conversation_id,
+            peer,
+            message_id,
+            status,
+        } => json!({
+            "event": "message_status_changed",
+            "account_id": account_id,
+            "conversation_id": conversation_id,
+            "peer": peer,
+            "message_id": message_id,
+            "status": status,
+        }),
+        Event::TextMessageStatusChanged {
+            account_id,
+            message_id,
+            peer,
+            status,
+        } => json!({
+            "event": "text_message_status_changed",
+            "account_id": account_id,
+            "message_id": message_id,
+            "peer": peer,
+            "status": status,
+        }),
+        Event::KnownDevicesChanged(account_id, devices) => json!({
+            "event": "known_devices_changed",
+            "account_id": account_id,
+            "devices": devices,
+        }),
+        Event::AudioMeter(level) => json!({ "event": "audio_meter", "level": level }),
+        Event::AccountDetailsChanged(account_id, details) => json!({
+            "event": "account_details_changed",
+            "account_id": account_id,
+            "details": details,
+        }),
+        Event::VolatileDetailsChanged(account_id, details) => json!({
+            "event": "volatile_details_changed",
+            "account_id": account_id,
+            "details": details,
+        }),
+        Event::MediaNegotiationStatus {
+            call_id,
+            event,
+            media,
+        } => json!({
+            "event": "media_negotiation_status",
+            "call_id": call_id,
+            "status": event,
+            "media": media,
+        }),
+        Event::MediaChangeRequested {
+            account_id,
+            call_id,
+            media,
+        } => json!({
+            "event": "media_change_requested",
+            "account_id": account_id,
+            "call_id": call_id,
+            "media": media,
+        }),
+        Event::DaemonDisconnected(reason) => json!({
+            "event": "daemon_disconnected",
+            "reason": reason,
+        }),
+        Event::DaemonReconnected => json!({ "event": "daemon_reconnected" }),
+        Event::Unknown {
+            interface, member, ..
+        } => json!({
+            "event": "unknown",
+            "interface": interface,
+            "member": member,
+        }),
+    };
+    Some(value)
+}
+
+fn write_line<W: Write>(writer: &Mutex<W>, value: &Value) -> io::Result<()> {
+    let mut writer = writer.lock().unwrap();
+    writeln!(writer, "{}", value)?;
+    writer.flush()
+}
+
+/**
+ * Handle a line of the reader
+ * @return the reply, and if run_jsonl must stop
+ */
+fn handle_line(line: &str) -> (Value, bool) {
+    let request: Request = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => {
+            return (
+                json!({ "reply": "invalid", "ok": false, "error": e.to_string() }),
+                false,
+            )
+        }
+    };
+    let mut reply = json!({ "reply": request.command.name(), "ok": true });
+    if let Some(id) = request.id {
+        reply["id"] = id;
+    }
+    if let Err(error) = request.command.execute() {
+        reply["ok"] = json!(false);
+        reply["error"] = json!(error);
+    }
+    (reply, request.command == Command::Stop)
+}
+
+/**
+ * Run the event loop, write its events to writer and execute the commands of reader,
+ * until the reader ends, a stop command or stop is set
+ */
+pub(crate) async fn run<R, W>(reader: R, writer: W, stop: Arc<AtomicBool>) -> io::Result<()>
+where
+    R: BufRead + Send + 'static,
+    W: Write + Send + 'static,
+{
+    let events = EventLoopHandle::new(256);
+    let mut events_rx = events.subscribe();
+    events.start(stop.clone(), EventLoopOptions::default());
+    let writer = Arc::new(Mutex::new(writer));
+
+    // Reads block, so they get their own thread
+    let (lines_tx, mut lines_rx) = mpsc::unbounded_channel();
+    thread::spawn(move || {
+        for line in reader.lines() {
+            if line
+                .map(|line| lines_tx.send(line))
+                .map(|sent| sent.is_err())
+                .unwrap_or(true)
+            {
+                break;
+            }
+        }
+    });
+
+    let result = loop {
+        if stop.load(Ordering::Relaxed) {
+            break Ok(());
+        }
+        tokio::select! {
+            event = events_rx.recv() => match event {
+                Ok(event) => {
+                    if let Some(value) = event_to_json(&event) {
+                        if let Err(e) = write_line(&writer, &value) {
+                            break Err(e);
+                        }
+                    }
+                }
+                Err(broadcast::RecvError::Lagged(_)) => {}
+                Err(broadcast::RecvError::Closed) => break Ok(()),
+            },
+            line = lines_rx.recv() => match line {
+                Some(line) if line.trim().is_empty() => {}
+                Some(line) => {
+                    let (reply, stopping) = handle_line(&line);
+                    if let Err(e) = write_line(&writer, &reply) {
+                        break Err(e);
+                    }
+                    if stopping {
+                        break Ok(());
+                    }
+                }
+                None => break Ok(()),
+            },
+            _ = tokio::time::delay_for(Duration::from_millis(100)) => {}
+        }
+    };
+    stop.store(true, Ordering::Relaxed);
+    result
+}
@@ -25,32 +25,109 @@
  * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  **/
 pub mod account;
+pub mod accountbuilder;
+pub mod accountdetails;
+pub mod accountstore;
+pub mod asyncclient;
+pub mod bus;
+pub mod call;
+pub mod calllog;
+pub mod capabilities;
+pub mod client;
+pub mod conversation;
+pub mod error;
+pub mod eventloop;
+#[cfg(feature = "jsonl")]
+pub mod jsonl;
+pub mod metadatacache;
 pub mod profile;
 pub mod profilemanager;
+pub mod session;
+#[cfg(feature = "test-util")]
+pub mod testutil;
+pub mod transfer;
 pub mod transfermanager;
+pub mod typing;
+pub mod unread;
+pub mod uri;
+pub mod validate;
 
+pub use accountbuilder::{AccountBuilder, AccountCreationProgress};
+pub use accountdetails::{details_keys, AccountDetails, DetailField, DetailKind};
+pub use accountstore::AccountStore;
+pub use asyncclient::AsyncJamiClient;
+pub use bus::{BusType, DaemonFlavor, JamiConfig};
+pub use call::{CallInfo, MediaAttribute, MediaType};
+pub use capabilities::{Capabilities, CapabilityThresholds};
+pub use client::{JamiClient, JamiHandle};
+pub use conversation::{
+    BroadcastResult, Conversation, ConversationMessage, ConversationMode, ConversationState,
+    GeoLocation, MessageStatus,
+};
+pub use error::{JamiError, NameRegistrationError, TransferError};
+pub use eventloop::{ConversationHeads, EventLoopHandle, StopHandle};
+pub use metadatacache::MetadataCache;
 pub use profile::Profile;
-pub use profilemanager::ProfileManager;
+pub use profilemanager::{ProfileManager, SearchHit};
+pub use session::{JamiSession, SessionConfig};
+pub use transfer::DataTransferEventCode;
 pub use transfermanager::TransferManager;
+pub use typing::TypingState;
+pub use unread::UnreadTracker;
+pub use uri::{InfoHash, JamiUri};
 
-use account::Account;
+use account::{Account, AccountStats, AccountType, NetworkStatus, ProxyConfig, RegistrationState};
+use accountbuilder::CreationWatch;
 
-use dbus::blocking::Connection;
-use dbus::message::MatchRule;
-use dbus_tokio::connection;
-use log::info;
+use app_dirs::{get_app_dir, AppDataType, AppInfo};
+use dbus::arg::messageitem::MessageItem;
+use dbus::message::{MatchRule, MessageType};
+use dbus::nonblock;
 use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::time::Duration;
-use std::{thread, time};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::stream::Stream;
+use tokio::sync::{broadcast, mpsc, oneshot};
 
 /**
  * Connect to the jami daemon
  */
 pub struct Jami {}
 
-#[derive(Debug)]
+// account id -> (computed at, stats). Used by account_stats
+static STATS_CACHE: Mutex<Vec<(String, Instant, AccountStats)>> = Mutex::new(Vec::new());
+const STATS_CACHE_DURATION: Duration = Duration::from_secs(2);
+
+// (account id, conversation id, started at, duration). Live locations sent by send_location
+static LOCATION_SHARES: Mutex<Vec<(String, String, Instant, Duration)>> = Mutex::new(Vec::new());
+
+// (account id, conversation id, message). Used by get_message, oldest first
+static MESSAGE_CACHE: Mutex<Vec<(String, String, ConversationMessage)>> = Mutex::new(Vec::new());
+const MESSAGE_CACHE_SIZE: usize = 256;
+// Max sendMessage calls in flight in broadcast_message
+const MAX_BROADCAST_CALLS: usize = 8;
+// Max time to wait for the commit of a broadcast message
+const BROADCAST_TIMEOUT: Duration = Duration::from_secs(10);
+// Max time to wait for the dataTransferEvent of a file sent by send_file_tracked
+const SENT_FILE_TIMEOUT: Duration = Duration::from_secs(10);
+// Bigger avatars are shrunk (image feature) or refused
+const MAX_AVATAR_BYTES: usize = 512 * 1024;
+// Size of shrunk avatars, in pixels
+#[cfg(feature = "image")]
+const AVATAR_SIZE: u32 = 400;
+// Removals remembered to not announce them twice, the oldest are forgotten
+const MAX_ANNOUNCED_REMOVALS: usize = 256;
+// Event loops, told of (account, conversation) found removed by a call (cf try_send_message)
+static REMOVAL_LISTENERS: Mutex<Vec<mpsc::UnboundedSender<(String, String)>>> =
+    Mutex::new(Vec::new());
+// (account, conversation) for which Event::ConversationRemoved was sent, oldest first
+static ANNOUNCED_REMOVALS: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+#[derive(Clone, Debug)]
 pub enum Event<I> {
     Input(I),
     Message {
@@ -61,17 +138,155 @@ pub enum Event<I> {
     ConversationReady(String, String),
     ConversationRemoved(String, String),
     ConversationRequest(String, String),
-    RegistrationStateChanged(String, String),
+    RegistrationStateChanged {
+        account_id: String,
+        state: RegistrationState,
+        // State as sent by the daemon
+        raw_state: String,
+        // Detail code and string (why the registration failed)
+        code: i32,
+        detail: String,
+    },
+    // Account still TRYING after EventLoopOptions::registration_watchdog
+    RegistrationStalled(String),
     ProfileReceived(String, String, String),
+    // Message outside of swarms (legacy text, application/geo...)
+    AccountMessage {
+        account_id: String,
+        message_id: String,
+        from: String,
+        payloads: HashMap<String, String>,
+    },
     RegisteredNameFound(String, u64, String, String),
     AccountsChanged(),
     ConversationLoaded(u32, String, String, Vec<HashMap<String, String>>),
-    DataTransferEvent(String, String, u64, i32),
-    IncomingTrustRequest(String, String, Vec<u8>, u64),
+    DataTransferEvent(String, String, u64, DataTransferEventCode),
+    // Trust request (contact invite), merged with the one-to-one conversation it creates
+    IncomingContactRequest {
+        account_id: String,
+        from: String,
+        // Conversation to accept with the request, for swarm-enabled daemons
+        conversation_id: Option<String>,
+        // vCard sent with the request
+        profile: Option<Profile>,
+        payload: Vec<u8>,
+        received: u64,
+    },
     MemberPresenceChanged(String, String, bool),
+    // status: 0 = stopped, 1 = typing (cf TypingState)
+    ComposingStatusChanged {
+        account_id: String,
+        conversation_id: String,
+        from: String,
+        status: i32,
+    },
+    // event: 0 = add, 1 = joins, 2 = leave, 3 = banned
+    ConversationMemberEvent {
+        account_id: String,
+        conversation_id: String,
+        member_uri: String,
+        event: i32,
+    },
+    // status: 0 = unknown, 1 = sending, 2 = sent, 3 = displayed, 4 = failure, 5 = cancelled
+    MessageStatusChanged {
+        account_id: String,
+        conversation_id: String,
+        peer: String,
+        message_id: String,
+        status: i32,
+    },
+    // Status of a message sent with try_send_text_message (non-swarm)
+    TextMessageStatusChanged {
+        account_id: String,
+        message_id: u64,
+        peer: String,
+        status: MessageStatus,
+    },
+    KnownDevicesChanged(String, HashMap<String, String>),
+    // Input level between 0 and 1 (while Jami::start_audio_test runs)
+    AudioMeter(f64),
+    AccountDetailsChanged(String, HashMap<String, String>),
+    VolatileDetailsChanged(String, HashMap<String, String>),
+    // The daemon doesn't send the account for this signal
+    MediaNegotiationStatus {
+        call_id: String,
+        event: String,
+        media: Vec<MediaAttribute>,
+    },
+    MediaChangeRequested {
+        account_id: String,
+        call_id: String,
+        media: Vec<MediaAttribute>,
+    },
+    // The connection to the bus or the daemon was lost (reason). Without
+    // EventLoopOptions::reconnect, the event loop ends
+    DaemonDisconnected(String),
+    // Back after a DaemonDisconnected (only with EventLoopOptions::reconnect)
+    DaemonReconnected,
+    // Signal without a typed variant (only if EventLoopOptions::forward_unknown)
+    Unknown {
+        interface: String,
+        member: String,
+        body: Vec<MessageItem>,
+    },
     Resize,
 }
 
+/**
+ * Options for the event loop (cf handle_events_with_options)
+ */
+#[derive(Clone, Debug, Default)]
+pub struct EventLoopOptions {
+    // Forward signals not handled by the crate as Event::Unknown.
+    // Heavier, because all daemon's signals are received.
+    pub forward_unknown: bool,
+    // Emit Event::RegistrationStalled if an account stays TRYING longer than this
+    pub registration_watchdog: Option<Duration>,
+    // Updated with the last commit received for each conversation, to be persisted
+    pub heads: Option<ConversationHeads>,
+    // Reconnect when the bus connection is lost and follow restarts of the daemon,
+    // instead of ending the loop
+    pub reconnect: Option<ReconnectPolicy>,
+    // Bus to listen to, None for the one of Jami::set_bus
+    pub bus: Option<BusType>,
+}
+
+/**
+ * Reconnection of the event loop (cf EventLoopOptions::reconnect)
+ */
+#[derive(Clone, Debug)]
+pub struct ReconnectPolicy {
+    // Delay before the first attempt, doubled after each failed attempt
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    // Failed attempts before the loop ends with an error, None to retry forever
+    pub max_retries: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            max_retries: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /**
+     * @param attempt   Number of the attempt, from 1
+     * @return the delay before this attempt
+     */
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+}
+
 #[derive(PartialEq)]
 pub enum ImportType {
     None,
@@ -79,6 +294,22 @@ pub enum ImportType {
     NETWORK,
 }
 
+// The dataTransferInfo wire layout, as produced by DataTransferInfo::tuple
+pub(crate) type DataTransferTuple = (
+    String,
+    u32,
+    u32,
+    i64,
+    i64,
+    String,
+    String,
+    String,
+    String,
+    String,
+    String,
+);
+
+#[derive(Default)]
 pub struct DataTransferInfo {
     pub account_id: String,
     pub last_event: u32,
@@ -116,24 +347,130 @@ impl DataTransferInfo {
 }
 
 impl Jami {
+    /**
+     * Change the bus where the daemon is, for the connections opened afterwards
+     * (functions of Jami, JamiClient, AsyncJamiClient and handle_events).
+     * E.g. for a jamid running in a container, with its bus forwarded to the host:
+     * Jami::set_bus(BusType::Address(String::from("tcp:host=127.0.0.1,port=4000")))
+     * @param bus   The bus, BusType::Session by default
+     */
+    pub fn set_bus(bus: BusType) {
+        bus::set_bus(bus)
+    }
+
+    /**
+     * @return the bus where the daemon is
+     */
+    pub fn bus() -> BusType {
+        bus::bus()
+    }
+
+    /**
+     * Change the names of the daemon (service, objects and interfaces), for the
+     * connections and event loops started afterwards, instead of detecting them.
+     * E.g. for a renamed daemon:
+     * Jami::set_config(JamiConfig::named("org.example.Jami", "/org/example/Jami"))
+     * JamiClient::with_config changes them for one client
+     * @param config    The names
+     */
+    pub fn set_config(config: JamiConfig) {
+        bus::set_config(config)
+    }
+
+    /**
+     * Get the names of the daemon. Unless set_config is used, they are detected
+     * from the names owned on the bus (cx.ring.Ring or net.jami.daemon) and kept
+     * once found. JamiConfig::default() (cx.ring.Ring) while the daemon isn't running
+     * @return the names of the daemon
+     */
+    pub fn config() -> JamiConfig {
+        bus::config()
+    }
+
+    /**
+     * Detect the names of the daemon again, e.g. after set_config or if the
+     * daemon was replaced by one of another flavor. Event loops already
+     * started keep their names
+     */
+    pub fn detect_daemon() {
+        bus::reset_config()
+    }
+
+    /**
+     * @return the names used for the daemon (cf config)
+     */
+    pub fn daemon_flavor() -> DaemonFlavor {
+        DaemonFlavor::of(&bus::config())
+    }
+
+    /**
+     * Change the timeout of the calls to the daemon, e.g. for slow name lookups.
+     * Functions taking a timeout parameter are not affected. A call without
+     * answer in time fails with JamiError::Timeout
+     * @param timeout   5s by default
+     */
+    pub fn set_call_timeout(timeout: Duration) {
+        bus::set_call_timeout(timeout)
+    }
+
+    /**
+     * @return the timeout of the calls to the daemon (cf with_timeout)
+     */
+    pub fn call_timeout() -> Duration {
+        bus::call_timeout()
+    }
+
+    /**
+     * Make calls with another timeout, only for this thread, e.g.
+     * Jami::with_timeout(Duration::from_secs(20), || Jami::lookup_name(&account, &ns, &name))
+     * Async functions read the timeout when polled, use set_call_timeout or
+     * JamiClient/AsyncJamiClient::with_timeout for them
+     * @param timeout   Timeout of the calls made by f
+     * @param f         Calls to make
+     * @return the result of f
+     */
+    pub fn with_timeout<R, F: FnOnce() -> R>(timeout: Duration, f: F) -> R {
+        bus::with_timeout(timeout, f)
+    }
+
+    /**
+     * Open a connection to the daemon, for many calls. The handle has the account,
+     * contact, trust request, conversation and call functions of Jami most clients
+     * need; call(), configuration() and call_manager() reach any other method.
+     * It is Send and cheap to clone
+     * @return the handle (cf set_bus, set_call_timeout)
+     */
+    pub fn connect() -> Result<JamiHandle, JamiError> {
+        JamiClient::new()
+    }
+
+    /**
+     * Open a connection to the daemon on a given bus, whatever set_bus says
+     * @param bus   e.g. BusType::System for a daemon running as a system service
+     * @return the handle
+     */
+    pub fn connect_to(bus: &BusType) -> Result<JamiHandle, JamiError> {
+        JamiClient::connect_to(bus)
+    }
+
     /**
      * Retrieve account or create one if necessary.
      * @param   create_if_not   Create if no account found
-     * @return the account
+     * @return the account (None if no account is ready yet)
      */
-    pub fn select_jami_account(create_if_not: bool) -> Account {
-        let accounts = Jami::get_account_list();
+    pub fn select_jami_account(create_if_not: bool) -> Option<Account> {
+        let accounts = Jami::try_get_account_list().unwrap_or_default();
         // Select first enabled account
         for account in &accounts {
             if account.enabled {
-                return account.clone();
+                return Some(account.clone());
             }
         }
         if create_if_not {
             // No valid account found, generate a new one
-            Jami::add_account("", "", ImportType::None);
+            let _ = AccountBuilder::new_ring().password("").alias("").build();
         }
-        return Account::null();
+        None
     }
 
     /**
@@ -143,72 +480,287 @@ impl Jami {
         tx: tokio::sync::mpsc::Sender<Event<T>>,
         stop: Arc<AtomicBool>,
     ) -> Result<(), std::io::Error> {
-        let (resource, conn) = connection::new_session_sync()
-            .ok()
-            .expect("Lost connection");
-        tokio::spawn(async {
-            let err = resource.await;
-            panic!("Lost connection to D-Bus: {}", err);
+        Jami::handle_events_with_options(tx, stop, EventLoopOptions::default()).await
+    }
+
+    /**
+     * Listen to daemon's signals until cancel.stop() is called. Where the flag of
+     * handle_events is checked every 10ms, the loop ends as soon as it is cancelled
+     * and removes its match rules
+     * @param tx        Where to send events
+     * @param cancel    Stops the loop
+     */
+    pub async fn handle_events_with_cancel<T: 'static + std::fmt::Debug + std::marker::Send>(
+        tx: tokio::sync::mpsc::Sender<Event<T>>,
+        cancel: StopHandle,
+    ) -> Result<(), std::io::Error> {
+        Jami::handle_events_until(tx, cancel, EventLoopOptions::default()).await
+    }
+
+    /**
+     * Listen to daemon's signals (cf handle_events_until)
+     * @param tx        Where to send events
+     * @param stop      Set to true to stop the loop, checked every 10ms
+     * @param options   Options of the loop
+     */
+    pub async fn handle_events_with_options<T: 'static + std::fmt::Debug + std::marker::Send>(
+        tx: tokio::sync::mpsc::Sender<Event<T>>,
+        stop: Arc<AtomicBool>,
+        options: EventLoopOptions,
+    ) -> Result<(), std::io::Error> {
+        let handle = StopHandle::new();
+        let watcher = handle.clone();
+        tokio::spawn(async move {
+            while !watcher.is_stopped() {
+                if stop.load(Ordering::Relaxed) {
+                    watcher.stop();
+                } else {
+                    tokio::time::delay_for(Duration::from_millis(10)).await;
+                }
+            }
+        });
+        let result = Jami::handle_events_until(tx, handle.clone(), options).await;
+        // Ends the watcher
+        handle.stop();
+        result
+    }
+
+    /**
+     * Listen to daemon's signals until stop.stop() is called. If the connection to
+     * the bus is lost, Event::DaemonDisconnected is sent and the loop ends with an
+     * error, unless options.reconnect is set: then the loop reconnects, registers its
+     * signals again and sends Event::DaemonReconnected. With reconnect, the loop also
+     * sends these events when the daemon leaves and comes back on the bus
+     * @param tx        Where to send events
+     * @param stop      Stops the loop, which waits for it without polling
+     * @param options   Options of the loop
+     * @return an error if the bus can't be reached, the signals can't be subscribed
+     * or the connection is lost (after options.reconnect's max_retries)
+     */
+    pub async fn handle_events_until<T: 'static + std::fmt::Debug + std::marker::Send>(
+        tx: tokio::sync::mpsc::Sender<Event<T>>,
+        stop: StopHandle,
+        options: EventLoopOptions,
+    ) -> Result<(), std::io::Error> {
+        #[cfg(feature = "call-log")]
+        let tx = calllog::record_events(tx);
+        // Set between DaemonDisconnected and DaemonReconnected, to send them once
+        let gap = Arc::new(AtomicBool::new(false));
+        // Failed attempts to reconnect
+        let mut attempt = 0;
+        loop {
+            let err = match Jami::listen_signals(&tx, &stop, &options, &gap, &mut attempt).await {
+                Ok(()) => return Ok(()),
+                Err(err) => err,
+            };
+            let policy = match &options.reconnect {
+                Some(policy) => policy,
+                None => return Err(err),
+            };
+            attempt += 1;
+            if policy.max_retries.map(|max| attempt > max).unwrap_or(false) {
+                return Err(err);
+            }
+            tokio::select! {
+                _ = stop.stopped() => return Ok(()),
+                _ = tokio::time::delay_for(policy.delay(attempt)) => {}
+            }
+        }
+    }
+
+    // Connect and forward signals until stop is set (Ok) or the connection is lost
+    async fn listen_signals<T: 'static + std::fmt::Debug + std::marker::Send>(
+        tx: &mpsc::Sender<Event<T>>,
+        stop: &StopHandle,
+        options: &EventLoopOptions,
+        gap: &Arc<AtomicBool>,
+        attempt: &mut u32,
+    ) -> Result<(), std::io::Error> {
+        let bus = options.bus.clone().unwrap_or_else(bus::bus);
+        let (resource, conn) = bus::connect_async_to(&bus).map_err(Jami::bus_error)?;
+        // Why the connection to the bus was lost, to leave the loop
+        let (lost_tx, mut lost) = oneshot::channel();
+        // Dropped when this function returns, to stop the resource
+        let (_stopped_tx, stopped) = oneshot::channel::<()>();
+        let resource_gap = gap.clone();
+        let mut txs = tx.clone();
+        tokio::spawn(async move {
+            let reason = tokio::select! {
+                err = resource => err.to_string(),
+                _ = stopped => return,
+            };
+            if !resource_gap.swap(true, Ordering::Relaxed) {
+                let _ = txs.send(Event::DaemonDisconnected(reason.clone())).await;
+            }
+            let _ = lost_tx.send(reason);
         });
+        // Removed when the loop stops, the connection can be shared (bus::connect_async)
+        let mut matches = Vec::new();
+        // Resolved once, a probe of the bus per match is too slow without the daemon
+        let config = bus::config();
 
-        let mr = MatchRule::new_signal("cx.ring.Ring.ConfigurationManager", "accountsChanged");
+        let mr = MatchRule::new_signal(config.config_manager_interface.clone(), "accountsChanged");
         let txs = tx.clone();
-        let _ic = conn
-            .add_match(mr)
-            .await
-            .ok()
-            .expect("Lost connection")
-            .cb(move |_, (): ()| {
+        matches.push(
+            conn.add_match(mr)
+                .await
+                .map_err(Jami::bus_error)?
+                .cb(move |_, (): ()| {
+                    let mut txs = txs.clone();
+                    tokio::spawn(async move { txs.send(Event::AccountsChanged()).await });
+                    true
+                }),
+        );
+
+        let mr = MatchRule::new_signal(
+            config.config_manager_interface.clone(),
+            "accountDetailsChanged",
+        );
+        let txs = tx.clone();
+        matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
+            move |_, (account_id, details): (String, HashMap<String, String>)| {
+                let mut txs = txs.clone();
+                tokio::spawn(async move {
+                    txs.send(Event::AccountDetailsChanged(account_id, details))
+                        .await
+                });
+                true
+            },
+        ));
+
+        let mr = MatchRule::new_signal(
+            config.config_manager_interface.clone(),
+            "volatileAccountDetailsChanged",
+        );
+        let txs = tx.clone();
+        matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
+            move |_, (account_id, details): (String, HashMap<String, String>)| {
                 let mut txs = txs.clone();
-                tokio::spawn(async move { txs.send(Event::AccountsChanged()).await });
+                tokio::spawn(async move {
+                    txs.send(Event::VolatileDetailsChanged(account_id, details))
+                        .await
+                });
                 true
-            });
+            },
+        ));
 
-        let mr = MatchRule::new_signal("cx.ring.Ring.ConfigurationManager", "messageReceived");
+        let mr = MatchRule::new_signal(
+            config.config_manager_interface.clone(),
+            "incomingAccountMessage",
+        );
         let txs = tx.clone();
-        let _ic = conn.add_match(mr).await.ok().expect("Lost connection").cb(
+        matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
             move |_,
-                  (account_id, conversation_id, payloads): (
+                  (account_id, message_id, from, payloads): (
+                String,
                 String,
                 String,
                 HashMap<String, String>,
             )| {
                 let mut txs = txs.clone();
                 tokio::spawn(async move {
-                    txs.send(Event::Message {
+                    txs.send(Event::AccountMessage {
                         account_id,
-                        conversation_id,
+                        message_id,
+                        from,
                         payloads,
                     })
                     .await
                 });
                 true
             },
-        );
+        ));
 
-        let mr = MatchRule::new_signal(
-            "cx.ring.Ring.ConfigurationManager",
-            "registrationStateChanged",
-        );
+        let mr = MatchRule::new_signal(config.config_manager_interface.clone(), "messageReceived");
         let txs = tx.clone();
-        let _ic = conn.add_match(mr).await.ok().expect("Lost connection").cb(
-            move |_, (account_id, registration_state, _, _): (String, String, u64, String)| {
+        let heads = options.heads.clone();
+        matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
+            move |_,
+                  (account_id, conversation_id, payloads): (
+                String,
+                String,
+                HashMap<String, String>,
+            )| {
+                if let (Some(heads), Some(commit)) = (&heads, payloads.get("id")) {
+                    heads.update(&conversation_id, commit);
+                }
+                conversation::invalidate_pages(&account_id, &conversation_id, false);
                 let mut txs = txs.clone();
                 tokio::spawn(async move {
-                    txs.send(Event::RegistrationStateChanged(
+                    txs.send(Event::Message {
                         account_id,
-                        registration_state,
-                    ))
+                        conversation_id,
+                        payloads,
+                    })
                     .await
                 });
                 true
             },
+        ));
+
+        let mr = MatchRule::new_signal(
+            config.config_manager_interface.clone(),
+            "registrationStateChanged",
+        );
+        let txs = tx.clone();
+        // account id -> number of state changes, to cancel the watchdog
+        let generations: Arc<Mutex<HashMap<String, u64>>> = Arc::new(Mutex::new(HashMap::new()));
+        let watchdog = options.registration_watchdog;
+        matches.push(
+            conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
+                move |_,
+                      (account_id, registration_state, code, detail): (
+                    String,
+                    String,
+                    i32,
+                    String,
+                )| {
+                    if let Some(timeout) = watchdog {
+                        let generation = {
+                            let mut generations = generations.lock().unwrap();
+                            let generation = generations.entry(account_id.clone()).or_insert(0);
+                            *generation += 1;
+                            *generation
+                        };
+                        if registration_state == "TRYING" {
+                            let generations = generations.clone();
+                            let account_id = account_id.clone();
+                            let mut txs = txs.clone();
+                            tokio::spawn(async move {
+                                tokio::time::delay_for(timeout).await;
+                                let current = generations.lock().unwrap().get(&account_id).cloned();
+                                if current == Some(generation) {
+                                    let _ = txs.send(Event::RegistrationStalled(account_id)).await;
+                                }
+                            });
+                        }
+                    }
+                    let mut txs = txs.clone();
+                    tokio::spawn(async move {
+                        txs.send(Event::RegistrationStateChanged {
+                            account_id,
+                            state: registration_state.parse().unwrap(),
+                            raw_state: registration_state,
+                            code,
+                            detail,
+                        })
+                        .await
+                    });
+                    true
+                },
+            ),
         );
 
-        let mr = MatchRule::new_signal("cx.ring.Ring.ConfigurationManager", "conversationReady");
+        let mr =
+            MatchRule::new_signal(config.config_manager_interface.clone(), "conversationReady");
         let txs = tx.clone();
-        let _ic = conn.add_match(mr).await.ok().expect("Lost connection").cb(
+        matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
             move |_, (account_id, conversation_id): (String, String)| {
+                let key = (account_id.clone(), conversation_id.clone());
+                ANNOUNCED_REMOVALS
+                    .lock()
+                    .unwrap()
+                    .retain(|removed| *removed != key);
                 let mut txs = txs.clone();
                 tokio::spawn(async move {
                     txs.send(Event::ConversationReady(account_id, conversation_id))
@@ -216,12 +768,17 @@ impl Jami {
                 });
                 true
             },
-        );
+        ));
 
-        let mr = MatchRule::new_signal("cx.ring.Ring.ConfigurationManager", "conversationRemoved");
+        let mr = MatchRule::new_signal(
+            config.config_manager_interface.clone(),
+            "conversationRemoved",
+        );
         let txs = tx.clone();
-        let _ic = conn.add_match(mr).await.ok().expect("Lost connection").cb(
+        matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
             move |_, (account_id, conversation_id): (String, String)| {
+                Jami::announce_removal(&account_id, &conversation_id);
+                conversation::invalidate_pages(&account_id, &conversation_id, true);
                 let mut txs = txs.clone();
                 tokio::spawn(async move {
                     txs.send(Event::ConversationRemoved(account_id, conversation_id))
@@ -229,14 +786,14 @@ impl Jami {
                 });
                 true
             },
-        );
+        ));
 
         let mr = MatchRule::new_signal(
-            "cx.ring.Ring.ConfigurationManager",
+            config.config_manager_interface.clone(),
             "conversationRequestReceived",
         );
         let txs = tx.clone();
-        let _ic = conn.add_match(mr).await.ok().expect("Lost connection").cb(
+        matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
             move |_, (account_id, conversation_id): (String, String)| {
                 let mut txs = txs.clone();
                 tokio::spawn(async move {
@@ -245,11 +802,14 @@ impl Jami {
                 });
                 true
             },
-        );
+        ));
 
-        let mr = MatchRule::new_signal("cx.ring.Ring.ConfigurationManager", "registeredNameFound");
+        let mr = MatchRule::new_signal(
+            config.config_manager_interface.clone(),
+            "registeredNameFound",
+        );
         let txs = tx.clone();
-        let _ic = conn.add_match(mr).await.ok().expect("Lost connection").cb(
+        matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
             move |_, (account_id, status, address, name): (String, i32, String, String)| {
                 let mut txs = txs.clone();
                 tokio::spawn(async move {
@@ -263,11 +823,11 @@ impl Jami {
                 });
                 true
             },
-        );
+        ));
 
-        let mr = MatchRule::new_signal("cx.ring.Ring.ConfigurationManager", "profileReceived");
+        let mr = MatchRule::new_signal(config.config_manager_interface.clone(), "profileReceived");
         let txs = tx.clone();
-        let _ic = conn.add_match(mr).await.ok().expect("Lost connection").cb(
+        matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
             move |_, (account_id, from, path): (String, String, String)| {
                 let mut txs = txs.clone();
                 tokio::spawn(async move {
@@ -276,29 +836,58 @@ impl Jami {
                 });
                 true
             },
-        );
+        ));
 
-        let mr = MatchRule::new_signal("cx.ring.Ring.ConfigurationManager", "incomingTrustRequest");
+        let mr = MatchRule::new_signal(
+            config.config_manager_interface.clone(),
+            "incomingTrustRequest",
+        );
         let txs = tx.clone();
-        let _ic = conn.add_match(mr).await.ok().expect("Lost connection").cb(
-            move |_, (account_id, from, payloads, receive_time): (String, String, Vec<u8>, u64)| {
-                let mut txs = txs.clone();
-                tokio::spawn(async move {
-                    txs.send(Event::IncomingTrustRequest(
-                        account_id,
-                        from,
-                        payloads,
-                        receive_time,
-                    ))
-                    .await
-                });
-                true
-            },
+        matches.push(
+            conn.add_match(mr)
+                .await
+                .map_err(Jami::bus_error)?
+                .msg_cb(move |msg| {
+                    // Swarm-enabled daemons add the conversation id after the account id
+                    let (account_id, conversation_id, from, payload, received) =
+                        if let Ok((account_id, conversation_id, from, payload, received)) =
+                            msg.read5::<String, String, String, Vec<u8>, u64>()
+                        {
+                            (account_id, Some(conversation_id), from, payload, received)
+                        } else if let Ok((account_id, from, payload, received)) =
+                            msg.read4::<String, String, Vec<u8>, u64>()
+                        {
+                            (account_id, None, from, payload, received)
+                        } else {
+                            return true;
+                        };
+                    let (payload_conversation_id, profile) =
+                        Jami::parse_contact_request_payload(&payload);
+                    let conversation_id = conversation_id
+                        .filter(|id| !id.is_empty())
+                        .or(payload_conversation_id);
+                    let mut txs = txs.clone();
+                    tokio::spawn(async move {
+                        txs.send(Event::IncomingContactRequest {
+                            account_id,
+                            from,
+                            conversation_id,
+                            profile,
+                            payload,
+                            received,
+                        })
+                        .await
+                    });
+                    true
+                }),
         );
 
-        let mr = MatchRule::new_signal("cx.ring.Ring.ConfigurationManager", "conversationLoaded");
+        let mr = MatchRule::new_signal(
+            config.config_manager_interface.clone(),
+            "conversationLoaded",
+        );
         let txs = tx.clone();
-        let _ic = conn.add_match(mr).await.ok().expect("Lost connection").cb(
+        matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
             move |_,
                   (id, account_id, conversation_id, messages): (
                 u32,
@@ -318,11 +907,12 @@ impl Jami {
                 });
                 true
             },
-        );
+        ));
 
-        let mr = MatchRule::new_signal("cx.ring.Ring.ConfigurationManager", "dataTransferEvent");
+        let mr =
+            MatchRule::new_signal(config.config_manager_interface.clone(), "dataTransferEvent");
         let txs = tx.clone();
-        let _ic = conn.add_match(mr).await.ok().expect("Lost connection").cb(
+        matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
             move |_,
                   (account_id, conversation_id, id, code): (
                 String,
@@ -336,106 +926,430 @@ impl Jami {
                         account_id,
                         conversation_id,
                         id,
-                        code,
+                        DataTransferEventCode::from(code),
                     ))
                     .await
                 });
                 true
             },
-        );
+        ));
 
-        let mr = MatchRule::new_signal("cx.ring.Ring.PresenceManager", "newBuddyNotification");
+        let mr = MatchRule::new_signal(
+            config.config_manager_interface.clone(),
+            "knownDevicesChanged",
+        );
         let txs = tx.clone();
-        let _ic = conn.add_match(mr).await.ok().expect("Lost connection").cb(
-            move |_, (account_id, uri, flag, _): (String, String, bool, String)| {
+        matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
+            move |_, (account_id, devices): (String, HashMap<String, String>)| {
                 let mut txs = txs.clone();
                 tokio::spawn(async move {
-                    txs.send(Event::MemberPresenceChanged(
-                        account_id,
-                        uri,
-                        flag,
-                    ))
-                    .await
+                    txs.send(Event::KnownDevicesChanged(account_id, devices))
+                        .await
                 });
                 true
             },
-        );
+        ));
 
-        let ten_millis = time::Duration::from_millis(10);
-        loop {
-            thread::sleep(ten_millis);
-            if stop.load(Ordering::Relaxed) {
-                break;
-            }
-        }
-
-        Ok(())
-    }
+        let mr = MatchRule::new_signal(config.config_manager_interface.clone(), "audioMeter");
+        let txs = tx.clone();
+        matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
+            move |_, (_id, level): (String, f64)| {
+                let mut txs = txs.clone();
+                tokio::spawn(async move { txs.send(Event::AudioMeter(level)).await });
+                true
+            },
+        ));
 
-    /**
-     * Asynchronously lookup a name
-     * @param account
-     * @param name_service
-     * @param name
-     * @return if dbus is ok
-     */
-    pub fn lookup_name(account: &String, name_service: &String, name: &String) -> bool {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let result: Result<(bool,), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "lookupName",
-            (account, name_service, name),
+        let mr = MatchRule::new_signal(
+            config.call_manager_interface.clone(),
+            "mediaNegotiationStatus",
         );
-        if result.is_ok() {
-            let result = result.unwrap().0;
-            return result;
-        }
-        false
-    }
+        let txs = tx.clone();
+        matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
+            move |_, (call_id, event, media): (String, String, Vec<HashMap<String, String>>)| {
+                let mut txs = txs.clone();
+                tokio::spawn(async move {
+                    txs.send(Event::MediaNegotiationStatus {
+                        call_id,
+                        event,
+                        media: MediaAttribute::from_maps(&media),
+                    })
+                    .await
+                });
+                true
+            },
+        ));
 
-    /**
-     * Asynchronously lookup an address
-     * @param account
-     * @param name_service
-     * @param address
-     * @return if dbus is ok
-     */
-    pub fn lookup_address(account: &String, name_service: &String, address: &String) -> bool {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
+        let mr = MatchRule::new_signal(
+            config.call_manager_interface.clone(),
+            "mediaChangeRequested",
         );
-        let result: Result<(bool,), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "lookupAddress",
-            (account, name_service, address),
+        let txs = tx.clone();
+        matches.push(
+            conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
+                move |_,
+                      (account_id, call_id, media): (
+                    String,
+                    String,
+                    Vec<HashMap<String, String>>,
+                )| {
+                    let mut txs = txs.clone();
+                    tokio::spawn(async move {
+                        txs.send(Event::MediaChangeRequested {
+                            account_id,
+                            call_id,
+                            media: MediaAttribute::from_maps(&media),
+                        })
+                        .await
+                    });
+                    true
+                },
+            ),
         );
-        if result.is_ok() {
-            let result = result.unwrap().0;
-            return result;
-        }
-        false
-    }
-
-    // Helpers
 
-    pub fn is_hash(string: &String) -> bool {
-        if string.len() != 40 {
-            return false;
-        }
-        for i in 0..string.len() {
-            if "0123456789abcdef".find(string.as_bytes()[i] as char) == None {
-                return false;
-            }
-        }
-        true
+        let mr = MatchRule::new_signal(
+            config.config_manager_interface.clone(),
+            "conversationMemberEvent",
+        );
+        let txs = tx.clone();
+        matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
+            move |_,
+                  (account_id, conversation_id, member_uri, event): (
+                String,
+                String,
+                String,
+                i32,
+            )| {
+                let mut txs = txs.clone();
+                tokio::spawn(async move {
+                    txs.send(Event::ConversationMemberEvent {
+                        account_id,
+                        conversation_id,
+                        member_uri,
+                        event,
+                    })
+                    .await
+                });
+                true
+            },
+        ));
+
+        let mr = MatchRule::new_signal(
+            config.config_manager_interface.clone(),
+            "composingStatusChanged",
+        );
+        let txs = tx.clone();
+        matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
+            move |_, (account_id, conversation_id, from, status): (String, String, String, i32)| {
+                let mut txs = txs.clone();
+                tokio::spawn(async move {
+                    txs.send(Event::ComposingStatusChanged {
+                        account_id,
+                        conversation_id,
+                        from,
+                        status,
+                    })
+                    .await
+                });
+                true
+            },
+        ));
+
+        // Swarm messages (s s s s i) or legacy text messages (s t s i)
+        let mr = MatchRule::new_signal(
+            config.config_manager_interface.clone(),
+            "accountMessageStatusChanged",
+        );
+        let txs = tx.clone();
+        matches.push(
+            conn.add_match(mr)
+                .await
+                .map_err(Jami::bus_error)?
+                .msg_cb(move |msg| {
+                    let event = if let Ok((account_id, conversation_id, peer, message_id, status)) =
+                        msg.read5::<String, String, String, String, i32>()
+                    {
+                        Event::MessageStatusChanged {
+                            account_id,
+                            conversation_id,
+                            peer,
+                            message_id,
+                            status,
+                        }
+                    } else if let Ok((account_id, message_id, peer, status)) =
+                        msg.read4::<String, u64, String, i32>()
+                    {
+                        Event::TextMessageStatusChanged {
+                            account_id,
+                            message_id,
+                            peer,
+                            status: MessageStatus::from(status),
+                        }
+                    } else {
+                        return true;
+                    };
+                    let mut txs = txs.clone();
+                    tokio::spawn(async move { txs.send(event).await });
+                    true
+                }),
+        );
+
+        let mr = MatchRule::new_signal(
+            config.presence_manager_interface.clone(),
+            "newBuddyNotification",
+        );
+        let txs = tx.clone();
+        matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
+            move |_, (account_id, uri, flag, _): (String, String, bool, String)| {
+                let mut txs = txs.clone();
+                tokio::spawn(async move {
+                    txs.send(Event::MemberPresenceChanged(
+                        account_id,
+                        uri,
+                        flag,
+                    ))
+                    .await
+                });
+                true
+            },
+        ));
+
+        // Must be the last match, as only the first matching rule receives a signal.
+        if options.forward_unknown {
+            // Parent of the objects of the daemon, e.g. /cx/ring/Ring
+            let config_manager_path = config.config_manager_path.clone();
+            let prefix = match config_manager_path.rfind('/') {
+                Some(end) if end > 0 => config_manager_path[..end].to_string(),
+                _ => config_manager_path,
+            };
+            let mr = MatchRule::new()
+                .with_type(MessageType::Signal)
+                .with_namespaced_path(prefix);
+            let txs = tx.clone();
+            matches.push(
+                conn.add_match(mr)
+                    .await
+                    .map_err(Jami::bus_error)?
+                    .msg_cb(move |msg| {
+                        let interface = msg.interface().map(|i| i.to_string()).unwrap_or_default();
+                        let member = msg.member().map(|m| m.to_string()).unwrap_or_default();
+                        let body = msg.get_items();
+                        let mut txs = txs.clone();
+                        tokio::spawn(async move {
+                            txs.send(Event::Unknown {
+                                interface,
+                                member,
+                                body,
+                            })
+                            .await
+                        });
+                        true
+                    }),
+            );
+        }
+
+        if options.reconnect.is_some() {
+            // The daemon restarts without the bus closing the connection
+            let mr = MatchRule::new_signal("org.freedesktop.DBus", "NameOwnerChanged");
+            let service = config.service.clone();
+            let txs = tx.clone();
+            let owner_gap = gap.clone();
+            let owner_service = service.clone();
+            matches.push(conn.add_match(mr).await.map_err(Jami::bus_error)?.cb(
+                move |_, (name, _, new_owner): (String, String, String)| {
+                    if name != owner_service {
+                        return true;
+                    }
+                    let event = if new_owner.is_empty() {
+                        if owner_gap.swap(true, Ordering::Relaxed) {
+                            return true;
+                        }
+                        Event::DaemonDisconnected(format!("{} left the bus", owner_service))
+                    } else {
+                        if !owner_gap.swap(false, Ordering::Relaxed) {
+                            return true;
+                        }
+                        Event::DaemonReconnected
+                    };
+                    let mut txs = txs.clone();
+                    tokio::spawn(async move { txs.send(event).await });
+                    true
+                },
+            ));
+            *attempt = 0;
+            // Else NameOwnerChanged will tell when it starts
+            let proxy = nonblock::Proxy::new(
+                "org.freedesktop.DBus",
+                "/org/freedesktop/DBus",
+                bus::call_timeout(),
+                conn.clone(),
+            );
+            let owned: Result<(bool,), _> = proxy
+                .method_call("org.freedesktop.DBus", "NameHasOwner", (service,))
+                .await;
+            if owned.map(|(owned,)| owned).unwrap_or(false) && gap.swap(false, Ordering::Relaxed) {
+                let mut txs = tx.clone();
+                let _ = txs.send(Event::DaemonReconnected).await;
+            }
+        }
+
+        // Removals detected by calls, not signaled by the daemon
+        let (removals_tx, mut removals) = mpsc::unbounded_channel();
+        REMOVAL_LISTENERS.lock().unwrap().push(removals_tx);
+        loop {
+            tokio::select! {
+                _ = stop.stopped() => break,
+                reason = &mut lost => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::ConnectionAborted,
+                        format!("Lost connection to D-Bus: {}", reason.unwrap_or_default()),
+                    ));
+                }
+                Some((account_id, conversation_id)) = removals.recv() => {
+                    if Jami::announce_removal(&account_id, &conversation_id) {
+                        let mut txs = tx.clone();
+                        tokio::spawn(async move {
+                            txs.send(Event::ConversationRemoved(account_id, conversation_id))
+                                .await
+                        });
+                    }
+                }
+            }
+        }
+
+        // Together, to not wait for each reply
+        let removals: Vec<_> = matches
+            .into_iter()
+            .map(|msg_match| {
+                let conn = conn.clone();
+                tokio::spawn(async move { conn.remove_match(msg_match.token()).await })
+            })
+            .collect();
+        for removal in removals {
+            let _ = removal.await;
+        }
+        Ok(())
+    }
+
+    // Error of handle_events when the bus can't be reached
+    fn bus_error(e: dbus::Error) -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::NotConnected, e.to_string())
+    }
+
+    /**
+     * Bridge for other processes (e.g. a Python script over stdio): run the event
+     * loop, write each event as a JSON line to writer (cf jsonl::event_to_json) and
+     * execute the JSON commands read from reader (cf jsonl::Command)
+     * @param reader    Commands, one JSON object per line
+     * @param writer    Where events and replies are written
+     * @param stop      Set to true to stop, set when the reader ends
+     */
+    #[cfg(feature = "jsonl")]
+    pub async fn run_jsonl<R, W>(reader: R, writer: W, stop: Arc<AtomicBool>) -> std::io::Result<()>
+    where
+        R: std::io::BufRead + Send + 'static,
+        W: std::io::Write + Send + 'static,
+    {
+        jsonl::run(reader, writer, stop).await
+    }
+
+    /**
+     * Asynchronously lookup a name
+     * @param account
+     * @param name_service
+     * @param name
+     * @return if dbus is ok (false on any error, cf try_lookup_name)
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_lookup_name")]
+    pub fn lookup_name(account: &str, name_service: &str, name: &str) -> bool {
+        Jami::try_lookup_name(account, name_service, name).unwrap_or_default()
+    }
+
+    /**
+     * Asynchronously lookup a name, reporting errors
+     * @param account
+     * @param name_service
+     * @param name
+     * @return if dbus is ok
+     */
+    pub fn try_lookup_name(
+        account: &str,
+        name_service: &str,
+        name: &str,
+    ) -> Result<bool, JamiError> {
+        JamiClient::shared()?.try_lookup_name(account, name_service, name)
+    }
+
+    /**
+     * Asynchronously lookup an address
+     * @param account
+     * @param name_service
+     * @param address
+     * @return if dbus is ok (false on any error, cf try_lookup_address)
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_lookup_address")]
+    pub fn lookup_address(account: &str, name_service: &str, address: &str) -> bool {
+        Jami::try_lookup_address(account, name_service, address).unwrap_or_default()
+    }
+
+    /**
+     * Asynchronously lookup an address, reporting errors
+     * @param account
+     * @param name_service
+     * @param address
+     * @return if dbus is ok
+     */
+    pub fn try_lookup_address(
+        account: &str,
+        name_service: &str,
+        address: &str,
+    ) -> Result<bool, JamiError> {
+        JamiClient::shared()?.try_lookup_address(account, name_service, address)
+    }
+
+    // Helpers
+
+    /**
+     * Extract a peer from a share link or QR payload (cf Account::share_uri)
+     * @param link  https://jami.net/?uri=<id>, jami:<id> or <id>
+     * @return the bare hash or registered name
+     */
+    pub fn parse_share_uri(link: &str) -> Option<String> {
+        uri::parse_share_uri(link)
+    }
+
+    /**
+     * Get the version of the daemon
+     * @return the version
+     */
+    pub fn try_version() -> Result<String, JamiError> {
+        let (version,): (String,) = JamiClient::shared()?.configuration("getVersion", ())?;
+        Ok(version)
+    }
+
+    /**
+     * Get the features supported by the daemon, from its version
+     * @return the capabilities
+     */
+    pub fn capabilities() -> Capabilities {
+        Jami::capabilities_with(&CapabilityThresholds::default())
+    }
+
+    /**
+     * Same as capabilities(), for forks with their own versions
+     * @param thresholds    First version of each feature
+     * @return the capabilities
+     */
+    pub fn capabilities_with(thresholds: &CapabilityThresholds) -> Capabilities {
+        Capabilities::from_version(&Jami::try_version().unwrap_or_default(), thresholds)
+    }
+
+    /**
+     * @param string    String to check
+     * @return if string is an infohash (40 lowercase hex chars, no scheme)
+     */
+    pub fn is_hash(string: &str) -> bool {
+        uri::is_hash(string)
     }
 
     /**
@@ -443,583 +1357,2745 @@ impl Jami {
      * @param main_info path or alias
      * @param password
      * @param from_archive if main_info is a path
+     * @return the id of the new account ("" on any error, cf AccountBuilder::build)
      */
+    #[deprecated(note = "errors are hidden, use AccountBuilder::build")]
     pub fn add_account(main_info: &str, password: &str, import_type: ImportType) -> String {
-        let mut details: HashMap<&str, &str> = HashMap::new();
-        if import_type == ImportType::BACKUP {
-            details.insert("Account.archivePath", main_info);
+        let builder = AccountBuilder::new_ring().password(password);
+        let result = if import_type == ImportType::BACKUP {
+            builder.from_backup(main_info).build()
         } else if import_type == ImportType::NETWORK {
-            details.insert("Account.archivePin", main_info);
+            builder.from_pin(main_info).build()
         } else {
-            details.insert("Account.alias", main_info);
-        }
-        details.insert("Account.type", "RING");
-        details.insert("Account.archivePassword", password);
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let result: Result<(String,), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "addAccount",
-            (details,),
-        );
-        if result.is_ok() {
-            let result = result.unwrap().0;
-            info!("New account: {:?}", result);
-            return result;
-        }
+            builder.alias(main_info).build()
+        };
+        result.unwrap_or_default()
+    }
+
+    /**
+     * Import an account from an archive in memory (e.g. downloaded from a cloud backup).
+     * The archive is written to a temporary file only readable by the user, which is
+     * removed once the daemon has loaded it (or on failure).
+     * @param archive   Content of the archive
+     * @param password  Password of the archive
+     * @return the id of the new account
+     */
+    pub async fn add_account_from_archive_bytes(
+        archive: &[u8],
+        password: &str,
+    ) -> Result<String, JamiError> {
+        // NamedTempFile is created with 0600 and removed when dropped
+        let mut file = tempfile::Builder::new()
+            .prefix("jami-archive-")
+            .tempfile()
+            .map_err(|e| JamiError::OperationFailed(e.to_string()))?;
+        file.write_all(archive)
+            .and_then(|_| file.flush())
+            .map_err(|e| JamiError::OperationFailed(e.to_string()))?;
+        let path = file.path().to_string_lossy().to_string();
+
+        let (resource, conn) = bus::connect_async().map_err(JamiError::BusConnection)?;
+        let work = async {
+            let (state_tx, mut state_rx) = tokio::sync::mpsc::unbounded_channel();
+            let mr = MatchRule::new_signal(
+                bus::config().config_manager_interface,
+                "registrationStateChanged",
+            );
+            let ic = conn
+                .add_match(mr)
+                .await
+                .map_err(|e| JamiError::method_call("AddMatch", e))?
+                .cb(
+                    move |_, (account_id, state, _, _): (String, String, i32, String)| {
+                        let _ = state_tx.send((account_id, state));
+                        true
+                    },
+                );
+
+            let result = AccountBuilder::new_ring()
+                .password(password)
+                .from_backup(&path)
+                .build();
+            if let Ok(id) = &result {
+                // The archive is read asynchronously by the daemon
+                let mut watch = CreationWatch::new(id);
+                let wait = async {
+                    while let Some((account_id, state)) = state_rx.recv().await {
+                        if account_id == *id && state != "INITIALIZING" {
+                            return;
+                        }
+                    }
+                };
+                tokio::select! {
+                    _ = tokio::time::timeout(Duration::from_secs(60), wait) => {}
+                    _ = &mut watch.aborted => {}
+                }
+            }
+            let _ = conn.remove_match(ic.token()).await;
+            result
+        };
+
+        let result = tokio::select! {
+            err = resource => Err(JamiError::ConnectionLost(err.to_string())),
+            result = work => result,
+        };
+        drop(file);
+        result
+    }
+
+    /**
+     * Cancel the creation of an account (e.g. the user leaves the onboarding): the
+     * account is removed, build_with_progress ends with Failed("aborted") and
+     * add_account_from_archive_bytes stops waiting. Also removes an account
+     * already created, and does nothing if the account is already removed
+     * @param account_id    Id returned by add_account or AccountBuilder
+     */
+    pub fn abort_account_creation(account_id: &str) -> Result<(), JamiError> {
+        validate::account_id("account_id", account_id)?;
+        CreationWatch::abort(account_id);
+        let client = JamiClient::shared()?;
+        client.configuration("removeAccount", (account_id,))
+    }
 
-        String::new()
+    /**
+     * Get the default details of a new account
+     * @param account_type "RING" or "SIP"
+     * @return the template
+     */
+    pub fn try_get_account_template(
+        account_type: &str,
+    ) -> Result<HashMap<String, String>, JamiError> {
+        let (template,): (HashMap<String, String>,) =
+            JamiClient::shared()?.configuration("getAccountTemplate", (account_type,))?;
+        Ok(template)
+    }
+
+    /**
+     * Describe the details of a type of account, e.g. for a settings editor
+     * @param account_type  RING or SIP
+     * @return the details of the template with their kind and default value
+     */
+    pub fn account_detail_schema(account_type: &str) -> Vec<DetailField> {
+        DetailField::from_template(
+            &Jami::try_get_account_template(account_type).unwrap_or_default(),
+        )
     }
 
     /**
      * Get current ring accounts
-     * @return current accounts
+     * @return current accounts, empty on any error (cf try_get_account_list)
      */
+    #[deprecated(note = "errors are hidden, use Jami::try_get_account_list")]
     pub fn get_account_list() -> Vec<Account> {
-        let mut account_list: Vec<Account> = Vec::new();
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let result: Result<(Vec<String>,), _> =
-            proxy.method_call("cx.ring.Ring.ConfigurationManager", "getAccountList", ());
-        if result.is_err() {
-            return account_list;
-        }
-        let accounts = result.unwrap().0;
-        for account in accounts {
-            account_list.push(Jami::get_account(&*account));
-        }
-        account_list
+        Jami::try_get_account_list().unwrap_or_default()
+    }
+
+    /**
+     * Get current ring accounts, reporting errors, to tell a missing daemon
+     * from no account
+     * @return current accounts
+     */
+    pub fn try_get_account_list() -> Result<Vec<Account>, JamiError> {
+        JamiClient::shared()?.try_get_account_list()
     }
 
     /**
      * Build a new account with an id from the daemon
      * @param id the account id to build
-     * @return the account retrieven
-     */
-    pub fn get_account(id: &str) -> Account {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let result: Result<(HashMap<String, String>,), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "getAccountDetails",
-            (id,),
-        );
-        if result.is_err() {
-            return Account::null();
-        }
-        let details = result.unwrap().0;
-
-        let mut account = Account::null();
-        account.id = id.to_owned();
-        for detail in details {
-            match detail {
-                (key, value) => {
-                    if key == "Account.enable" {
-                        account.enabled = value == "true";
-                    }
-                    if key == "Account.alias" {
-                        account.alias = value.clone();
-                    }
-                    if key == "Account.username" {
-                        account.hash = value.clone().replace("ring:", "");
-                    }
-                    if key == "Account.registeredName" {
-                        account.registered_name = value.clone();
-                    }
-                }
-            }
-        }
-        account
+     * @return the account retrieven (None if the daemon doesn't know it or on error)
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_get_account")]
+    pub fn get_account(id: &str) -> Option<Account> {
+        Jami::try_get_account(id).ok().flatten()
     }
 
     /**
-     * Remove an account
-     * @param id the account id to remove
+     * Build a new account with an id from the daemon, reporting errors
+     * @param id the account id to build
+     * @return the account retrieven (None if the daemon doesn't know it)
      */
-    pub fn rm_account(id: &str) {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let _: Result<(), _> =
-            proxy.method_call("cx.ring.Ring.ConfigurationManager", "removeAccount", (id,));
+    pub fn try_get_account(id: &str) -> Result<Option<Account>, JamiError> {
+        JamiClient::shared()?.try_get_account(id)
     }
 
     /**
-     * Get account details
-     * @param id the account id to build
+     * Get account details, typed
+     * @param id the account id
      * @return the account details
      */
-    pub fn get_account_details(id: &str) -> HashMap<String, String> {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let result: Result<(HashMap<String, String>,), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "getAccountDetails",
-            (id,),
-        );
-        if result.is_ok() {
-            let result = result.unwrap().0;
-            return result;
-        }
+    pub fn try_get_typed_account_details(id: &str) -> Result<AccountDetails, JamiError> {
+        Ok(AccountDetails::from_map(Jami::try_get_account_details(id)?))
+    }
 
-        HashMap::new()
+    /**
+     * Set account details, typed
+     * @param id        the account id
+     * @param details   the new details
+     */
+    pub fn try_set_typed_account_details(
+        id: &str,
+        details: AccountDetails,
+    ) -> Result<(), JamiError> {
+        Jami::try_set_account_details(id, details.into_map())
     }
 
     /**
-     * Get account details
-     * @param id the account id to build
+     * Get volatile account details (registration status, registered name, etc.)
+     * @param id the account id
+     * @return the volatile details
      */
-    pub fn set_account_details(id: &str, details: HashMap<String, String>) {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let _: Result<(), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "setAccountDetails",
-            (id, details),
-        );
+    pub fn try_get_volatile_account_details(
+        id: &str,
+    ) -> Result<HashMap<String, String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        JamiClient::shared()?.try_get_volatile_account_details(id)
     }
 
     /**
-     * Subscribe to a member presence
-     * @param id the account id to build
-     * @param uri to subscribe
-     * @param flag true to subscribe else stop
+     * Update the profile of an account, shared with contacts
+     * @param id            the account id
+     * @param display_name  new display name
+     * @param avatar        path of the avatar if flag is 0, base64 content if flag is 1
+     * @param file_type     type of the avatar (e.g. "png")
+     * @param flag          0: avatar is a path, 1: avatar is base64, 2: remove the avatar
      */
-    pub fn subscribe_presence(id: &str, uri: &str, flag: bool) {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/PresenceManager",
-            Duration::from_millis(5000),
-        );
-        let _: Result<(), _> = proxy.method_call(
-            "cx.ring.Ring.PresenceManager",
-            "subscribeBuddy",
-            (id, uri, flag),
-        );
+    pub fn try_update_profile(
+        id: &str,
+        display_name: &str,
+        avatar: &str,
+        file_type: &str,
+        flag: i32,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        JamiClient::shared()?
+            .configuration("updateProfile", (id, display_name, avatar, file_type, flag))
     }
 
     /**
-     * Add a new contact
-     * @param id        Account id
-     * @param uri       Uri of the contact
+     * Get the avatar of an account, from its profile (jami::app_data + id/profile.vcf)
+     * @param id        the account id
+     * @return the raw image, if any
      */
-    pub fn add_contact(id: &String, uri: &String) {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let _: Result<(), _> =
-            proxy.method_call("cx.ring.Ring.ConfigurationManager", "addContact", (id, uri));
+    pub fn get_account_avatar(id: &str) -> Option<Vec<u8>> {
+        Account::new(id)
+            .profile_path()
+            .and_then(|path| Profile::load(&path))
+            .and_then(|profile| profile.avatar_bytes())
     }
 
     /**
-     * Get trusts requests from an account
-     * @param id        Account id
-     * @return the list of trusts requests senders
+     * Change the avatar of an account, the display name is kept.
+     * Images bigger than 512 KiB are shrunk to 400x400 with the image feature, refused without
+     * @param id        the account id
+     * @param image     PNG, JPEG, GIF or WebP image
      */
-    pub fn get_trust_requests(id: &String) -> Vec<String> {
-        let mut res = Vec::new();
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let result: Result<(Vec<HashMap<String, String>>,), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "getTrustRequests",
-            (id,),
-        );
-        if result.is_ok() {
-            let result = result.unwrap().0;
-            for tr in result {
-                if tr.contains_key("from") {
-                    res.push(tr.get("from").unwrap().clone());
-                }
+    pub fn set_account_avatar(id: &str, image: Vec<u8>) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        #[cfg(feature = "image")]
+        let image = if image.len() > MAX_AVATAR_BYTES {
+            Profile::shrink_image(&image, AVATAR_SIZE).ok_or_else(|| {
+                JamiError::InvalidArgument(String::from("image"), String::from("unreadable image"))
+            })?
+        } else {
+            image
+        };
+        if image.len() > MAX_AVATAR_BYTES {
+            return Err(JamiError::InvalidArgument(
+                String::from("image"),
+                format!("bigger than {} bytes", MAX_AVATAR_BYTES),
+            ));
+        }
+        let file_type = match Profile::mimetype_from_bytes(&image) {
+            Some(mimetype) => mimetype.trim_start_matches("image/"),
+            None => {
+                return Err(JamiError::InvalidArgument(
+                    String::from("image"),
+                    String::from("unknown image format"),
+                ))
             }
+        };
+        let display_name = Account::new(id)
+            .profile_path()
+            .and_then(|path| Profile::load(&path))
+            .map(|profile| profile.display_name)
+            .unwrap_or_default();
+
+        let client = JamiClient::shared()?;
+        client.configuration(
+            "updateProfile",
+            (id, display_name, base64::encode(&image), file_type, 1),
+        )
+    }
+
+    /**
+     * Get devices linked to an account
+     * @param id the account id
+     * @return device id -> device name
+     */
+    pub fn try_get_known_devices(id: &str) -> Result<HashMap<String, String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (devices,): (HashMap<String, String>,) =
+            JamiClient::shared()?.configuration("getKnownRingDevices", (id,))?;
+        Ok(devices)
+    }
+
+    /**
+     * Register a name on the name server. Result is sent by nameRegistrationEnded
+     * @param id        the account id
+     * @param password  password of the account
+     * @param name      name to register
+     * @return if the registration started
+     */
+    pub fn try_register_name(id: &str, password: &str, name: &str) -> Result<bool, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (started,): (bool,) =
+            JamiClient::shared()?.configuration("registerName", (id, password, name))?;
+        Ok(started)
+    }
+
+    /**
+     * Export an account on the DHT to link a new device. The pin is sent by exportOnRingEnded
+     * @param id        the account id
+     * @param password  password of the account
+     * @return if the export started
+     */
+    pub fn try_export_on_ring(id: &str, password: &str) -> Result<bool, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (started,): (bool,) =
+            JamiClient::shared()?.configuration("exportOnRing", (id, password))?;
+        Ok(started)
+    }
+
+    /**
+     * Export an account to an archive
+     * @param id        the account id
+     * @param path      destination of the archive
+     * @param password  password of the account ("" if none)
+     * @return if successful
+     */
+    pub fn try_export_to_file(id: &str, path: &str, password: &str) -> Result<bool, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::path("path", path)?;
+        let scheme = if password.is_empty() { "" } else { "password" };
+        let (exported,): (bool,) =
+            JamiClient::shared()?.configuration("exportToFile", (id, path, scheme, password))?;
+        Ok(exported)
+    }
+
+    /**
+     * Revoke a device. Result is sent by deviceRevocationEnded
+     * @param id        the account id
+     * @param device_id device to revoke
+     * @param password  password of the account ("" if none)
+     * @return if the revocation started
+     */
+    pub fn try_revoke_device(id: &str, device_id: &str, password: &str) -> Result<bool, JamiError> {
+        validate::account_id("account_id", id)?;
+        let scheme = if password.is_empty() { "" } else { "password" };
+        let (started,): (bool,) = JamiClient::shared()?
+            .configuration("revokeDevice", (id, device_id, scheme, password))?;
+        Ok(started)
+    }
+
+    /**
+     * Register a name and wait for nameRegistrationEnded
+     * @param id        the account id
+     * @param password  password of the account
+     * @param name      name to register
+     * @param timeout   max time to wait for nameRegistrationEnded
+     */
+    pub async fn register_name_await(
+        id: &str,
+        password: &str,
+        name: &str,
+        timeout: Duration,
+    ) -> Result<(), NameRegistrationError> {
+        validate::account_id("account_id", id)?;
+        let (resource, conn) = bus::connect_async().map_err(JamiError::BusConnection)?;
+
+        let work = async {
+            let (ended_tx, mut ended_rx) = tokio::sync::mpsc::unbounded_channel();
+            let account = id.to_string();
+            let mr = MatchRule::new_signal(
+                bus::config().config_manager_interface,
+                "nameRegistrationEnded",
+            );
+            let ic = conn
+                .add_match(mr)
+                .await
+                .map_err(|e| JamiError::method_call("AddMatch", e))?
+                .cb(
+                    move |_, (account_id, state, _name): (String, i32, String)| {
+                        if account_id == account {
+                            let _ = ended_tx.send(state);
+                        }
+                        true
+                    },
+                );
+
+            let proxy = nonblock::Proxy::new(
+                bus::config().service,
+                bus::config().config_manager_path,
+                timeout,
+                conn.clone(),
+            );
+            let result: Result<(bool,), _> = calllog::logged_call_async(
+                &proxy,
+                &bus::config().config_manager_interface,
+                "registerName",
+                (id, password, name),
+            )
+            .await;
+            // States from the daemon's NameDirectory::RegistrationResponse
+            let result = match result {
+                Ok((true,)) => match tokio::time::timeout(timeout, ended_rx.recv()).await {
+                    Ok(Some(0)) => Ok(()),
+                    Ok(Some(1)) => Err(NameRegistrationError::WrongPassword),
+                    Ok(Some(2)) => Err(NameRegistrationError::Invalid(String::from(
+                        "refused by the name server",
+                    ))),
+                    Ok(Some(3)) => Err(NameRegistrationError::AlreadyTaken),
+                    Ok(Some(4)) => Err(NameRegistrationError::Network),
+                    Ok(Some(state)) => {
+                        Err(NameRegistrationError::Failed(JamiError::OperationFailed(
+                            format!("registerName failed with state {}", state),
+                        )))
+                    }
+                    _ => Err(NameRegistrationError::Failed(JamiError::Timeout)),
+                },
+                Ok((false,)) => Err(NameRegistrationError::Failed(JamiError::OperationFailed(
+                    String::from("registerName refused"),
+                ))),
+                Err(e) => Err(NameRegistrationError::Failed(JamiError::method_call(
+                    "registerName",
+                    e,
+                ))),
+            };
+            let _ = conn.remove_match(ic.token()).await;
+            result
+        };
+
+        tokio::select! {
+            err = resource => Err(NameRegistrationError::Failed(JamiError::ConnectionLost(err.to_string()))),
+            result = work => result,
         }
-        return res;
     }
 
     /**
-     * Send a trust request to someone
-     * @param id        Account id
-     * @param to        Contact uri
-     * @param payloads  VCard
+     * Export an account on the DHT and wait for the pin
+     * @param id        the account id
+     * @param password  password of the account
+     * @param timeout   max time to wait for exportOnRingEnded
+     * @return the pin to enter on the new device
+     */
+    pub async fn export_on_ring_await(
+        id: &str,
+        password: &str,
+        timeout: Duration,
+    ) -> Result<String, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (resource, conn) = bus::connect_async().map_err(JamiError::BusConnection)?;
+
+        let work = async {
+            let (ended_tx, mut ended_rx) = tokio::sync::mpsc::unbounded_channel();
+            let account = id.to_string();
+            let mr =
+                MatchRule::new_signal(bus::config().config_manager_interface, "exportOnRingEnded");
+            let ic = conn
+                .add_match(mr)
+                .await
+                .map_err(|e| JamiError::method_call("AddMatch", e))?
+                .cb(move |_, (account_id, status, pin): (String, i32, String)| {
+                    if account_id == account {
+                        let _ = ended_tx.send((status, pin));
+                    }
+                    true
+                });
+
+            let proxy = nonblock::Proxy::new(
+                bus::config().service,
+                bus::config().config_manager_path,
+                timeout,
+                conn.clone(),
+            );
+            let result: Result<(bool,), _> = calllog::logged_call_async(
+                &proxy,
+                &bus::config().config_manager_interface,
+                "exportOnRing",
+                (id, password),
+            )
+            .await;
+            let result = match result {
+                Ok((true,)) => match tokio::time::timeout(timeout, ended_rx.recv()).await {
+                    Ok(Some((0, pin))) => Ok(pin),
+                    Ok(Some((1, _))) => {
+                        Err(JamiError::OperationFailed(String::from("Wrong password")))
+                    }
+                    Ok(Some((status, _))) => Err(JamiError::OperationFailed(format!(
+                        "exportOnRing failed with status {}",
+                        status
+                    ))),
+                    _ => Err(JamiError::Timeout),
+                },
+                Ok((false,)) => Err(JamiError::OperationFailed(String::from(
+                    "exportOnRing refused",
+                ))),
+                Err(e) => Err(JamiError::method_call("exportOnRing", e)),
+            };
+            let _ = conn.remove_match(ic.token()).await;
+            result
+        };
+
+        tokio::select! {
+            err = resource => Err(JamiError::ConnectionLost(err.to_string())),
+            result = work => result,
+        }
+    }
+
+    /**
+     * Get credentials of a SIP account
+     * @param id the account id
+     * @return the credentials
+     */
+    pub fn try_get_credentials(id: &str) -> Result<Vec<HashMap<String, String>>, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (credentials,): (Vec<HashMap<String, String>>,) =
+            JamiClient::shared()?.configuration("getCredentials", (id,))?;
+        Ok(credentials)
+    }
+
+    /**
+     * Set credentials of a SIP account
+     * @param id            the account id
+     * @param credentials   new credentials
+     */
+    pub fn try_set_credentials(
+        id: &str,
+        credentials: Vec<HashMap<String, String>>,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        JamiClient::shared()?.configuration("setCredentials", (id, credentials))
+    }
+
+    /**
+     * Remove an account (errors are ignored, cf try_rm_account)
+     * @param id the account id to remove
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_rm_account")]
+    pub fn rm_account(id: &str) {
+        let _ = Jami::try_rm_account(id);
+    }
+
+    /**
+     * Remove an account, reporting errors
+     * @param id the account id to remove
+     */
+    pub fn try_rm_account(id: &str) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        JamiClient::shared()?.configuration("removeAccount", (id,))
+    }
+
+    /**
+     * Get account details
+     * @param id the account id to build
+     * @return the account details (empty on any error, cf try_get_account_details)
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_get_account_details")]
+    pub fn get_account_details(id: &str) -> HashMap<String, String> {
+        Jami::try_get_account_details(id).unwrap_or_default()
+    }
+
+    /**
+     * Get account details, reporting errors
+     * @param id the account id to build
+     * @return the account details
+     */
+    pub fn try_get_account_details(id: &str) -> Result<HashMap<String, String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        JamiClient::shared()?.try_get_account_details(id)
+    }
+
+    /**
+     * Get account details (errors are ignored, cf try_set_account_details)
+     * @param id the account id to build
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_set_account_details")]
+    pub fn set_account_details(id: &str, details: HashMap<String, String>) {
+        let _ = Jami::try_set_account_details(id, details);
+    }
+
+    /**
+     * Get account details, reporting errors
+     * @param id the account id to build
+     */
+    pub fn try_set_account_details(
+        id: &str,
+        details: HashMap<String, String>,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        JamiClient::shared()?.try_set_account_details(id, details)
+    }
+
+    /**
+     * Change one detail of an account, other details are kept as the daemon knows them
+     * @param id        the account id
+     * @param key       the detail to change (cf details_keys)
+     * @param value     the new value
+     */
+    pub fn set_account_detail(id: &str, key: &str, value: &str) -> Result<(), JamiError> {
+        let mut changes = HashMap::new();
+        changes.insert(key.to_string(), value.to_string());
+        Jami::update_account_details(id, changes)
+    }
+
+    /**
+     * Change some details of an account in one call, other details are kept
+     * as the daemon knows them
+     * @param id        the account id
+     * @param changes   the details to change (cf details_keys)
+     */
+    pub fn update_account_details(
+        id: &str,
+        changes: HashMap<String, String>,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        let client = JamiClient::shared()?;
+        let (mut details,): (HashMap<String, String>,) =
+            client.configuration("getAccountDetails", (id,))?;
+        if details.is_empty() {
+            return Err(JamiError::InvalidArgument(
+                String::from("account_id"),
+                format!("unknown account {}", id),
+            ));
+        }
+        details.extend(changes);
+        client.configuration::<_, ()>("setAccountDetails", (id, details))
+    }
+
+    /**
+     * Give the push token of the device to the daemon (used by all accounts)
+     * @param token     Token from the push service ("" to disable)
+     * @param topic     Topic of the application ("" if unused)
+     */
+    pub fn set_push_notification_token(token: &str, topic: &str) -> Result<(), JamiError> {
+        let client = JamiClient::shared()?;
+        client.configuration::<_, ()>("setPushNotificationToken", (token,))?;
+        client.configuration::<_, ()>("setPushNotificationTopic", (topic,))
+    }
+
+    /**
+     * Set the TLS certificate used by an account
+     * @param id            Account id
+     * @param cert_path     Path of the certificate
+     * @param key_path      Path of the private key
+     * @param key_password  Password of the private key ("" if none)
+     */
+    pub fn try_set_tls_certificate(
+        id: &str,
+        cert_path: &str,
+        key_path: &str,
+        key_password: &str,
+    ) -> Result<(), JamiError> {
+        let mut changes = HashMap::new();
+        changes.insert(String::from("TLS.certificateFile"), cert_path.to_string());
+        changes.insert(String::from("TLS.privateKeyFile"), key_path.to_string());
+        changes.insert(String::from("TLS.password"), key_password.to_string());
+        Jami::update_account_details(id, changes)
+    }
+
+    /**
+     * Get the TLS certificate used by an account
+     * @param id            Account id
+     * @return (certificate path, private key path)
+     */
+    pub fn try_get_tls_certificate(id: &str) -> Result<(String, String), JamiError> {
+        let details = Jami::try_get_account_details(id)?;
+        Ok((
+            details
+                .get("TLS.certificateFile")
+                .cloned()
+                .unwrap_or_default(),
+            details
+                .get("TLS.privateKeyFile")
+                .cloned()
+                .unwrap_or_default(),
+        ))
+    }
+
+    /**
+     * Get the CA list used by an account
+     * @param id            Account id
+     * @return path of the CA list
+     */
+    pub fn try_get_tls_ca_list(id: &str) -> Result<String, JamiError> {
+        let details = Jami::try_get_account_details(id)?;
+        Ok(details
+            .get("TLS.certificateListFile")
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    /**
+     * Set the CA list used by an account
+     * @param id            Account id
+     * @param ca_list_path  Path of the CA list
+     */
+    pub fn try_set_tls_ca_list(id: &str, ca_list_path: &str) -> Result<(), JamiError> {
+        Jami::set_account_detail(id, "TLS.certificateListFile", ca_list_path)
+    }
+
+    /**
+     * Get the DHT proxy settings of an account
+     * @param id            Account id
+     * @return the proxy config
+     */
+    pub fn try_get_proxy_config(id: &str) -> Result<ProxyConfig, JamiError> {
+        Ok(ProxyConfig::from_details(&Jami::try_get_account_details(
+            id,
+        )?))
+    }
+
+    /**
+     * Get the connectivity of an account, for troubleshooting screens.
+     * Blocks up to 2 seconds to reach the name server
+     * @param id            Account id
+     * @return the status
+     */
+    pub fn account_network_status(id: &str) -> NetworkStatus {
+        let details = Jami::try_get_account_details(id).unwrap_or_default();
+        let volatile = Jami::try_get_volatile_account_details(id).unwrap_or_default();
+        let mut status = NetworkStatus::from_details(&details, &volatile);
+        let kind: AccountType = details
+            .get("Account.type")
+            .map(|t| t.parse().unwrap())
+            .unwrap_or_default();
+        // Only RING accounts use a name server
+        if kind == AccountType::Ring {
+            let name_server = details.get("RingNS.uri").cloned().unwrap_or_default();
+            status.nameserver_reachable =
+                account::ping_name_server(&name_server, Duration::from_millis(2000));
+        }
+        status
+    }
+
+    /**
+     * Set the DHT proxy settings of an account
+     * @param id            Account id
+     * @param config        New config
+     */
+    pub fn try_set_proxy_config(id: &str, config: &ProxyConfig) -> Result<(), JamiError> {
+        let mut changes = HashMap::new();
+        config.apply(&mut changes);
+        Jami::update_account_details(id, changes)
+    }
+
+    /**
+     * Enable or disable the DHT proxy
+     * @param id            Account id
+     * @param enabled       Use the proxy
+     * @param server        Proxy to use ("" to use the proxy list)
+     */
+    pub fn try_set_proxy_enabled(id: &str, enabled: bool, server: &str) -> Result<(), JamiError> {
+        let mut config = Jami::try_get_proxy_config(id)?;
+        config.enabled = enabled;
+        config.server = server.to_string();
+        Jami::try_set_proxy_config(id, &config)
+    }
+
+    /**
+     * Get if the DHT proxy is enabled
+     * @param id            Account id
+     * @return (enabled, server)
+     */
+    pub fn try_get_proxy_enabled(id: &str) -> Result<(bool, String), JamiError> {
+        let config = Jami::try_get_proxy_config(id)?;
+        Ok((config.enabled, config.server))
+    }
+
+    /**
+     * Validate a certificate for an account
+     * @param id            Account id
+     * @param certificate   Certificate to validate
+     * @return the validation results
+     */
+    pub fn try_validate_certificate(
+        id: &str,
+        certificate: &str,
+    ) -> Result<HashMap<String, String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (results,): (HashMap<String, String>,) =
+            JamiClient::shared()?.configuration("validateCertificate", (id, certificate))?;
+        Ok(results)
+    }
+
+    /**
+     * Subscribe to a member presence (errors are ignored, cf try_subscribe_presence)
+     * @param id the account id to build
+     * @param uri to subscribe
+     * @param flag true to subscribe else stop
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_subscribe_presence")]
+    pub fn subscribe_presence(id: &str, uri: &str, flag: bool) {
+        let _ = Jami::try_subscribe_presence(id, uri, flag);
+    }
+
+    /**
+     * Subscribe to a member presence, reporting errors
+     * @param id the account id to build
+     * @param uri to subscribe
+     * @param flag true to subscribe else stop
+     */
+    pub fn try_subscribe_presence(id: &str, uri: &str, flag: bool) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        JamiClient::shared()?.presence_manager("subscribeBuddy", (id, uri, flag))
+    }
+
+    /**
+     * Choose if the account advertises its online status. Messages are still
+     * received when it doesn't, the account just appears offline to its contacts
+     * @param id            Id of the account
+     * @param published     If the presence is published
+     */
+    pub fn set_presence_published(id: &str, published: bool) -> Result<(), JamiError> {
+        Jami::set_account_detail(id, details_keys::PRESENCE_ENABLED, &published.to_string())?;
+        let note = Jami::try_get_typed_account_details(id)?
+            .presence_note()
+            .unwrap_or_default()
+            .to_string();
+        Jami::publish_presence(id, published, &note)
+    }
+
+    /**
+     * Get if the account advertises its online status (cf set_presence_published)
+     * @param id            Id of the account
+     * @return if the presence is published
+     */
+    pub fn is_presence_published(id: &str) -> bool {
+        Jami::try_get_typed_account_details(id)
+            .ok()
+            .and_then(|details| details.presence_enabled())
+            .unwrap_or(false)
+    }
+
+    /**
+     * Set the status message sent with the presence
+     * @param id            Id of the account
+     * @param note          Status message, e.g. "In a meeting"
+     */
+    pub fn set_presence_note(id: &str, note: &str) -> Result<(), JamiError> {
+        Jami::set_account_detail(id, details_keys::PRESENCE_NOTE, note)?;
+        if Jami::is_presence_published(id) {
+            Jami::publish_presence(id, true, note)?;
+        }
+        Ok(())
+    }
+
+    fn publish_presence(id: &str, online: bool, note: &str) -> Result<(), JamiError> {
+        let client = JamiClient::shared()?;
+        client.presence_manager("publish", (id, online, note))
+    }
+
+    /**
+     * Check if a peer is reachable, by subscribing to its presence and waiting for
+     * it to be announced online. This is best-effort: the DHT can take a while to
+     * find a peer, so a false only means it did not answer before the timeout.
+     * A subscription created by the probe is removed before returning.
+     * @param account_id    Account to use
+     * @param peer_uri      Peer to probe (hash, with or without scheme)
+     * @param timeout       Max time to wait for the peer
+     * @return if the peer was seen online
+     */
+    pub async fn check_peer_reachable(account_id: &str, peer_uri: &str, timeout: Duration) -> bool {
+        if validate::account_id("account_id", account_id)
+            .and(validate::peer("uri", peer_uri))
+            .is_err()
+        {
+            return false;
+        }
+        let (resource, conn) = match bus::connect_async() {
+            Ok(session) => session,
+            Err(_) => return false,
+        };
+        let peer = uri::normalize_peer(peer_uri);
+
+        let work = async {
+            let proxy = nonblock::Proxy::new(
+                bus::config().service,
+                bus::config().presence_manager_path,
+                timeout,
+                conn.clone(),
+            );
+            // An existing subscription already knows the status and must be kept
+            let subscriptions: Vec<HashMap<String, String>> = calllog::logged_call_async(
+                &proxy,
+                &bus::config().presence_manager_interface,
+                "getSubscriptions",
+                (account_id,),
+            )
+            .await
+            .map(|(subscriptions,)| subscriptions)
+            .unwrap_or_default();
+            // Each subscription is {"Buddy": uri, "Status": "Online" or "Offline"}
+            let subscribed = subscriptions.iter().find(|buddy| {
+                buddy.get("Buddy").map(|uri| uri::normalize_peer(uri)) == Some(peer.clone())
+            });
+            if let Some(buddy) = subscribed {
+                if buddy.get("Status").map(|s| &**s) == Some("Online") {
+                    return true;
+                }
+            }
+
+            let (online_tx, mut online_rx) = tokio::sync::mpsc::unbounded_channel();
+            let (account, buddy) = (account_id.to_string(), peer.clone());
+            let mr = MatchRule::new_signal(
+                bus::config().presence_manager_interface,
+                "newBuddyNotification",
+            );
+            let ic = match conn.add_match(mr).await {
+                Ok(ic) => ic.cb(
+                    move |_, (account_id, from, online, _): (String, String, bool, String)| {
+                        if account_id == account && uri::normalize_peer(&from) == buddy {
+                            let _ = online_tx.send(online);
+                        }
+                        true
+                    },
+                ),
+                Err(_) => return false,
+            };
+
+            let created = subscribed.is_none();
+            let result: Result<(), _> = calllog::logged_call_async(
+                &proxy,
+                &bus::config().presence_manager_interface,
+                "subscribeBuddy",
+                (account_id, &*peer, true),
+            )
+            .await;
+            let online = result.is_ok()
+                && tokio::time::timeout(timeout, async {
+                    while let Some(online) = online_rx.recv().await {
+                        if online {
+                            return true;
+                        }
+                    }
+                    false
+                })
+                .await
+                .unwrap_or(false);
+
+            if created {
+                let _: Result<(), _> = calllog::logged_call_async(
+                    &proxy,
+                    &bus::config().presence_manager_interface,
+                    "subscribeBuddy",
+                    (account_id, &*peer, false),
+                )
+                .await;
+            }
+            let _ = conn.remove_match(ic.token()).await;
+            online
+        };
+
+        tokio::select! {
+            _ = resource => false,
+            online = work => online,
+        }
+    }
+
+    /**
+     * Add a new contact (errors are ignored, cf try_add_contact)
+     * @param id        Account id
+     * @param uri       Uri of the contact
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_add_contact")]
+    pub fn add_contact(id: &str, uri: &str) {
+        let _ = Jami::try_add_contact(id, uri);
+    }
+
+    /**
+     * Add a new contact, reporting errors
+     * @param id        Account id
+     * @param uri       Uri of the contact
+     */
+    pub fn try_add_contact(id: &str, uri: &str) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::peer("uri", uri)?;
+        JamiClient::shared()?.try_add_contact(id, uri)
+    }
+
+    /**
+     * Remove a contact
+     * @param id        Account id
+     * @param uri       Uri of the contact
+     * @param ban       Also ban the contact
+     */
+    pub fn try_remove_contact(id: &str, uri: &str, ban: bool) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::peer("uri", uri)?;
+        JamiClient::shared()?.try_remove_contact(id, uri, ban)
+    }
+
+    /**
+     * Get default moderators of an account (moderators of the conferences it hosts)
+     * @param id        Account id
+     * @return the uris of the moderators
+     */
+    pub fn try_get_default_moderators(id: &str) -> Result<Vec<String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (moderators,): (Vec<String>,) =
+            JamiClient::shared()?.configuration("getDefaultModerators", (id,))?;
+        Ok(moderators)
+    }
+
+    /**
+     * Add or remove a default moderator
+     * @param id        Account id
+     * @param uri       Peer uri
+     * @param state     If the peer is a moderator
+     */
+    pub fn try_set_default_moderator(id: &str, uri: &str, state: bool) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::peer("uri", uri)?;
+        JamiClient::shared()?.configuration("setDefaultModerator", (id, uri, state))
+    }
+
+    /**
+     * Make every participant of the conferences hosted by an account a moderator
+     * @param id        Account id
+     * @param enabled   If all participants are moderators
+     */
+    pub fn try_set_all_moderators(id: &str, enabled: bool) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        JamiClient::shared()?.configuration("setAllModerators", (id, enabled))
+    }
+
+    /**
+     * @param id        Account id
+     * @return if all participants are moderators
+     */
+    pub fn try_is_all_moderators(id: &str) -> Result<bool, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (enabled,): (bool,) = JamiClient::shared()?.configuration("isAllModerators", (id,))?;
+        Ok(enabled)
+    }
+
+    /**
+     * Mute all notifications of an account (cf should_notify).
+     * The daemon has no such setting, the preference is managed by this crate
+     * and stored in the directory of the account (jami::app_data + id/jami-rs-muted)
+     * @param id        Account id
+     * @param muted     If the account is muted
+     * @return if the preference was saved
+     */
+    pub fn set_account_muted(id: &str, muted: bool) -> bool {
+        let path = match Jami::muted_marker_path(id) {
+            Some(path) => path,
+            None => return false,
+        };
+        if muted {
+            fs::write(path, "").is_ok()
+        } else {
+            fs::remove_file(&path).is_ok() || !path.exists()
+        }
+    }
+
+    /**
+     * Get if an account is muted (cf set_account_muted)
+     * @param id        Account id
+     * @return if the account is muted
+     */
+    pub fn is_account_muted(id: &str) -> bool {
+        Jami::muted_marker_path(id)
+            .map(|path| path.exists())
+            .unwrap_or(false)
+    }
+
+    /**
+     * Get if an event deserves a notification: a message, a trust request
+     * or a conversation request, for an account not muted
+     * @param event     Event from the event loop
+     * @return if the user should be notified
+     */
+    pub fn should_notify<I>(event: &Event<I>) -> bool {
+        let account_id = match event {
+            Event::Message { account_id, .. } => account_id,
+            Event::AccountMessage { account_id, .. } => account_id,
+            Event::ConversationRequest(account_id, _) => account_id,
+            Event::IncomingContactRequest { account_id, .. } => account_id,
+            _ => return false,
+        };
+        !Jami::is_account_muted(account_id)
+    }
+
+    fn muted_marker_path(id: &str) -> Option<PathBuf> {
+        get_app_dir(
+            AppDataType::UserData,
+            &AppInfo {
+                name: "jami",
+                author: "SFL",
+            },
+            &format!("{}/jami-rs-muted", id),
+        )
+        .ok()
+    }
+
+    /**
+     * Parse the payload of a trust request
+     * @param payload   Payload of incomingTrustRequest or getTrustRequests
+     * @return the conversation id (from a JSON payload) and the profile (from a vCard payload)
+     */
+    pub fn parse_contact_request_payload(payload: &[u8]) -> (Option<String>, Option<Profile>) {
+        let content = String::from_utf8_lossy(payload);
+        if content.contains("BEGIN:VCARD") {
+            return (None, Some(Profile::from_vcard(&content)));
+        }
+        let conversation_id = serde_json::from_str::<serde_json::Value>(&content)
+            .ok()
+            .and_then(|json| {
+                json.get("conversationId")
+                    .and_then(|id| id.as_str())
+                    .map(|id| id.to_string())
+            })
+            .filter(|id| !id.is_empty());
+        (conversation_id, None)
+    }
+
+    /**
+     * Get trusts requests from an account
+     * @param id        Account id
+     * @return the list of trusts requests senders (empty on any error, cf try_get_trust_requests)
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_get_trust_requests")]
+    pub fn get_trust_requests(id: &str) -> Vec<String> {
+        Jami::try_get_trust_requests(id).unwrap_or_default()
+    }
+
+    /**
+     * Get trusts requests from an account, reporting errors
+     * @param id        Account id
+     * @return the list of trusts requests senders
+     */
+    pub fn try_get_trust_requests(id: &str) -> Result<Vec<String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        JamiClient::shared()?.try_get_trust_requests(id)
+    }
+
+    /**
+     * Get contacts of an account
+     * @param id        Account id
+     * @return the contacts (id, added, confirmed, etc.)
+     */
+    pub fn try_get_contacts(id: &str) -> Result<Vec<HashMap<String, String>>, JamiError> {
+        validate::account_id("account_id", id)?;
+        JamiClient::shared()?.try_get_contacts(id)
+    }
+
+    /**
+     * Send a trust request to someone (errors are ignored, cf try_send_trust_request)
+     * @param id        Account id
+     * @param to        Contact uri
+     * @param payloads  VCard
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_send_trust_request")]
+    pub fn send_trust_request(id: &str, to: &str, payloads: Vec<u8>) {
+        let _ = Jami::try_send_trust_request(id, to, payloads);
+    }
+
+    /**
+     * Send a trust request to someone, reporting errors
+     * @param id        Account id
+     * @param to        Contact uri
+     * @param payloads  VCard
+     */
+    pub fn try_send_trust_request(id: &str, to: &str, payloads: Vec<u8>) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::peer("uri", to)?;
+        JamiClient::shared()?.try_send_trust_request(id, to, payloads)
+    }
+
+    /**
+     * Accept a trust request
+     * @param id        Account id
+     * @param from      Contact uri
+     * @return if successful (false on any error, cf try_accept_trust_request)
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_accept_trust_request")]
+    pub fn accept_trust_request(id: &str, from: &str) -> bool {
+        Jami::try_accept_trust_request(id, from).unwrap_or(false)
+    }
+
+    /**
+     * Accept a trust request, reporting errors
+     * @param id        Account id
+     * @param from      Contact uri
+     * @return if the daemon accepted it
+     */
+    pub fn try_accept_trust_request(id: &str, from: &str) -> Result<bool, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::peer("uri", from)?;
+        JamiClient::shared()?.try_accept_trust_request(id, from)
+    }
+
+    /**
+     * Discard a trust request
+     * @param id        Account id
+     * @param from      Contact uri
+     * @return if successful (false on any error, cf try_discard_trust_request)
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_discard_trust_request")]
+    pub fn discard_trust_request(id: &str, from: &str) -> bool {
+        Jami::try_discard_trust_request(id, from).unwrap_or(false)
+    }
+
+    /**
+     * Discard a trust request, reporting errors
+     * @param id        Account id
+     * @param from      Contact uri
+     * @return if the daemon discarded it
+     */
+    pub fn try_discard_trust_request(id: &str, from: &str) -> Result<bool, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::peer("uri", from)?;
+        JamiClient::shared()?.try_discard_trust_request(id, from)
+    }
+
+    /**
+     * Get current members for a conversation
+     * @param id        Id of the account
+     * @param convid    Id of the conversation
+     * @return current members (empty on any error, cf try_get_members)
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_get_members")]
+    pub fn get_members(id: &str, convid: &str) -> Vec<HashMap<String, String>> {
+        Jami::try_get_members(id, convid).unwrap_or_default()
+    }
+
+    /**
+     * Get current members for a conversation, reporting errors
+     * @param id        Id of the account
+     * @param convid    Id of the conversation
+     * @return current members
+     */
+    pub fn try_get_members(
+        id: &str,
+        convid: &str,
+    ) -> Result<Vec<HashMap<String, String>>, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", convid)?;
+        JamiClient::shared()?.try_get_members(id, convid)
+    }
+
+    /**
+     * Get conversation's infos
+     * @param id        Id of the account
+     * @param convid    Id of the conversation
+     * @return current infos (empty on any error, cf try_get_conversation_infos)
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_get_conversation_infos")]
+    pub fn get_conversation_infos(id: &str, convid: &str) -> HashMap<String, String> {
+        Jami::try_get_conversation_infos(id, convid).unwrap_or_default()
+    }
+
+    /**
+     * Get conversation's infos, reporting errors
+     * @param id        Id of the account
+     * @param convid    Id of the conversation
+     * @return current infos
+     */
+    pub fn try_get_conversation_infos(
+        id: &str,
+        convid: &str,
+    ) -> Result<HashMap<String, String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", convid)?;
+        JamiClient::shared()?.try_get_conversation_infos(id, convid)
+    }
+
+    /**
+     * Update conversation's i nfos (errors are ignored, cf try_update_conversation_infos)
+     * @param id        Id of the account
+     * @param convid    Id of the conversation
+     * @param infos     New infos
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_update_conversation_infos")]
+    pub fn update_conversation_infos(id: &str, convid: &str, infos: HashMap<String, String>) {
+        let _ = Jami::try_update_conversation_infos(id, convid, infos);
+    }
+
+    /**
+     * Update conversation's i nfos, reporting errors
+     * @param id        Id of the account
+     * @param convid    Id of the conversation
+     * @param infos     New infos
+     */
+    pub fn try_update_conversation_infos(
+        id: &str,
+        convid: &str,
+        infos: HashMap<String, String>,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", convid)?;
+        JamiClient::shared()?.configuration("updateConversationInfos", (id, convid, infos))
+    }
+
+    /**
+     * Get device-local preferences of a conversation
+     * @param id        Id of the account
+     * @param convid    Id of the conversation
+     * @return the preferences
+     */
+    pub fn try_get_conversation_preferences(
+        id: &str,
+        convid: &str,
+    ) -> Result<HashMap<String, String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", convid)?;
+        let (prefs,): (HashMap<String, String>,) =
+            JamiClient::shared()?.configuration("getConversationPreferences", (id, convid))?;
+        Ok(prefs)
+    }
+
+    /**
+     * Update device-local preferences of a conversation
+     * @param id        Id of the account
+     * @param convid    Id of the conversation
+     * @param prefs     Preferences to set
+     */
+    pub fn try_set_conversation_preferences(
+        id: &str,
+        convid: &str,
+        prefs: HashMap<String, String>,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", convid)?;
+        JamiClient::shared()?.configuration("setConversationPreferences", (id, convid, prefs))
+    }
+
+    /**
+     * Manually mark a conversation as unread (or clear the mark)
+     * @param id        Id of the account
+     * @param convid    Id of the conversation
+     * @param unread    true to mark as unread
+     */
+    pub fn try_set_conversation_unread(
+        id: &str,
+        convid: &str,
+        unread: bool,
+    ) -> Result<(), JamiError> {
+        let mut prefs = HashMap::new();
+        prefs.insert(String::from("markedUnread"), unread.to_string());
+        Jami::try_set_conversation_preferences(id, convid, prefs)
+    }
+
+    /**
+     * @param id        Id of the account
+     * @param convid    Id of the conversation
+     * @return if the conversation was manually marked as unread
+     */
+    pub fn is_conversation_marked_unread(id: &str, convid: &str) -> bool {
+        Jami::try_get_conversation_preferences(id, convid)
+            .ok()
+            .and_then(|prefs| prefs.get("markedUnread").map(|v| v == "true"))
+            .unwrap_or(false)
+    }
+
+    /**
+     * Count interactions between two commits
+     * @param id        Id of the account
+     * @param convid    Id of the conversation
+     * @param to        Oldest commit ("" for the start)
+     * @param from      Newest commit ("" for the head)
+     * @param author    Only count this author ("" for everyone)
+     * @return the number of interactions
+     */
+    pub fn try_count_interactions(
+        id: &str,
+        convid: &str,
+        to: &str,
+        from: &str,
+        author: &str,
+    ) -> Result<u32, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", convid)?;
+        let (count,): (u32,) = JamiClient::shared()?
+            .configuration("countInteractions", (id, convid, to, from, author))?;
+        Ok(count)
+    }
+
+    /**
+     * Number of unread interactions since the last displayed one.
+     * A conversation manually marked as unread counts at least 1.
+     * @param id        Id of the account
+     * @param convid    Id of the conversation
+     * @return the number of unread interactions
+     */
+    pub fn unread_count(id: &str, convid: &str) -> u32 {
+        let hash = match Jami::try_get_account(id) {
+            Ok(Some(account)) => account.hash,
+            _ => return 0,
+        };
+        let last_displayed = Jami::try_get_members(id, convid)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|m| m.get("uri") == Some(&hash))
+            .and_then(|mut m| m.remove("lastDisplayed"))
+            .unwrap_or_default();
+        let count = Jami::try_count_interactions(id, convid, &last_displayed, "", "").unwrap_or(0);
+        if count == 0 && Jami::is_conversation_marked_unread(id, convid) {
+            return 1;
+        }
+        count
+    }
+
+    /**
+     * Start conversation
+     * @param id        Id of the account
+     * @return the id of the conversation, empty on any error (cf try_start_conversation)
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_start_conversation")]
+    pub fn start_conversation(id: &str) -> String {
+        Jami::try_start_conversation(id).unwrap_or_default()
+    }
+
+    /**
+     * Start conversation, reporting errors
+     * @param id        Id of the account
+     * @return the id of the conversation
+     */
+    pub fn try_start_conversation(id: &str) -> Result<String, JamiError> {
+        validate::account_id("account_id", id)?;
+        JamiClient::shared()?.try_start_conversation(id)
+    }
+
+    /**
+     * Start a new conversation with the given mode. startConversation always creates
+     * an invites-only group, so the mode of the others is set right after with
+     * updateConversationInfos, before any member is invited
+     * @param account_id    Id of the account
+     * @param mode          Mode of the group, OneToOne is refused (cf add_contact)
+     * @return the id of the conversation, InvalidArgument for OneToOne
+     */
+    pub fn try_start_conversation_mode(
+        account_id: &str,
+        mode: ConversationMode,
+    ) -> Result<String, JamiError> {
+        validate::account_id("account_id", account_id)?;
+        if mode == ConversationMode::OneToOne {
+            return Err(JamiError::InvalidArgument(
+                String::from("mode"),
+                String::from("one to one conversations are started by add_contact"),
+            ));
+        }
+        let client = JamiClient::shared()?;
+        let (conv_id,): (String,) = client.configuration("startConversation", (account_id,))?;
+        if conv_id.is_empty() {
+            return Err(JamiError::OperationFailed(String::from(
+                "startConversation",
+            )));
+        }
+        if mode != ConversationMode::InvitesOnly {
+            let mut infos = HashMap::new();
+            infos.insert(String::from("mode"), mode.value().to_string());
+            let result: Result<(), _> =
+                client.configuration("updateConversationInfos", (account_id, &conv_id, infos));
+            if let Err(e) = result {
+                // Don't leave a group with the wrong mode
+                let _: Result<(bool,), _> =
+                    client.configuration("removeConversation", (account_id, &conv_id));
+                return Err(e);
+            }
+        }
+        Ok(conv_id)
+    }
+
+    /**
+     * Start conversation and wait for it to be ready
+     * @param id        Id of the account
+     * @param timeout   Max time to wait for conversationReady
+     * @return the id of the conversation
+     */
+    pub async fn start_conversation_await(
+        id: &str,
+        timeout: Duration,
+    ) -> Result<String, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (resource, conn) = bus::connect_async().map_err(JamiError::BusConnection)?;
+
+        let work = async {
+            // Listen before starting, conversationReady can be emitted before the reply
+            let (ready_tx, mut ready_rx) = tokio::sync::mpsc::unbounded_channel();
+            let account = id.to_string();
+            let mr =
+                MatchRule::new_signal(bus::config().config_manager_interface, "conversationReady");
+            let ic = conn
+                .add_match(mr)
+                .await
+                .map_err(|e| JamiError::method_call("AddMatch", e))?
+                .cb(move |_, (account_id, conversation_id): (String, String)| {
+                    if account_id == account {
+                        let _ = ready_tx.send(conversation_id);
+                    }
+                    true
+                });
+
+            let proxy = nonblock::Proxy::new(
+                bus::config().service,
+                bus::config().config_manager_path,
+                timeout,
+                conn.clone(),
+            );
+            let result: Result<(String,), _> = calllog::logged_call_async(
+                &proxy,
+                &bus::config().config_manager_interface,
+                "startConversation",
+                (id,),
+            )
+            .await;
+            let result = match result {
+                Ok((conv_id,)) => {
+                    let wait = async {
+                        while let Some(ready) = ready_rx.recv().await {
+                            if ready == conv_id {
+                                return;
+                            }
+                        }
+                    };
+                    match tokio::time::timeout(timeout, wait).await {
+                        Ok(_) => Ok(conv_id),
+                        Err(_) => Err(JamiError::Timeout),
+                    }
+                }
+                Err(e) => Err(JamiError::method_call("startConversation", e)),
+            };
+            let _ = conn.remove_match(ic.token()).await;
+            result
+        };
+
+        tokio::select! {
+            err = resource => Err(JamiError::ConnectionLost(err.to_string())),
+            result = work => result,
+        }
+    }
+
+    /**
+     * Get current conversations for account
+     * @param id        Id of the account
+     * @return current conversations, empty on any error (cf try_get_conversations)
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_get_conversations")]
+    pub fn get_conversations(id: &str) -> Vec<String> {
+        Jami::try_get_conversations(id).unwrap_or_default()
+    }
+
+    /**
+     * Get current conversations for account, reporting errors
+     * @param id        Id of the account
+     * @return current conversations
+     */
+    pub fn try_get_conversations(id: &str) -> Result<Vec<String>, JamiError> {
+        validate::account_id("account_id", id)?;
+        JamiClient::shared()?.try_get_conversations(id)
+    }
+
+    /**
+     * Get handles on the conversations of an account, sharing one connection
+     * @param id        Id of the account
+     * @return the conversations (empty if an error occurs)
+     */
+    pub fn conversations(id: &str) -> Vec<Conversation> {
+        let client = match JamiClient::new() {
+            Ok(client) => client,
+            Err(_) => return Vec::new(),
+        };
+        client
+            .try_get_conversations(id)
+            .unwrap_or_default()
+            .iter()
+            .map(|conv_id| Conversation::new(client.clone(), id, conv_id))
+            .collect()
+    }
+
+    /**
+     * Count conversations, requests and contacts of an account.
+     * Results are cached for 2 seconds to avoid flooding the bus on refreshes.
+     * @param id        Id of the account
+     * @return the stats
+     */
+    pub async fn account_stats(id: &str) -> AccountStats {
+        {
+            let mut cache = STATS_CACHE.lock().unwrap();
+            cache.retain(|(_, at, _)| at.elapsed() < STATS_CACHE_DURATION);
+            if let Some((_, _, stats)) = cache.iter().find(|(account, _, _)| account == id) {
+                return stats.clone();
+            }
+        }
+
+        let (account, account2, account3) = (id.to_string(), id.to_string(), id.to_string());
+        let (conversations, requests, contacts) = tokio::join!(
+            tokio::task::spawn_blocking(
+                move || Jami::try_get_conversations(&account).map_or(0, |c| c.len())
+            ),
+            tokio::task::spawn_blocking(
+                move || Jami::try_get_conversations_requests(&account2).map_or(0, |r| r.len())
+            ),
+            tokio::task::spawn_blocking(
+                move || Jami::try_get_contacts(&account3).map_or(0, |c| c.len())
+            ),
+        );
+        let stats = AccountStats {
+            conversations: conversations.unwrap_or(0),
+            requests: requests.unwrap_or(0),
+            contacts: contacts.unwrap_or(0),
+            messages: None,
+        };
+
+        STATS_CACHE
+            .lock()
+            .unwrap()
+            .push((id.to_string(), Instant::now(), stats.clone()));
+        stats
+    }
+
+    /**
+     * Same as account_stats, but also count messages of all conversations.
+     * Slow: every conversation is fully loaded
+     * @param id        Id of the account
+     * @return the stats
+     */
+    pub async fn account_stats_with_messages(id: &str) -> AccountStats {
+        let mut stats = Jami::account_stats(id).await;
+        let mut messages = 0;
+        for conv_id in Jami::try_get_conversations(id).unwrap_or_default() {
+            if let Ok(loaded) =
+                Jami::load_conversation_await(id, &conv_id, "", 0, bus::call_timeout()).await
+            {
+                messages += loaded.len();
+            }
+        }
+        stats.messages = Some(messages);
+        stats
+    }
+
+    /**
+     * Get current conversations requests for account
+     * @param id        Id of the account
+     * @return current conversations requests (empty on any error, cf try_get_conversations_requests)
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_get_conversations_requests")]
+    pub fn get_conversations_requests(id: &str) -> Vec<HashMap<String, String>> {
+        Jami::try_get_conversations_requests(id).unwrap_or_default()
+    }
+
+    /**
+     * Get current conversations requests for account, reporting errors
+     * @param id        Id of the account
+     * @return current conversations requests
+     */
+    pub fn try_get_conversations_requests(
+        id: &str,
+    ) -> Result<Vec<HashMap<String, String>>, JamiError> {
+        validate::account_id("account_id", id)?;
+        let (requests,): (Vec<HashMap<String, String>>,) =
+            JamiClient::shared()?.configuration("getConversationRequests", (id,))?;
+        Ok(requests)
+    }
+
+    /**
+     * Decline a conversation request (errors are ignored, cf try_decline_request)
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_decline_request")]
+    pub fn decline_request(id: &str, conv_id: &str) {
+        let _ = Jami::try_decline_request(id, conv_id);
+    }
+
+    /**
+     * Decline a conversation request, reporting errors
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     */
+    pub fn try_decline_request(id: &str, conv_id: &str) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        JamiClient::shared()?.configuration("declineConversationRequest", (id, conv_id))
+    }
+
+    /**
+     * Accept a conversation request (errors are ignored, cf try_accept_request)
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_accept_request")]
+    pub fn accept_request(id: &str, conv_id: &str) {
+        let _ = Jami::try_accept_request(id, conv_id);
+    }
+
+    /**
+     * Accept a conversation request, reporting errors
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     */
+    pub fn try_accept_request(id: &str, conv_id: &str) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        JamiClient::shared()?.configuration("acceptConversationRequest", (id, conv_id))
+    }
+
+    /**
+     * Asynchronously load a conversation
+     * @param account
+     * @param conversation
+     * @param from              "" if latest else the commit id
+     * @param size              0 if all else max number of messages to get
+     * @return the id of the request (0 on any error, cf try_load_conversation)
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_load_conversation")]
+    pub fn load_conversation(account: &str, conversation: &str, from: &str, size: u32) -> u32 {
+        Jami::try_load_conversation(account, conversation, from, size).unwrap_or_default()
+    }
+
+    /**
+     * Asynchronously load a conversation, reporting errors
+     * @param account
+     * @param conversation
+     * @param from              "" if latest else the commit id
+     * @param size              0 if all else max number of messages to get
+     * @return the id of the request
+     */
+    pub fn try_load_conversation(
+        account: &str,
+        conversation: &str,
+        from: &str,
+        size: u32,
+    ) -> Result<u32, JamiError> {
+        validate::account_id("account_id", account)?;
+        validate::conversation_id("conversation_id", conversation)?;
+        let (request_id,): (u32,) = JamiClient::shared()?.configuration(
+            "loadConversationMessages",
+            (account, conversation, from, size),
+        )?;
+        Ok(request_id)
+    }
+
+    /**
+     * Load a conversation and wait for the messages
+     * @param account
+     * @param conversation
+     * @param from              "" if latest else the commit id
+     * @param size              0 if all else max number of messages to get
+     * @param timeout           Max time to wait for conversationLoaded
+     * @return the messages. Pages (size > 0) are cached, cf conversation::set_page_cache_size
+     */
+    pub async fn load_conversation_await(
+        account: &str,
+        conversation: &str,
+        from: &str,
+        size: u32,
+        timeout: Duration,
+    ) -> Result<Vec<HashMap<String, String>>, JamiError> {
+        validate::account_id("account_id", account)?;
+        validate::conversation_id("conversation_id", conversation)?;
+        let key = (
+            account.to_string(),
+            conversation.to_string(),
+            from.to_string(),
+            size,
+        );
+        if let Some(messages) = conversation::cached_page(&key) {
+            return Ok(messages);
+        }
+        let (resource, conn) = bus::connect_async().map_err(JamiError::BusConnection)?;
+
+        let work = async {
+            // Listen before loading, the signal can be emitted before the reply
+            let (loaded_tx, mut loaded_rx) = tokio::sync::mpsc::unbounded_channel();
+            let mr =
+                MatchRule::new_signal(bus::config().config_manager_interface, "conversationLoaded");
+            let ic = conn
+                .add_match(mr)
+                .await
+                .map_err(|e| JamiError::method_call("AddMatch", e))?
+                .cb(
+                    move |_,
+                          (id, _, _, messages): (
+                        u32,
+                        String,
+                        String,
+                        Vec<HashMap<String, String>>,
+                    )| {
+                        let _ = loaded_tx.send((id, messages));
+                        true
+                    },
+                );
+
+            let proxy = nonblock::Proxy::new(
+                bus::config().service,
+                bus::config().config_manager_path,
+                timeout,
+                conn.clone(),
+            );
+            let result: Result<(u32,), _> = calllog::logged_call_async(
+                &proxy,
+                &bus::config().config_manager_interface,
+                "loadConversationMessages",
+                (account, conversation, from, size),
+            )
+            .await;
+            let result = match result {
+                Ok((request_id,)) => {
+                    let wait = async {
+                        while let Some((id, messages)) = loaded_rx.recv().await {
+                            if id == request_id {
+                                return Some(messages);
+                            }
+                        }
+                        None
+                    };
+                    match tokio::time::timeout(timeout, wait).await {
+                        Ok(Some(messages)) => {
+                            // Whole histories are too big to be kept
+                            if size > 0 {
+                                conversation::cache_page(key, messages.clone());
+                            }
+                            Ok(messages)
+                        }
+                        _ => Err(JamiError::Timeout),
+                    }
+                }
+                Err(e) => Err(JamiError::method_call("loadConversationMessages", e)),
+            };
+            let _ = conn.remove_match(ic.token()).await;
+            result
+        };
+
+        tokio::select! {
+            err = resource => Err(JamiError::ConnectionLost(err.to_string())),
+            result = work => result,
+        }
+    }
+
+    /**
+     * Get commits of a conversation without the payloads. Useful to compare
+     * the history of two devices.
+     * @param account
+     * @param conversation
+     * @param from              "" if latest else the commit id
+     * @param size              0 if all else max number of commits to get
+     * @return commit ids, from the newest
+     */
+    pub async fn conversation_commits(
+        account: &str,
+        conversation: &str,
+        from: &str,
+        size: u32,
+    ) -> Result<Vec<String>, JamiError> {
+        let messages =
+            Jami::load_conversation_await(account, conversation, from, size, bus::call_timeout())
+                .await?;
+        Ok(messages
+            .into_iter()
+            .filter_map(|mut message| message.remove("id"))
+            .collect())
+    }
+
+    /**
+     * Load the messages received after known heads, e.g. the ones saved from
+     * ConversationHeads before a restart. Conversations without head are fully loaded
+     * @param id        Id of the account
+     * @param heads     conversation id -> last handled commit
+     * @return conversation id -> new messages (newest first)
+     */
+    pub async fn conversations_since(
+        id: &str,
+        heads: &HashMap<String, String>,
+    ) -> Result<HashMap<String, Vec<HashMap<String, String>>>, JamiError> {
+        const PAGE_SIZE: u32 = 20;
+        let mut result = HashMap::new();
+        for conv_id in Jami::try_get_conversations(id)? {
+            let head = match heads.get(&conv_id) {
+                Some(head) => head,
+                None => {
+                    let messages =
+                        Jami::load_conversation_await(id, &conv_id, "", 0, bus::call_timeout())
+                            .await?;
+                    result.insert(conv_id, messages);
+                    continue;
+                }
+            };
+            let mut messages: Vec<HashMap<String, String>> = Vec::new();
+            let mut from = String::new();
+            'pages: loop {
+                let page = Jami::load_conversation_await(
+                    id,
+                    &conv_id,
+                    &from,
+                    PAGE_SIZE,
+                    bus::call_timeout(),
+                )
+                .await?;
+                let page_len = page.len();
+                for message in page {
+                    let commit = message.get("id").cloned().unwrap_or_default();
+                    if &commit == head {
+                        break 'pages;
+                    }
+                    // A page starts with its "from" commit
+                    if commit != from {
+                        messages.push(message);
+                    }
+                }
+                match messages.last().and_then(|m| m.get("id")) {
+                    Some(last) if page_len as u32 >= PAGE_SIZE && *last != from => {
+                        from = last.clone()
+                    }
+                    _ => break,
+                }
+            }
+            result.insert(conv_id, messages);
+        }
+        Ok(result)
+    }
+
+    /**
+     * Forget the messages kept by get_message and load_conversation_await,
+     * e.g. to free memory or after the history was rewritten
+     */
+    pub fn clear_cache() {
+        MESSAGE_CACHE.lock().unwrap().clear();
+        conversation::clear_page_cache();
+    }
+
+    /**
+     * Get one message of a conversation. Messages are cached
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @param msg_id    Id of the message
+     * @return the message, None if not found
+     */
+    pub async fn get_message(
+        id: &str,
+        conv_id: &str,
+        msg_id: &str,
+    ) -> Result<Option<ConversationMessage>, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        {
+            let cache = MESSAGE_CACHE.lock().unwrap();
+            let cached = cache.iter().find(|(account, conv, message)| {
+                account == id && conv == conv_id && message.id == *msg_id
+            });
+            if let Some((_, _, message)) = cached {
+                return Ok(Some(message.clone()));
+            }
+        }
+        if let Some(message) = conversation::cached_message(id, conv_id, msg_id) {
+            return Ok(Some(ConversationMessage::from_payloads(&message)));
+        }
+        let messages =
+            Jami::load_conversation_await(id, conv_id, msg_id, 1, bus::call_timeout()).await?;
+        let message = match messages
+            .iter()
+            .find(|message| message.get("id").map(|id| &**id) == Some(msg_id))
+        {
+            Some(message) => ConversationMessage::from_payloads(message),
+            None => return Ok(None),
+        };
+        let mut cache = MESSAGE_CACHE.lock().unwrap();
+        if cache.len() >= MESSAGE_CACHE_SIZE {
+            cache.remove(0);
+        }
+        cache.push((id.to_string(), conv_id.to_string(), message.clone()));
+        Ok(Some(message))
+    }
+
+    /**
+     * Get the message answered by a message, to show "replying to …"
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @param msg       The reply
+     * @return the answered message, None if msg is not a reply or if it's not found
+     */
+    pub async fn reply_context(
+        id: &str,
+        conv_id: &str,
+        msg: &ConversationMessage,
+    ) -> Option<ConversationMessage> {
+        let reply_to = msg.reply_to.as_ref()?;
+        Jami::get_message(id, conv_id, reply_to)
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /**
+     * Follow the new messages of one conversation, e.g. for an open chat
+     * Must be called from a tokio runtime. The stream ends with the event loop
+     * @param events    Running event loop
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @return new commits of the conversation, as they arrive
+     */
+    pub fn conversation_messages_stream(
+        events: &EventLoopHandle,
+        id: &str,
+        conv_id: &str,
+    ) -> impl Stream<Item = ConversationMessage> {
+        let mut rx = events.subscribe();
+        let (tx, messages) = mpsc::unbounded_channel();
+        let id = id.to_string();
+        let conv_id = conv_id.to_string();
+        tokio::spawn(async move {
+            loop {
+                let event = match rx.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::RecvError::Closed) => break,
+                };
+                if let Event::Message {
+                    account_id,
+                    conversation_id,
+                    payloads,
+                } = event
+                {
+                    if account_id != id || conversation_id != conv_id {
+                        continue;
+                    }
+                    if tx
+                        .send(ConversationMessage::from_payloads(&payloads))
+                        .is_err()
+                    {
+                        // Stream dropped
+                        break;
+                    }
+                }
+            }
+        });
+        messages
+    }
+
+    /**
+     * Ask the daemon to pull conversations from peers again (pull to refresh)
+     * Daemon side: there is no per-conversation fetch on the bus, so this triggers
+     * connectivityChanged, which reconnects to peers and syncs every conversation.
+     * New commits arrive as Event::Message
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @return NotFound if the conversation is unknown
+     */
+    pub fn try_fetch_conversation(id: &str, conv_id: &str) -> Result<(), JamiError> {
+        let client = JamiClient::shared()?;
+        if !client
+            .try_get_conversations(id)?
+            .iter()
+            .any(|conv| conv == conv_id)
+        {
+            return Err(JamiError::NotFound(format!("conversation {}", conv_id)));
+        }
+        client.configuration("connectivityChanged", ())
+    }
+
+    /**
+     * Reload a conversation from scratch.
+     * Crate side: cached stats of the account and cached messages of the conversation are dropped.
+     * Daemon side: conversations are reloaded from disk (reloadConversationsAndRequests),
+     * then the whole history is loaded again. Nothing is pulled from peers,
+     * use try_fetch_conversation() for that
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @return all the messages
+     */
+    pub async fn reload_conversation(
+        id: &str,
+        conv_id: &str,
+    ) -> Result<Vec<HashMap<String, String>>, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        STATS_CACHE
+            .lock()
+            .unwrap()
+            .retain(|(account, _, _)| account != id);
+        MESSAGE_CACHE
+            .lock()
+            .unwrap()
+            .retain(|(account, conv, _)| account != id || conv != conv_id);
+        conversation::invalidate_pages(id, conv_id, true);
+
+        let client = JamiClient::shared()?;
+        client.configuration::<_, ()>("reloadConversationsAndRequests", (id,))?;
+
+        Jami::load_conversation_await(id, conv_id, "", 0, Duration::from_millis(30000)).await
+    }
+
+    /**
+     * Remove a conversation for an account
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @return if the conversation is removed (false on any error, cf try_rm_conversation)
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_rm_conversation")]
+    pub fn rm_conversation(id: &str, conv_id: &str) -> bool {
+        Jami::try_rm_conversation(id, conv_id).unwrap_or(false)
+    }
+
+    /**
+     * Remove a conversation for an account, reporting errors
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @return if the daemon removed it
+     */
+    pub fn try_rm_conversation(id: &str, conv_id: &str) -> Result<bool, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        JamiClient::shared()?.try_rm_conversation(id, conv_id)
+    }
+
+    /**
+     * Invite a member to a conversation (errors are ignored, cf try_add_conversation_member)
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @param hash      Id of the member to invite
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_add_conversation_member")]
+    pub fn add_conversation_member(id: &str, conv_id: &str, hash: &str) {
+        let _ = Jami::try_add_conversation_member(id, conv_id, hash);
+    }
+
+    /**
+     * Invite a member to a conversation, reporting errors
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @param hash      Id of the member to invite
+     */
+    pub fn try_add_conversation_member(
+        id: &str,
+        conv_id: &str,
+        hash: &str,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        validate::peer("uri", hash)?;
+        JamiClient::shared()?.configuration("addConversationMember", (id, conv_id, hash))
+    }
+
+    /**
+     * Invite several members to a conversation (cf add_conversation_member)
+     * @param account_id    Id of the account
+     * @param conv_id       Id of the conversation
+     * @param uris          Members to invite
+     * @param join_timeout  If set, also wait for each member to join
+     *                      (ConversationMemberEvent 1), Timeout for the others
+     * @return the outcome for each uri, in order
+     */
+    pub async fn invite_members(
+        account_id: &str,
+        conv_id: &str,
+        uris: Vec<String>,
+        join_timeout: Option<Duration>,
+    ) -> Vec<(String, Result<(), JamiError>)> {
+        let fail_all = |reason: String| {
+            uris.iter()
+                .map(|member| {
+                    (
+                        member.clone(),
+                        Err(JamiError::ConnectionLost(reason.clone())),
+                    )
+                })
+                .collect()
+        };
+        let (resource, conn) = match bus::connect_async() {
+            Ok(session) => session,
+            Err(e) => return fail_all(e.to_string()),
+        };
+
+        let work = async {
+            let (joined_tx, mut joined_rx) = tokio::sync::mpsc::unbounded_channel();
+            let ic = if join_timeout.is_some() {
+                let (account, conversation) = (account_id.to_string(), conv_id.to_string());
+                let mr = MatchRule::new_signal(
+                    bus::config().config_manager_interface,
+                    "conversationMemberEvent",
+                );
+                match conn.add_match(mr).await {
+                    Ok(ic) => Some(ic.cb(
+                        move |_,
+                              (account_id, conversation_id, member_uri, event): (
+                            String,
+                            String,
+                            String,
+                            i32,
+                        )| {
+                            if account_id == account
+                                && conversation_id == conversation
+                                && event == 1
+                            {
+                                let _ = joined_tx.send(uri::normalize_peer(&member_uri));
+                            }
+                            true
+                        },
+                    )),
+                    Err(e) => return fail_all(e.to_string()),
+                }
+            } else {
+                None
+            };
+
+            let proxy = nonblock::Proxy::new(
+                bus::config().service,
+                bus::config().config_manager_path,
+                bus::call_timeout(),
+                conn.clone(),
+            );
+            let mut outcomes = Vec::new();
+            for member in &uris {
+                let invited = match validate::account_id("account_id", account_id)
+                    .and(validate::conversation_id("conversation_id", conv_id))
+                    .and(validate::peer("uri", member))
+                {
+                    Ok(()) => calllog::logged_call_async(
+                        &proxy,
+                        &bus::config().config_manager_interface,
+                        "addConversationMember",
+                        (account_id, conv_id, &**member),
+                    )
+                    .await
+                    .map_err(|e| JamiError::method_call("addConversationMember", e)),
+                    Err(e) => Err(e),
+                };
+                outcomes.push((member.clone(), invited));
+            }
+
+            if let Some(join_timeout) = join_timeout {
+                let mut waiting: Vec<String> = outcomes
+                    .iter()
+                    .filter(|(_, invited)| invited.is_ok())
+                    .map(|(member, _)| uri::normalize_peer(member))
+                    .collect();
+                let deadline = Instant::now() + join_timeout;
+                while !waiting.is_empty() {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    match tokio::time::timeout(remaining, joined_rx.recv()).await {
+                        Ok(Some(member)) => waiting.retain(|waited| *waited != member),
+                        _ => break,
+                    }
+                }
+                for (member, outcome) in outcomes.iter_mut() {
+                    if waiting.contains(&uri::normalize_peer(member)) {
+                        *outcome = Err(JamiError::Timeout);
+                    }
+                }
+            }
+            if let Some(ic) = ic {
+                let _ = conn.remove_match(ic.token()).await;
+            }
+            outcomes
+        };
+
+        tokio::select! {
+            err = resource => fail_all(err.to_string()),
+            outcomes = work => outcomes,
+        }
+    }
+
+    /**
+     * Remove a member from a conversation (errors are ignored, cf try_rm_conversation_member)
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @param hash      Id of the member to invite
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_rm_conversation_member")]
+    pub fn rm_conversation_member(id: &str, conv_id: &str, hash: &str) {
+        let _ = Jami::try_rm_conversation_member(id, conv_id, hash);
+    }
+
+    /**
+     * Remove a member from a conversation, reporting errors
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @param hash      Id of the member to invite
+     */
+    pub fn try_rm_conversation_member(
+        id: &str,
+        conv_id: &str,
+        hash: &str,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        validate::peer("uri", hash)?;
+        JamiClient::shared()?.configuration("rmConversationMember", (id, conv_id, hash))
+    }
+
+    /**
+     * Get calls hosted in a conversation
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @return current calls
+     */
+    pub fn try_get_active_calls(id: &str, conv_id: &str) -> Result<Vec<CallInfo>, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        let (calls,): (Vec<HashMap<String, String>>,) =
+            JamiClient::shared()?.configuration("getActiveCalls", (id, conv_id))?;
+        Ok(calls
+            .iter()
+            .map(|infos| CallInfo::from_map(conv_id, infos))
+            .collect())
+    }
+
+    /**
+     * Get calls hosted in all conversations of an account
+     * @param id        Id of the account
+     * @return current calls
+     */
+    pub fn try_active_calls(id: &str) -> Result<Vec<CallInfo>, JamiError> {
+        let mut res = Vec::new();
+        for conv_id in Jami::try_get_conversations(id)? {
+            res.append(&mut Jami::try_get_active_calls(id, &conv_id)?);
+        }
+        Ok(res)
+    }
+
+    /**
+     * Get the call hosted in a conversation
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @return the id of the call if any
+     */
+    pub fn try_call_for_conversation(id: &str, conv_id: &str) -> Result<Option<String>, JamiError> {
+        Ok(Jami::try_get_active_calls(id, conv_id)?
+            .into_iter()
+            .next()
+            .map(|call| call.id))
+    }
+
+    /**
+     * Place an audio call (cf try_place_call_with_media for other medias)
+     * @param account_id    Id of the account
+     * @param uri           Peer to call (or swarm:/rdv: uri)
+     * @return the id of the call
+     */
+    pub fn place_call(account_id: &str, uri: &str) -> Result<String, JamiError> {
+        JamiClient::shared()?.place_call(account_id, uri)
+    }
+
+    /**
+     * Hang up a call
+     * @param account_id    Id of the account
+     * @param call_id       Id of the call
+     * @return NotFound if the daemon doesn't know the call
+     */
+    pub fn hang_up(account_id: &str, call_id: &str) -> Result<(), JamiError> {
+        JamiClient::shared()?.hang_up(account_id, call_id)
+    }
+
+    /**
+     * Place a call
+     * @param account_id    Id of the account
+     * @param uri           Peer to call (or swarm:/rdv: uri)
+     * @param media_list    Medias to use (cf call::default_media_list)
+     * @return the id of the call, OperationFailed if the daemon refused it
+     */
+    pub fn try_place_call_with_media(
+        account_id: &str,
+        uri: &str,
+        media_list: Vec<MediaAttribute>,
+    ) -> Result<String, JamiError> {
+        JamiClient::shared()?.place_call_with_media(account_id, uri, media_list)
+    }
+
+    /**
+     * Accept an incoming call
+     * @param account_id    Id of the account
+     * @param call_id       Id of the call
+     * @param media_list    Medias to use
+     * @return if successful
+     */
+    pub fn try_accept_with_media(
+        account_id: &str,
+        call_id: &str,
+        media_list: Vec<MediaAttribute>,
+    ) -> Result<bool, JamiError> {
+        validate::account_id("account_id", account_id)?;
+        let (accepted,): (bool,) = JamiClient::shared()?.call_manager(
+            "acceptWithMedia",
+            (account_id, call_id, MediaAttribute::to_maps(&media_list)),
+        )?;
+        Ok(accepted)
+    }
+
+    /**
+     * Answer to Event::MediaChangeRequested (e.g. the peer adds a video)
+     * @param account_id    Id of the account
+     * @param call_id       Id of the call
+     * @param media_list    Accepted medias
+     */
+    pub fn try_answer_media_change_request(
+        account_id: &str,
+        call_id: &str,
+        media_list: Vec<MediaAttribute>,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", account_id)?;
+        JamiClient::shared()?.call_manager(
+            "answerMediaChangeRequest",
+            (account_id, call_id, MediaAttribute::to_maps(&media_list)),
+        )
+    }
+
+    /**
+     * Change medias of a call (e.g. add a video to an audio call)
+     * @param account_id    Id of the account
+     * @param call_id       Id of the call
+     * @param media_list    New medias
+     * @return if successful
+     */
+    pub fn try_request_media_change(
+        account_id: &str,
+        call_id: &str,
+        media_list: Vec<MediaAttribute>,
+    ) -> Result<bool, JamiError> {
+        validate::account_id("account_id", account_id)?;
+        let (requested,): (bool,) = JamiClient::shared()?.call_manager(
+            "requestMediaChange",
+            (account_id, call_id, MediaAttribute::to_maps(&media_list)),
+        )?;
+        Ok(requested)
+    }
+
+    /**
+     * Get the medias of a call
+     * @param account_id    Id of the account
+     * @param call_id       Id of the call
+     * @return the medias, empty if the call doesn't exist
      */
-    pub fn send_trust_request(id: &String, to: &String, payloads: Vec<u8>) {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let _: Result<(), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "sendTrustRequest",
-            (id, to, payloads),
-        );
+    pub fn try_get_current_media_list(
+        account_id: &str,
+        call_id: &str,
+    ) -> Result<Vec<MediaAttribute>, JamiError> {
+        validate::account_id("account_id", account_id)?;
+        let (media_list,): (Vec<HashMap<String, String>>,) =
+            JamiClient::shared()?.call_manager("currentMediaList", (account_id, call_id))?;
+        Ok(MediaAttribute::from_maps(&media_list))
     }
 
     /**
-     * Accept a trust request
-     * @param id        Account id
-     * @param from      Contact uri
-     * @return if successful
+     * Get the cameras known by the daemon
+     * @return the devices names
      */
-    pub fn accept_trust_request(id: &String, from: &String) -> bool {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let result: Result<(bool,), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "acceptTrustRequest",
-            (id, from),
-        );
-        if result.is_ok() {
-            let result = result.unwrap().0;
-            return result;
-        }
-        false
+    pub fn try_get_video_devices() -> Result<Vec<String>, JamiError> {
+        let (devices,): (Vec<String>,) =
+            JamiClient::shared()?.video_manager("getDeviceList", ())?;
+        Ok(devices)
     }
 
     /**
-     * Discard a trust request
-     * @param id        Account id
-     * @param from      Contact uri
+     * Change the video source of a call
+     * @param account_id    Id of the account
+     * @param call_id       Id of the call
+     * @param resource      New source, e.g. camera://<device>
      * @return if successful
      */
-    pub fn discard_trust_request(id: &String, from: &String) -> bool {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let result: Result<(bool,), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "discardTrustRequest",
-            (id, from),
-        );
-        if result.is_ok() {
-            let result = result.unwrap().0;
-            return result;
-        }
-        false
+    pub fn try_switch_input(
+        account_id: &str,
+        call_id: &str,
+        resource: &str,
+    ) -> Result<bool, JamiError> {
+        validate::account_id("account_id", account_id)?;
+        let (switched,): (bool,) =
+            JamiClient::shared()?.call_manager("switchInput", (account_id, call_id, resource))?;
+        Ok(switched)
     }
 
     /**
-     * Get current members for a conversation
-     * @param id        Id of the account
-     * @param convid    Id of the conversation
-     * @return current members
+     * Switch a call to the next camera (e.g. front/back flip)
+     * @param account_id    Id of the account
+     * @param call_id       Id of the call
+     * @return the new camera, None if there is no other camera or if the switch failed
      */
-    pub fn get_members(id: &String, convid: &String) -> Vec<HashMap<String, String>> {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let result: Result<(Vec<HashMap<String, String>>,), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "getConversationMembers",
-            (id, convid),
-        );
-        if result.is_ok() {
-            let result = result.unwrap().0;
-            return result;
+    pub fn next_camera(account_id: &str, call_id: &str) -> Option<String> {
+        let devices = Jami::try_get_video_devices().unwrap_or_default();
+        if devices.len() < 2 {
+            return None;
         }
-
-        Vec::new()
+        let current = Jami::try_get_current_media_list(account_id, call_id)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|media| media.media_type == MediaType::Video)
+            .map(|media| media.source.trim_start_matches("camera://").to_string())
+            .unwrap_or_default();
+        let next = match devices.iter().position(|device| *device == current) {
+            Some(index) => &devices[(index + 1) % devices.len()],
+            // Default camera, or the camera was unplugged
+            None => &devices[0],
+        };
+        if Jami::switch_camera_to(account_id, call_id, next) {
+            return Some(next.clone());
+        }
+        None
     }
 
     /**
-     * Get conversation's infos
-     * @param id        Id of the account
-     * @param convid    Id of the conversation
-     * @return current infos
+     * Switch a call to a camera
+     * @param account_id    Id of the account
+     * @param call_id       Id of the call
+     * @param device        Name of the camera (cf try_get_video_devices)
+     * @return false if the camera doesn't exist or if the switch failed
      */
-    pub fn get_conversation_infos(id: &String, convid: &String) -> HashMap<String, String> {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let result: Result<(HashMap<String, String>,), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "conversationInfos",
-            (id, convid),
-        );
-        if result.is_ok() {
-            let result = result.unwrap().0;
-            return result;
+    pub fn switch_camera_to(account_id: &str, call_id: &str, device: &str) -> bool {
+        let devices = Jami::try_get_video_devices().unwrap_or_default();
+        if !devices.iter().any(|d| d == device) {
+            return false;
         }
-
-        HashMap::new()
+        Jami::try_switch_input(account_id, call_id, &format!("camera://{}", device))
+            .unwrap_or(false)
     }
 
     /**
-     * Update conversation's i nfos
-     * @param id        Id of the account
-     * @param convid    Id of the conversation
-     * @param infos     New infos
+     * Host a call in a swarm
+     * @param account_id    Id of the account
+     * @param conv_id       Id of the conversation
+     * @return the id of the call
      */
-    pub fn update_conversation_infos(id: &String, convid: &String, infos: HashMap<String, String>) {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let _: Result<(), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "updateConversationInfos",
-            (id, convid, infos),
-        );
+    pub fn try_host_conference(account_id: &str, conv_id: &str) -> Result<String, JamiError> {
+        validate::conversation_id("conversation_id", conv_id)?;
+        Jami::try_place_call_with_media(
+            account_id,
+            &format!("swarm:{}", conv_id),
+            call::default_media_list(false),
+        )
     }
 
     /**
-     * Start conversation
-     * @param id        Id of the account
+     * Join a call hosted in a swarm
+     * @param account_id    Id of the account
+     * @param conv_id       Id of the conversation
+     * @param call_uri      CallInfo::join_uri() or "host uri/host device/call id"
+     * @return the id of the call
      */
-    pub fn start_conversation(id: &String) -> String {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let result: Result<(String,), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "startConversation",
-            (id,),
-        );
-        if result.is_ok() {
-            let result = result.unwrap().0;
-            return result;
-        }
-
-        String::new()
+    pub fn try_join_conference(
+        account_id: &str,
+        conv_id: &str,
+        call_uri: &str,
+    ) -> Result<String, JamiError> {
+        let uri = if call_uri.starts_with("rdv:") {
+            call_uri.to_string()
+        } else {
+            format!("rdv:{}/{}", conv_id, call_uri)
+        };
+        Jami::try_place_call_with_media(account_id, &uri, call::default_media_list(false))
     }
 
     /**
-     * Get current conversations for account
-     * @param id        Id of the account
-     * @return current conversations
+     * Test the audio devices before a call: plays the dial tone on the speaker
+     * (CallManager.startTone) and starts the input meter (ConfigurationManager.startAudioMeter),
+     * so the microphone level is received as Event::AudioMeter
+     * @return if the test started
      */
-    pub fn get_conversations(id: &String) -> Vec<String> {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let result: Result<(Vec<String>,), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "getConversations",
-            (id,),
-        );
-        if result.is_ok() {
-            let result = result.unwrap().0;
-            return result;
-        }
-
-        Vec::new()
+    pub fn start_audio_test() -> bool {
+        Jami::audio_test(true)
     }
 
     /**
-     * Get current conversations requests for account
-     * @param id        Id of the account
-     * @return current conversations requests
+     * Stop the test started by start_audio_test
+     * @return if the test stopped
      */
-    pub fn get_conversations_requests(id: &String) -> Vec<HashMap<String, String>> {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let result: Result<(Vec<HashMap<String, String>>,), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "getConversationRequests",
-            (id,),
-        );
-        if result.is_ok() {
-            let result = result.unwrap().0;
-            return result;
-        }
+    pub fn stop_audio_test() -> bool {
+        Jami::audio_test(false)
+    }
 
-        Vec::new()
+    fn audio_test(start: bool) -> bool {
+        let client = match JamiClient::shared() {
+            Ok(client) => client,
+            Err(_) => return Default::default(),
+        };
+        // type 0 = dial tone
+        let tone: Result<(), _> = client.call_manager("startTone", (start as i32, 0i32));
+        let method = if start {
+            "startAudioMeter"
+        } else {
+            "stopAudioMeter"
+        };
+        let meter: Result<(), _> = client.configuration(method, (false,));
+        tone.is_ok() && meter.is_ok()
     }
 
     /**
-     * Decline a conversation request
-     * @param id        Id of the account
-     * @param conv_id   Id of the conversation
+     * Set the status of a received message, e.g. displayed
+     * @param account_id        Id of the account
+     * @param conversation_uri  swarm:<conversation id>
+     * @param message_id        Id of the message
+     * @param status            3 for displayed
+     * @return if successful
      */
-    pub fn decline_request(id: &String, conv_id: &String) {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let _: Result<(), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "declineConversationRequest",
-            (id, conv_id),
-        );
+    pub fn try_set_message_displayed(
+        account_id: &str,
+        conversation_uri: &str,
+        message_id: &str,
+        status: i32,
+    ) -> Result<bool, JamiError> {
+        validate::account_id("account_id", account_id)?;
+        let (displayed,): (bool,) = JamiClient::shared()?.configuration(
+            "setMessageDisplayed",
+            (account_id, conversation_uri, message_id, status),
+        )?;
+        Ok(displayed)
     }
 
     /**
-     * Accept a conversation request
-     * @param id        Id of the account
-     * @param conv_id   Id of the conversation
+     * Send a text message outside of swarms (SIP or legacy peers)
+     * The daemon queues it if the peer is offline, follow it with
+     * Event::TextMessageStatusChanged or try_get_message_status
+     * @param account_id    Id of the account
+     * @param to            Peer
+     * @param body          Text to send
+     * @return the id of the message
      */
-    pub fn accept_request(id: &String, conv_id: &String) {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let _: Result<(), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "acceptConversationRequest",
-            (id, conv_id),
-        );
+    pub fn try_send_text_message(account_id: &str, to: &str, body: &str) -> Result<u64, JamiError> {
+        validate::account_id("account_id", account_id)?;
+        let mut payloads = HashMap::new();
+        payloads.insert(String::from("text/plain"), body.to_string());
+        let (message_id,): (u64,) = JamiClient::shared()?
+            .configuration("sendTextMessage", (account_id, to, payloads, 0i32))?;
+        Ok(message_id)
     }
 
     /**
-     * Asynchronously load a conversation
-     * @param account
-     * @param conversation
-     * @param from              "" if latest else the commit id
-     * @param size              0 if all else max number of messages to get
-     * @return the id of the request
+     * Share a location with the members of a conversation. Like other Jami clients,
+     * the position is sent to each member as an application/geo account message.
+     * @param account_id    Id of the account
+     * @param conv_id       Id of the conversation
+     * @param lat           Latitude
+     * @param lon           Longitude
+     * @param duration      None for a one-shot position, else the duration of a live
+     *                      sharing (send the new positions with the same duration)
+     * @return the id of the message sent to the first member (0 if nothing was sent)
      */
-    pub fn load_conversation(
-        account: &String,
-        conversation: &String,
-        from: &String,
-        size: u32,
-    ) -> u32 {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let result: Result<(u32,), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "loadConversationMessages",
-            (account, conversation, from, size),
-        );
-        if result.is_ok() {
-            let result = result.unwrap().0;
-            return result;
+    pub fn send_location(
+        account_id: &str,
+        conv_id: &str,
+        lat: f64,
+        lon: f64,
+        duration: Option<Duration>,
+    ) -> u64 {
+        let location = GeoLocation {
+            lat,
+            lon,
+            time: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            duration,
+            stop: false,
+        };
+        let id = Jami::send_geo(account_id, conv_id, &location);
+        if let (Some(duration), true) = (duration, id != 0) {
+            let mut shares = LOCATION_SHARES.lock().unwrap();
+            let known = shares
+                .iter()
+                .any(|(account, conv, _, _)| account == account_id && conv == conv_id);
+            if !known {
+                shares.push((
+                    account_id.to_string(),
+                    conv_id.to_string(),
+                    Instant::now(),
+                    duration,
+                ));
+            }
         }
-        0
+        id
     }
 
     /**
-     * Remove a conversation for an account
-     * @param id        Id of the account
-     * @param conv_id   Id of the conversation
-     * @return if the conversation is removed
-     */
-    pub fn rm_conversation(id: &String, conv_id: &String) -> bool {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let result: Result<(bool,), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "removeConversation",
-            (id, conv_id),
-        );
-        if result.is_ok() {
-            let result = result.unwrap().0;
-            return result;
-        }
-        false
+     * Stop a live location sharing: peers receive a Stop message.
+     * Nothing is kept by the daemon: if the client stops without calling this,
+     * peers stop showing the location when the duration sent with the positions ends
+     * @param account_id    Id of the account
+     * @param conv_id       Id of the conversation
+     * @return the id of the message sent to the first member (0 if nothing was sent)
+     */
+    pub fn stop_sharing_location(account_id: &str, conv_id: &str) -> u64 {
+        LOCATION_SHARES
+            .lock()
+            .unwrap()
+            .retain(|(account, conv, _, _)| account != account_id || conv != conv_id);
+        let location = GeoLocation {
+            lat: 0.,
+            lon: 0.,
+            time: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            duration: None,
+            stop: true,
+        };
+        Jami::send_geo(account_id, conv_id, &location)
     }
 
     /**
-     * Invite a member to a conversation
-     * @param id        Id of the account
-     * @param conv_id   Id of the conversation
-     * @param hash      Id of the member to invite
+     * Get the live locations shared by this process and not stopped or expired
+     * @return (account id, conversation id, remaining time)
      */
-    pub fn add_conversation_member(id: &String, conv_id: &String, hash: &String) {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let _: Result<(), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "addConversationMember",
-            (id, conv_id, hash),
-        );
+    pub fn active_location_shares() -> Vec<(String, String, Duration)> {
+        let mut shares = LOCATION_SHARES.lock().unwrap();
+        shares.retain(|(_, _, started, duration)| started.elapsed() < *duration);
+        shares
+            .iter()
+            .map(|(account, conv, started, duration)| {
+                let remaining = duration.checked_sub(started.elapsed()).unwrap_or_default();
+                (account.clone(), conv.clone(), remaining)
+            })
+            .collect()
     }
 
     /**
-     * Remove a member from a conversation
-     * @param id        Id of the account
-     * @param conv_id   Id of the conversation
-     * @param hash      Id of the member to invite
+     * Send an application/geo message to the other members of a conversation
+     * @return the id of the message sent to the first member (0 if nothing was sent)
      */
-    pub fn rm_conversation_member(id: &String, conv_id: &String, hash: &String) {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let _: Result<(), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "rmConversationMember",
-            (id, conv_id, hash),
-        );
+    fn send_geo(account_id: &str, conv_id: &str, location: &GeoLocation) -> u64 {
+        let own_uri = Jami::try_get_account(account_id)
+            .ok()
+            .flatten()
+            .map(|account| account.hash().to_string())
+            .unwrap_or_default();
+        let mut payloads = HashMap::new();
+        payloads.insert(String::from("application/geo"), location.to_json());
+        let client = match JamiClient::shared() {
+            Ok(client) => client,
+            Err(_) => return Default::default(),
+        };
+        let mut first_id = 0;
+        for member in Jami::try_get_members(account_id, conv_id).unwrap_or_default() {
+            let uri = member.get("uri").cloned().unwrap_or_default();
+            if uri.is_empty() || uri == own_uri {
+                continue;
+            }
+            let result: Result<(u64,), _> = client.configuration(
+                "sendTextMessage",
+                (account_id, &uri, payloads.clone(), 0i32),
+            );
+            if let Ok((id,)) = result {
+                if first_id == 0 {
+                    first_id = id;
+                }
+            }
+        }
+        first_id
+    }
+
+    /**
+     * Get the status of a message sent with try_send_text_message
+     * The daemon identifies messages by id only, account_id is for consistency
+     * @param message_id    Id returned by try_send_text_message
+     * @return the status
+     */
+    pub fn try_get_message_status(message_id: u64) -> Result<MessageStatus, JamiError> {
+        let (status,): (i32,) =
+            JamiClient::shared()?.configuration("getMessageStatus", (message_id,))?;
+        Ok(MessageStatus::from(status))
     }
 
     /**
-     * Send a text message to a conversation swarm of an account
+     * Send a text message to a conversation swarm of an account (errors are ignored,
+     * cf try_send_message).
      * See https://git.jami.net/savoirfairelinux/jami-daemon/-/blob/master/bin/dbus/cx.ring.Ring.ConfigurationManager.xml
      * for more details of the API call.
      * @param account_id    Id of the account
@@ -1028,24 +4104,221 @@ impl Jami {
      * @param commit_id     commitId
      * @param flag          flag
      */
+    #[deprecated(note = "errors are hidden, use Jami::try_send_message")]
     pub fn send_message(
-        account_id: &String,
-        conv_id: &String,
-        message: &String,
-        commit_id: &String,
+        account_id: &str,
+        conv_id: &str,
+        message: &str,
+        commit_id: &str,
         flag: &i32,
     ) {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let _: Result<(), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
+        if let Ok(client) = JamiClient::shared() {
+            let _: Result<(), _> = client.configuration(
+                "sendMessage",
+                (account_id, conv_id, message, commit_id, *flag),
+            );
+        }
+    }
+
+    /**
+     * Send a text message to a conversation swarm of an account, reporting errors.
+     * If the conversation is unknown (e.g. removed meanwhile), the event loop
+     * emits Event::ConversationRemoved unless the daemon already did it
+     * @param account_id    Id of the account
+     * @param conv_id       Id of the conversation
+     * @param message       Message to be sent to conversation swarm
+     * @param reply_to      Commit answered, or empty
+     * @param flag          flag
+     * @return NotFound if the conversation doesn't exist
+     */
+    pub fn try_send_message(
+        account_id: &str,
+        conv_id: &str,
+        message: &str,
+        reply_to: &str,
+        flag: i32,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", account_id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        // The daemon ignores messages for unknown conversations, without error
+        let client = JamiClient::shared()?;
+        let (conversations,): (Vec<String>,) =
+            client.configuration("getConversations", (account_id,))?;
+        if !conversations.iter().any(|id| id == conv_id) {
+            return Err(Jami::conversation_not_found(account_id, conv_id));
+        }
+        let result: Result<(), _> = client.configuration(
             "sendMessage",
-            (account_id, conv_id, message, commit_id, flag),
+            (account_id, conv_id, message, reply_to, flag),
         );
+        match result {
+            // Removed between the two calls, for daemons reporting it
+            Err(JamiError::MethodCall { ref source, .. }) if Jami::is_not_found_error(source) => {
+                Err(Jami::conversation_not_found(account_id, conv_id))
+            }
+            result => result,
+        }
+    }
+
+    fn is_not_found_error(e: &dbus::Error) -> bool {
+        e.name()
+            .map(|name| name.ends_with("NotFound"))
+            .unwrap_or(false)
+            || e.message()
+                .map(|message| message.to_lowercase().contains("not found"))
+                .unwrap_or(false)
+    }
+
+    // Ask the event loops to send Event::ConversationRemoved
+    fn conversation_not_found(account_id: &str, conv_id: &str) -> JamiError {
+        REMOVAL_LISTENERS.lock().unwrap().retain(|listener| {
+            listener
+                .send((account_id.to_string(), conv_id.to_string()))
+                .is_ok()
+        });
+        JamiError::NotFound(format!("conversation {}", conv_id))
+    }
+
+    // Record that ConversationRemoved is sent, return false if it already was
+    fn announce_removal(account_id: &str, conv_id: &str) -> bool {
+        let key = (account_id.to_string(), conv_id.to_string());
+        let mut announced = ANNOUNCED_REMOVALS.lock().unwrap();
+        if announced.contains(&key) {
+            return false;
+        }
+        if announced.len() >= MAX_ANNOUNCED_REMOVALS {
+            announced.remove(0);
+        }
+        announced.push(key);
+        true
+    }
+
+    /**
+     * Send the same text message to several conversations of an account.
+     * Messages are sent over one connection, a few at a time, and each one waits for
+     * its commit (messageReceived) to get the id of the message
+     * @param account_id    Id of the account
+     * @param conv_ids      Conversations, duplicates are sent once
+     * @param body          Message to send
+     * @return the outcome per conversation, InvalidArgument if the body is empty
+     */
+    pub async fn broadcast_message(
+        account_id: &str,
+        conv_ids: &[String],
+        body: &str,
+    ) -> Result<BroadcastResult, JamiError> {
+        validate::account_id("account_id", account_id)?;
+        if body.is_empty() {
+            return Err(JamiError::InvalidArgument(
+                String::from("body"),
+                String::from("empty message"),
+            ));
+        }
+        let mut targets: Vec<String> = Vec::new();
+        for conv_id in conv_ids {
+            if !targets.contains(conv_id) {
+                targets.push(conv_id.clone());
+            }
+        }
+        let (resource, conn) = bus::connect_async().map_err(JamiError::BusConnection)?;
+
+        let work = async {
+            // conversation -> waiting for the commit of the message
+            let pending: Arc<Mutex<HashMap<String, tokio::sync::oneshot::Sender<String>>>> =
+                Arc::new(Mutex::new(HashMap::new()));
+            let account = account_id.to_string();
+            let text = body.to_string();
+            let waiting = pending.clone();
+            let mr =
+                MatchRule::new_signal(bus::config().config_manager_interface, "messageReceived");
+            let ic = conn
+                .add_match(mr)
+                .await
+                .map_err(|e| JamiError::method_call("AddMatch", e))?
+                .cb(
+                    move |_,
+                          (account_id, conversation_id, payloads): (
+                        String,
+                        String,
+                        HashMap<String, String>,
+                    )| {
+                        if account_id == account
+                            && payloads.get("type").map(|t| &**t) == Some("text/plain")
+                            && payloads.get("body") == Some(&text)
+                        {
+                            let sender = waiting.lock().unwrap().remove(&conversation_id);
+                            if let Some(sender) = sender {
+                                let _ =
+                                    sender.send(payloads.get("id").cloned().unwrap_or_default());
+                            }
+                        }
+                        true
+                    },
+                );
+
+            let semaphore = Arc::new(tokio::sync::Semaphore::new(MAX_BROADCAST_CALLS));
+            let handles: Vec<_> = targets
+                .iter()
+                .map(|conv_id| {
+                    let (conn, pending, semaphore) =
+                        (conn.clone(), pending.clone(), semaphore.clone());
+                    let (account_id, conv_id, body) =
+                        (account_id.to_string(), conv_id.clone(), body.to_string());
+                    tokio::spawn(async move {
+                        validate::conversation_id("conversation_id", &conv_id)?;
+                        let _permit = semaphore.acquire().await;
+                        let (commit_tx, commit_rx) = tokio::sync::oneshot::channel();
+                        pending.lock().unwrap().insert(conv_id.clone(), commit_tx);
+                        let proxy = nonblock::Proxy::new(
+                            bus::config().service,
+                            bus::config().config_manager_path,
+                            bus::call_timeout(),
+                            conn,
+                        );
+                        let result: Result<(), _> = calllog::logged_call_async(
+                            &proxy,
+                            &bus::config().config_manager_interface,
+                            "sendMessage",
+                            (&*account_id, &*conv_id, &*body, "", 0),
+                        )
+                        .await;
+                        let result = match result {
+                            Ok(()) => {
+                                match tokio::time::timeout(BROADCAST_TIMEOUT, commit_rx).await {
+                                    Ok(Ok(id)) => Ok(id),
+                                    _ => Err(JamiError::Timeout),
+                                }
+                            }
+                            Err(e) if Jami::is_not_found_error(&e) => {
+                                Err(Jami::conversation_not_found(&account_id, &conv_id))
+                            }
+                            Err(e) => Err(JamiError::method_call("sendMessage", e)),
+                        };
+                        pending.lock().unwrap().remove(&conv_id);
+                        result
+                    })
+                })
+                .collect();
+
+            let mut broadcast = BroadcastResult::default();
+            for (conv_id, handle) in targets.iter().zip(handles) {
+                let outcome = match handle.await {
+                    Ok(outcome) => outcome,
+                    Err(e) => Err(JamiError::OperationFailed(e.to_string())),
+                };
+                if outcome.is_ok() {
+                    broadcast.succeeded += 1;
+                }
+                broadcast.outcomes.push((conv_id.clone(), outcome));
+            }
+            let _ = conn.remove_match(ic.token()).await;
+            Ok(broadcast)
+        };
+
+        tokio::select! {
+            err = resource => Err(JamiError::ConnectionLost(err.to_string())),
+            result = work => result,
+        }
     }
 
     /**
@@ -1056,26 +4329,131 @@ impl Jami {
      * @param conv_id           Id of the conversation
      * @param file_path         Path of the file to send
      * @param file_display_name Label attached to file for display
-     * @param reply_to          reply to
+     * @param reply_to          reply to (cf try_send_file to get errors)
      */
+    #[deprecated(note = "errors are hidden, use Jami::try_send_file")]
     pub fn send_file(
-        account_id: &String,
-        conv_id: &String,
-        file_path: &String,
-        file_display_name: &String,
-        reply_to: &String,
+        account_id: &str,
+        conv_id: &str,
+        file_path: &str,
+        file_display_name: &str,
+        reply_to: &str,
     ) {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let _: Result<(), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
+        let _ = Jami::try_send_file(account_id, conv_id, file_path, file_display_name, reply_to);
+    }
+
+    /**
+     * Send a file to a conversation swarm of an account, reporting errors.
+     * sendFile doesn't give the id of the transfer: it is the fileId of the commit
+     * of the file, which is also in Event::DataTransferEvent (cf send_file_tracked)
+     * @param account_id        Id of the account
+     * @param conv_id           Id of the conversation
+     * @param file_path         Path of the file to send
+     * @param file_display_name Label attached to file for display
+     * @param reply_to          Commit answered, or empty
+     */
+    pub fn try_send_file(
+        account_id: &str,
+        conv_id: &str,
+        file_path: &str,
+        file_display_name: &str,
+        reply_to: &str,
+    ) -> Result<(), JamiError> {
+        validate::account_id("account_id", account_id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        validate::path("path", file_path)?;
+        JamiClient::shared()?.configuration(
             "sendFile",
             (account_id, conv_id, file_path, file_display_name, reply_to),
-        );
+        )
+    }
+
+    /**
+     * Send a file and get the id of its transfer, to follow it (data_transfer_info)
+     * or cancel it. sendFile doesn't return the id, so it is read from the first
+     * dataTransferEvent of the conversation, created by this sending
+     * @param account_id        Id of the account
+     * @param conv_id           Id of the conversation
+     * @param file_path         Path of the file to send
+     * @param file_display_name Label attached to file for display
+     * @param reply_to          Commit answered, or empty
+     * @return the id of the transfer, as in Event::DataTransferEvent.
+     *         Timeout if the daemon doesn't announce the transfer within 10s
+     */
+    pub async fn send_file_tracked(
+        account_id: &str,
+        conv_id: &str,
+        file_path: &str,
+        file_display_name: &str,
+        reply_to: &str,
+    ) -> Result<u64, JamiError> {
+        validate::account_id("account_id", account_id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        validate::path("path", file_path)?;
+        let (resource, conn) = bus::connect_async().map_err(JamiError::BusConnection)?;
+
+        let work = async {
+            let (created_tx, created_rx) = oneshot::channel();
+            let created_tx = Arc::new(Mutex::new(Some(created_tx)));
+            let (account, conversation) = (account_id.to_string(), conv_id.to_string());
+            let mr =
+                MatchRule::new_signal(bus::config().config_manager_interface, "dataTransferEvent");
+            let ic =
+                conn.add_match(mr)
+                    .await
+                    .map_err(|e| JamiError::method_call("AddMatch", e))?
+                    .cb(
+                        move |_,
+                              (account_id, conversation_id, id, code): (
+                            String,
+                            String,
+                            u64,
+                            i32,
+                        )| {
+                            if account_id == account
+                                && conversation_id == conversation
+                                && DataTransferEventCode::from(code)
+                                    == DataTransferEventCode::Created
+                            {
+                                if let Some(created_tx) = created_tx.lock().unwrap().take() {
+                                    let _ = created_tx.send(id);
+                                }
+                            }
+                            true
+                        },
+                    );
+
+            let proxy = nonblock::Proxy::new(
+                bus::config().service,
+                bus::config().config_manager_path,
+                bus::call_timeout(),
+                conn.clone(),
+            );
+            let result: Result<(), _> = calllog::logged_call_async(
+                &proxy,
+                &bus::config().config_manager_interface,
+                "sendFile",
+                (account_id, conv_id, file_path, file_display_name, reply_to),
+            )
+            .await;
+            let result = match result {
+                Ok(()) => match tokio::time::timeout(SENT_FILE_TIMEOUT, created_rx).await {
+                    Ok(Ok(id)) => Ok(id),
+                    _ => Err(JamiError::Timeout),
+                },
+                Err(e) if Jami::is_not_found_error(&e) => {
+                    Err(Jami::conversation_not_found(account_id, conv_id))
+                }
+                Err(e) => Err(JamiError::method_call("sendFile", e)),
+            };
+            let _ = conn.remove_match(ic.token()).await;
+            result
+        };
+
+        tokio::select! {
+            err = resource => Err(JamiError::ConnectionLost(err.to_string())),
+            result = work => result,
+        }
     }
 
     /**
@@ -1084,58 +4462,81 @@ impl Jami {
      * @param conv_id           Related conversation
      * @param tid               File transfer to accepts
      * @param path              Path of the file to send
-     * @return if an error occurs
+     * @return if an error occurs (0 on any error, cf try_accept_file_transfer)
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_accept_file_transfer")]
+    pub fn accept_file_transfer(id: &str, conv_id: &str, tid: u64, path: &str) -> u32 {
+        Jami::try_accept_file_transfer(id, conv_id, tid, path).unwrap_or_default()
+    }
+
+    /**
+     * Accepts a file transfer, reporting errors
+     * @param account_id        Related account
+     * @param conv_id           Related conversation
+     * @param tid               File transfer to accepts
+     * @param path              Path of the file to send
+     * @return the code of the daemon (cf TransferError::from_code)
      */
-    pub fn accept_file_transfer(
-        id: &String,
-        conv_id: &String,
+    pub fn try_accept_file_transfer(
+        id: &str,
+        conv_id: &str,
         tid: u64,
-        path: &String,
-    ) -> u32 {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let result: Result<(u32,), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "acceptFileTransfer",
-            (id, conv_id, tid, path, 0 as i64),
-        );
-        if result.is_ok() {
-            return result.unwrap().0;
-        }
-        0
+        path: &str,
+    ) -> Result<u32, JamiError> {
+        Jami::try_accept_file_transfer_from(id, conv_id, tid, path, 0)
     }
 
     /**
-     * Cancel a file transfer
+     * Accepts a file transfer, resuming it from a byte offset. Pass the
+     * current length of a partially downloaded file to only get the rest
      * @param account_id        Related account
      * @param conv_id           Related conversation
      * @param tid               File transfer to accepts
-     * @return if an error occurs
+     * @param path              Path of the file to send
+     * @param offset            Bytes already received (0 to restart), non-negative
+     * @return the code of the daemon (cf TransferError::from_code)
      */
-    pub fn cancel_file_transfer(
-        id: &String,
-        conv_id: &String,
+    pub fn try_accept_file_transfer_from(
+        id: &str,
+        conv_id: &str,
         tid: u64,
-    ) -> u32 {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
-        let result: Result<(u32,), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "cancelDataTransfer",
-            (id, conv_id, tid),
-        );
-        if result.is_ok() {
-            return result.unwrap().0;
-        }
-        0
+        path: &str,
+        offset: i64,
+    ) -> Result<u32, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        validate::path("path", path)?;
+        validate::offset("offset", offset)?;
+        let (code,): (u32,) = JamiClient::shared()?
+            .configuration("acceptFileTransfer", (id, conv_id, tid, path, offset))?;
+        Ok(code)
+    }
+
+    /**
+     * Cancel a file transfer
+     * @param account_id        Related account
+     * @param conv_id           Related conversation
+     * @param tid               File transfer to accepts
+     * @return if an error occurs (0 on any error, cf try_cancel_file_transfer)
+     */
+    #[deprecated(note = "errors are hidden, use Jami::try_cancel_file_transfer")]
+    pub fn cancel_file_transfer(id: &str, conv_id: &str, tid: u64) -> u32 {
+        Jami::try_cancel_file_transfer(id, conv_id, tid).unwrap_or_default()
+    }
+
+    /**
+     * Cancel a file transfer, reporting errors
+     * @param account_id        Related account
+     * @param conv_id           Related conversation
+     * @param tid               File transfer to accepts
+     * @return the code of the daemon (cf TransferError::from_code)
+     */
+    pub fn try_cancel_file_transfer(id: &str, conv_id: &str, tid: u64) -> Result<u32, JamiError> {
+        validate::account_id("account_id", id)?;
+        validate::conversation_id("conversation_id", conv_id)?;
+        let (code,): (u32,) =
+            JamiClient::shared()?.configuration("cancelDataTransfer", (id, conv_id, tid))?;
+        Ok(code)
     }
 
     /**
@@ -1143,20 +4544,31 @@ impl Jami {
      * @param account_id        Related account
      * @param conv_id           Related conversation
      * @param tid               File transfer to accepts
-     * @return if an error occurs or the info
+     * @return the info, None on any error, e.g. a stale id (cf try_data_transfer_info)
      */
+    #[deprecated(note = "errors are hidden, use Jami::try_data_transfer_info")]
     pub fn data_transfer_info(
         account_id: String,
         conv_id: String,
         tid: u64,
     ) -> Option<DataTransferInfo> {
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy(
-            "cx.ring.Ring",
-            "/cx/ring/Ring/ConfigurationManager",
-            Duration::from_millis(5000),
-        );
+        Jami::try_data_transfer_info(account_id, conv_id, tid).ok()
+    }
 
+    /**
+     * Get informations about a transfer, reporting errors
+     * @param account_id    Related account
+     * @param conv_id       Related conversation
+     * @param tid           Id of the transfer
+     * @return the informations, TransferError::Unknown for a stale id
+     */
+    pub fn try_data_transfer_info(
+        account_id: String,
+        conv_id: String,
+        tid: u64,
+    ) -> Result<DataTransferInfo, TransferError> {
+        validate::account_id("account_id", &account_id)?;
+        validate::conversation_id("conversation_id", &conv_id)?;
         let info = DataTransferInfo {
             account_id: String::new(),
             last_event: 0,
@@ -1170,16 +4582,84 @@ impl Jami {
             path: String::new(),
             mimetype: String::new()
         };
-        let result: Result<(u32, (String, u32, u32, i64, i64, String, String, String, String, String, String),), _> = proxy.method_call(
-            "cx.ring.Ring.ConfigurationManager",
-            "dataTransferInfo",
-            (account_id, conv_id, tid, info.tuple()),
-        );
-        if result.is_ok() {
-            return Some(DataTransferInfo::from_tuple(result.unwrap().1));
+        let (code, info): (u32, DataTransferTuple) = JamiClient::shared()?
+            .configuration("dataTransferInfo", (account_id, conv_id, tid, info.tuple()))?;
+        match TransferError::from_code(code) {
+            None => Ok(DataTransferInfo::from_tuple(info)),
+            Some(error) => Err(error),
         }
-        None
     }
 
+    /**
+     * Get the directory where received files go (cf set_download_dir). The daemon
+     * has no such preference, so it is stored by jami-rs
+     * @return the directory set, else the download directory of the user
+     */
+    pub fn get_download_dir() -> PathBuf {
+        let stored = Jami::download_dir_config()
+            .and_then(|config| fs::read_to_string(config).ok())
+            .map(|path| PathBuf::from(path.trim_end_matches('\n')))
+            .filter(|path| !path.as_os_str().is_empty());
+        stored
+            .or_else(dirs::download_dir)
+            .or_else(dirs::home_dir)
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    /**
+     * Change the directory where received files go
+     * @param path      The directory
+     * @param create    Create it (and its parents) if missing
+     * @return InvalidArgument if the directory is missing or not writable
+     */
+    pub fn set_download_dir(path: &Path, create: bool) -> Result<(), JamiError> {
+        let invalid = |reason: &str| {
+            JamiError::InvalidArgument(
+                String::from("path"),
+                format!("{}: {}", path.display(), reason),
+            )
+        };
+        validate::path("path", &path.to_string_lossy())?;
+        if !path.exists() {
+            if !create {
+                return Err(invalid("does not exist"));
+            }
+            fs::create_dir_all(path).map_err(|e| invalid(&e.to_string()))?;
+        }
+        if !path.is_dir() {
+            return Err(invalid("not a directory"));
+        }
+        tempfile::tempfile_in(path).map_err(|_| invalid("not writable"))?;
+        let path = path.canonicalize().map_err(|e| invalid(&e.to_string()))?;
+        let config = Jami::download_dir_config().ok_or_else(|| {
+            JamiError::OperationFailed(String::from("no configuration directory"))
+        })?;
+        if let Some(parent) = config.parent() {
+            fs::create_dir_all(parent).map_err(|e| JamiError::OperationFailed(e.to_string()))?;
+        }
+        fs::write(config, path.to_string_lossy().as_bytes())
+            .map_err(|e| JamiError::OperationFailed(e.to_string()))
+    }
+
+    fn download_dir_config() -> Option<PathBuf> {
+        get_app_dir(
+            AppDataType::UserConfig,
+            &AppInfo {
+                name: "jami",
+                author: "SFL",
+            },
+            "jami-rs-download-dir",
+        )
+        .ok()
+    }
 
+    /**
+     * Get the last calls to the daemon and signals received (call-log feature).
+     * Passwords, PINs, message bodies and vCard photos are redacted
+     * @return up to 512 records, oldest first
+     */
+    #[cfg(feature = "call-log")]
+    pub fn recent_calls() -> Vec<calllog::CallRecord> {
+        calllog::recent_calls()
+    }
 }
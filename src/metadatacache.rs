@@ -0,0 +1,211 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use super::client::JamiClient;
+use super::error::JamiError;
+use super::Event;
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// (account id, conversation id)
+type ConversationKey = (String, String);
+
+/**
+ * Memoize account details, conversation infos and members, which are often
+ * read while drawing a UI. Entries are dropped when the related events arrive
+ * (cf apply). Jami's functions and JamiClient never use it, so nothing is cached
+ * unless calls go through a MetadataCache.
+ **/
+pub struct MetadataCache {
+    client: JamiClient,
+    // account id -> details
+    details: Mutex<HashMap<String, HashMap<String, String>>>,
+    infos: Mutex<HashMap<ConversationKey, HashMap<String, String>>>,
+    members: Mutex<HashMap<ConversationKey, Vec<HashMap<String, String>>>>,
+}
+
+impl MetadataCache {
+    /**
+     * @param client    Client used on cache misses
+     * @return an empty cache
+     */
+    pub fn new(client: JamiClient) -> MetadataCache {
+        MetadataCache {
+            client,
+            details: Mutex::new(HashMap::new()),
+            infos: Mutex::new(HashMap::new()),
+            members: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /**
+     * Get the details of an account (getAccountDetails)
+     * @param id        Id of the account
+     * @return the details
+     */
+    pub fn account_details(&self, id: &str) -> Result<HashMap<String, String>, JamiError> {
+        if let Some(details) = self.details.lock().unwrap().get(id) {
+            return Ok(details.clone());
+        }
+        let (details,): (HashMap<String, String>,) =
+            self.client.configuration("getAccountDetails", (id,))?;
+        self.details
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), details.clone());
+        Ok(details)
+    }
+
+    /**
+     * Get the infos of a conversation (conversationInfos)
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @return the infos
+     */
+    pub fn conversation_infos(
+        &self,
+        id: &str,
+        conv_id: &str,
+    ) -> Result<HashMap<String, String>, JamiError> {
+        let key = (id.to_string(), conv_id.to_string());
+        if let Some(infos) = self.infos.lock().unwrap().get(&key) {
+            return Ok(infos.clone());
+        }
+        let (infos,): (HashMap<String, String>,) = self
+            .client
+            .configuration("conversationInfos", (id, conv_id))?;
+        self.infos.lock().unwrap().insert(key, infos.clone());
+        Ok(infos)
+    }
+
+    /**
+     * Get the members of a conversation (getConversationMembers)
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     * @return the members
+     */
+    pub fn members(
+        &self,
+        id: &str,
+        conv_id: &str,
+    ) -> Result<Vec<HashMap<String, String>>, JamiError> {
+        let key = (id.to_string(), conv_id.to_string());
+        if let Some(members) = self.members.lock().unwrap().get(&key) {
+            return Ok(members.clone());
+        }
+        let (members,): (Vec<HashMap<String, String>>,) = self
+            .client
+            .configuration("getConversationMembers", (id, conv_id))?;
+        self.members.lock().unwrap().insert(key, members.clone());
+        Ok(members)
+    }
+
+    /**
+     * Drop the entries made obsolete by an event
+     * @param event     Event from the event loop
+     */
+    pub fn apply<I>(&self, event: &Event<I>) {
+        match event {
+            Event::AccountsChanged() => self.details.lock().unwrap().clear(),
+            Event::AccountDetailsChanged(id, details) => {
+                self.details
+                    .lock()
+                    .unwrap()
+                    .insert(id.clone(), details.clone());
+            }
+            Event::ConversationReady(id, conv_id) | Event::ConversationRemoved(id, conv_id) => {
+                self.invalidate_conversation(id, conv_id)
+            }
+            Event::ConversationMemberEvent {
+                account_id,
+                conversation_id,
+                ..
+            } => {
+                let key = (account_id.clone(), conversation_id.clone());
+                self.members.lock().unwrap().remove(&key);
+            }
+            // Title, description, avatar... are changed by a commit
+            Event::Message {
+                account_id,
+                conversation_id,
+                payloads,
+            } if payloads.get("type").map(|t| &**t) == Some("application/update-profile") => {
+                let key = (account_id.clone(), conversation_id.clone());
+                self.infos.lock().unwrap().remove(&key);
+            }
+            _ => {}
+        }
+    }
+
+    /**
+     * Drop everything cached for a conversation
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     */
+    pub fn invalidate_conversation(&self, id: &str, conv_id: &str) {
+        let key = (id.to_string(), conv_id.to_string());
+        self.infos.lock().unwrap().remove(&key);
+        self.members.lock().unwrap().remove(&key);
+    }
+
+    /**
+     * Drop everything cached for an account, including its conversations
+     * @param id        Id of the account
+     */
+    pub fn invalidate_account(&self, id: &str) {
+        self.details.lock().unwrap().remove(id);
+        self.infos
+            .lock()
+            .unwrap()
+            .retain(|(account, _), _| account != id);
+        self.members
+            .lock()
+            .unwrap()
+            .retain(|(account, _), _| account != id);
+    }
+
+    /**
+     * Drop everything, e.g. if events were missed
+     */
+    pub fn invalidate(&self) {
+        self.details.lock().unwrap().clear();
+        self.infos.lock().unwrap().clear();
+        self.members.lock().unwrap().clear();
+    }
+
+    /**
+     * Fetch again what is cached for a conversation
+     * @param id        Id of the account
+     * @param conv_id   Id of the conversation
+     */
+    pub fn refresh(&self, id: &str, conv_id: &str) -> Result<(), JamiError> {
+        self.invalidate_conversation(id, conv_id);
+        self.conversation_infos(id, conv_id)?;
+        self.members(id, conv_id)?;
+        Ok(())
+    }
+}
@@ -24,16 +24,25 @@
  * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
  * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  **/
+use super::uri;
+
 use serde::{Deserialize, Serialize};
+use std::fs;
 
 /**
  * Represent a User Profile, just here to store informations. Cf ProfileManager for logic
  **/
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Profile {
     pub uri: String,
     pub username: String,
     pub display_name: String,
+    // base64 content of the PHOTO field of the vCard
+    #[serde(default)]
+    pub avatar: String,
+    // TYPE parameter of the PHOTO field. Only a hint, it's often wrong
+    #[serde(default)]
+    pub avatar_type: String,
 }
 
 impl Profile {
@@ -42,7 +51,73 @@ impl Profile {
             uri: String::new(),
             username: String::new(),
             display_name: String::new(),
+            avatar: String::new(),
+            avatar_type: String::new(),
+        }
+    }
+
+    /**
+     * Load a vCard file
+     * @param path   Path to load
+     * @return the profile if the file is readable
+     */
+    pub fn load(path: &str) -> Option<Profile> {
+        let content = fs::read_to_string(path).ok()?;
+        Some(Profile::from_vcard(&content))
+    }
+
+    /**
+     * Parse a vCard
+     * @param content   Content of the vCard
+     * @return the profile
+     */
+    pub fn from_vcard(content: &str) -> Profile {
+        // TODO better parsing?
+        // For now we don't care about the full vcard file
+        // and current Rust libs seems bugguy
+        let mut profile = Profile::new();
+
+        let mut in_photo = false;
+        for line in content.lines() {
+            if in_photo && (line.starts_with(' ') || line.starts_with('\t')) {
+                // Folded line (RFC 6350 3.2)
+                profile.avatar += line.trim();
+                continue;
+            }
+            in_photo = false;
+            if line.starts_with("FN:") {
+                profile.display_name = String::from(line.strip_prefix("FN:").unwrap());
+            } else if line.starts_with("TEL") {
+                // e.g. TEL;other:ring:<hash>. Sent by peers, ignored if malformed
+                let value = line.rsplit(':').next().unwrap_or_default();
+                if uri::is_hash(value) {
+                    profile.uri = value.to_string();
+                }
+            } else if line.starts_with("PHOTO") {
+                let sep = match line.find(':') {
+                    Some(sep) => sep,
+                    None => continue,
+                };
+                let (params, value) = (&line[..sep], &line[(sep + 1)..]);
+                for param in params.split(';') {
+                    if let Some(t) = param.strip_prefix("TYPE=") {
+                        profile.avatar_type = t.to_string();
+                    }
+                }
+                // vCard 4 uses data URIs (data:image/png;base64,...)
+                profile.avatar = match value.find("base64,") {
+                    Some(idx) => {
+                        if let Some(t) = value[..idx].strip_prefix("data:") {
+                            profile.avatar_type = t.trim_end_matches(';').to_string();
+                        }
+                        value[(idx + 7)..].to_string()
+                    }
+                    None => value.to_string(),
+                };
+                in_photo = true;
+            }
         }
+        profile
     }
 
     pub fn bestname(&self) -> String {
@@ -54,4 +129,86 @@ impl Profile {
         }
         return self.uri.clone();
     }
+
+    /**
+     * Decode the avatar of the profile
+     * @return the raw image if any
+     */
+    pub fn avatar_bytes(&self) -> Option<Vec<u8>> {
+        if self.avatar.is_empty() {
+            return None;
+        }
+        let data: String = self.avatar.split_whitespace().collect();
+        base64::decode(&data).ok()
+    }
+
+    /**
+     * Detect the mimetype of the avatar from its magic bytes.
+     * The TYPE parameter of the vCard is only used if the content is not recognized
+     * @return the mimetype if any
+     */
+    pub fn avatar_mimetype(&self) -> Option<&'static str> {
+        let bytes = self.avatar_bytes()?;
+        if let Some(mimetype) = Profile::mimetype_from_bytes(&bytes) {
+            return Some(mimetype);
+        }
+        match &*self.avatar_type.to_uppercase() {
+            "PNG" | "IMAGE/PNG" => Some("image/png"),
+            "JPEG" | "JPG" | "IMAGE/JPEG" => Some("image/jpeg"),
+            "GIF" | "IMAGE/GIF" => Some("image/gif"),
+            "WEBP" | "IMAGE/WEBP" => Some("image/webp"),
+            _ => None,
+        }
+    }
+
+    /**
+     * Convert the avatar to PNG (for toolkits only supporting PNG)
+     * @return the PNG image if the avatar can be decoded
+     */
+    #[cfg(feature = "image")]
+    pub fn avatar_as_png(&self) -> Option<Vec<u8>> {
+        let bytes = self.avatar_bytes()?;
+        if Profile::mimetype_from_bytes(&bytes) == Some("image/png") {
+            return Some(bytes);
+        }
+        let img = image::load_from_memory(&bytes).ok()?;
+        let mut png = Vec::new();
+        img.write_to(&mut png, image::ImageOutputFormat::Png).ok()?;
+        Some(png)
+    }
+
+    /**
+     * Shrink an image to fit in size x size pixels, as PNG
+     * @param bytes     Image to shrink
+     * @param size      Max width and height
+     * @return the PNG image if the input can be decoded
+     */
+    #[cfg(feature = "image")]
+    pub fn shrink_image(bytes: &[u8], size: u32) -> Option<Vec<u8>> {
+        let img = image::load_from_memory(bytes).ok()?.thumbnail(size, size);
+        let mut png = Vec::new();
+        img.write_to(&mut png, image::ImageOutputFormat::Png).ok()?;
+        Some(png)
+    }
+
+    /**
+     * Detect the mimetype of an image from its magic bytes
+     * @param bytes     Image to check
+     * @return the mimetype if recognized
+     */
+    pub fn mimetype_from_bytes(bytes: &[u8]) -> Option<&'static str> {
+        if bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+            return Some("image/png");
+        }
+        if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            return Some("image/jpeg");
+        }
+        if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+            return Some("image/gif");
+        }
+        if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+            return Some("image/webp");
+        }
+        None
+    }
 }
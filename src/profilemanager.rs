@@ -25,14 +25,25 @@
  * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  **/
 
+use super::account;
 use super::profile::Profile;
 
 use app_dirs::{get_app_dir, AppDataType, AppInfo};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use unicode_normalization::UnicodeNormalization;
+
+/**
+ * A result of ProfileManager::search
+ **/
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchHit {
+    pub uri: String,
+    pub bestname: String,
+    // Lower is better. 0 is an exact match on the registered name
+    pub score: u32,
+}
 
 /**
  * A class used to share user profiles for all Jami accounts
@@ -40,6 +51,9 @@ use std::io::{BufRead, BufReader};
 #[derive(Serialize, Deserialize)]
 pub struct ProfileManager {
     pub profiles: HashMap<String, Profile>,
+    // uris of the profiles loaded per account
+    #[serde(default)]
+    pub account_profiles: HashMap<String, HashSet<String>>,
 }
 
 impl ProfileManager {
@@ -50,6 +64,7 @@ impl ProfileManager {
     pub fn new() -> Self {
         Self {
             profiles: HashMap::new(),
+            account_profiles: HashMap::new(),
         }
     }
 
@@ -76,38 +91,44 @@ impl ProfileManager {
         }
         let paths = paths.unwrap();
 
+        let mut uris = HashSet::new();
         for path in paths {
-            self.load_profile(&path.unwrap().path().to_str().unwrap().to_string());
+            if let Some(uri) = self.load_profile_uri(path.unwrap().path().to_str().unwrap()) {
+                uris.insert(uri);
+            }
         }
+        self.account_profiles
+            .entry(account_id.clone())
+            .or_default()
+            .extend(uris);
     }
 
     /**
      * Load one profile
      * @param path   Path to load
      */
-    pub fn load_profile(&mut self, path: &String) {
-        // TODO better parsing?
-        // For now we don't care about the full vcard file
-        // and current Rust libs seems bugguy
-        let buf = BufReader::new(File::open(path).unwrap());
-        let mut profile = Profile::new();
-
-        for line in buf.lines() {
-            let line = line.unwrap();
-            if line.starts_with("FN:") {
-                profile.display_name = String::from(line.strip_prefix("FN:").unwrap());
-            } else if line.starts_with("TEL") {
-                profile.uri = line[(line.len() - 40)..].to_string();
-            }
-        }
+    pub fn load_profile(&mut self, path: &str) {
+        self.load_profile_uri(path);
+    }
+
+    /**
+     * Load one profile
+     * @param path   Path to load
+     * @return the uri of the loaded profile if any
+     */
+    pub fn load_profile_uri(&mut self, path: &str) -> Option<String> {
+        let mut profile = Profile::load(path)?;
 
         if self.profiles.contains_key(&profile.uri) {
             profile.username = self.profiles.get(&profile.uri).unwrap().username.clone();
         }
 
-        if !profile.uri.is_empty() {
-            self.profiles.insert(profile.uri.clone(), profile);
+        if profile.uri.is_empty() {
+            return None;
         }
+        let uri = profile.uri.clone();
+        self.profiles.insert(uri.clone(), profile);
+        Some(uri)
     }
 
     /**
@@ -138,4 +159,85 @@ impl ProfileManager {
         }
         uri.to_string()
     }
+
+    /**
+     * Return the identity of a user, same rules as Account::display_identity
+     * (registered name, else display name, else the start of the uri)
+     * @param uri        Id of the user
+     * @return The name to show
+     */
+    pub fn display_identity(&self, uri: &str) -> String {
+        match self.profiles.get(uri) {
+            Some(profile) => {
+                account::display_identity(&profile.username, &profile.display_name, uri)
+            }
+            None => account::display_identity("", "", uri),
+        }
+    }
+
+    /**
+     * Search cached profiles by prefix. Case and diacritic insensitive.
+     * Exact registered names are first, then prefixes of registered names,
+     * of display names (or any word of the display name), then of uris.
+     * No daemon call is done, so this can be used on each keystroke.
+     * @param account_id    Account to search in ("" for all profiles)
+     * @param query         Text typed by the user
+     * @param limit         Max number of results (0 for no limit)
+     * @return the hits sorted by relevance
+     */
+    pub fn search(&self, account_id: &str, query: &str, limit: usize) -> Vec<SearchHit> {
+        let query = ProfileManager::fold(query.trim());
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let uris = self.account_profiles.get(account_id);
+        let mut hits = Vec::new();
+        for (uri, profile) in &self.profiles {
+            if !account_id.is_empty() && !uris.is_some_and(|uris| uris.contains(uri)) {
+                continue;
+            }
+            let username = ProfileManager::fold(&profile.username);
+            let display_name = ProfileManager::fold(&profile.display_name);
+            let score = if !username.is_empty() && username == query {
+                0
+            } else if username.starts_with(&query) {
+                1
+            } else if display_name.starts_with(&query) {
+                2
+            } else if display_name
+                .split_whitespace()
+                .any(|w| w.starts_with(&query))
+            {
+                3
+            } else if uri.starts_with(&query) {
+                4
+            } else {
+                continue;
+            };
+            hits.push(SearchHit {
+                uri: uri.clone(),
+                bestname: profile.bestname(),
+                score,
+            });
+        }
+        hits.sort_by(|a, b| {
+            a.score
+                .cmp(&b.score)
+                .then_with(|| a.bestname.to_lowercase().cmp(&b.bestname.to_lowercase()))
+        });
+        if limit != 0 {
+            hits.truncate(limit);
+        }
+        hits
+    }
+
+    /**
+     * Lowercase and remove diacritics (é -> e)
+     */
+    fn fold(text: &str) -> String {
+        text.nfd()
+            .filter(|c| !unicode_normalization::char::is_combining_mark(*c))
+            .collect::<String>()
+            .to_lowercase()
+    }
 }
@@ -0,0 +1,275 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use super::account::{Account, AccountType};
+use super::accountbuilder::{AccountBuilder, AccountCreationProgress};
+use super::client::JamiClient;
+use super::error::JamiError;
+use super::eventloop::EventLoopHandle;
+use super::profilemanager::ProfileManager;
+use super::transfermanager::TransferManager;
+use super::{Event, EventLoopOptions, Jami};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::stream::StreamExt;
+use tokio::sync::broadcast;
+
+// Events kept for slow subscribers of JamiSession::events
+const EVENT_CAPACITY: usize = 128;
+
+/**
+ * How JamiSession::start selects its account
+ **/
+#[derive(Clone, Debug, Default)]
+pub struct SessionConfig {
+    // Account to use, else the first enabled Jami account
+    pub account_id: Option<String>,
+    // Create a Jami account with this alias if there is none
+    pub create_account: Option<String>,
+    // Accept trust and conversation requests as they arrive
+    pub accept_trust_requests: bool,
+}
+
+/**
+ * Everything a simple bot needs: an account, the event loop, a client and the managers.
+ * Only composes the public API, which stays usable without it
+ **/
+pub struct JamiSession {
+    account: Account,
+    client: JamiClient,
+    events: EventLoopHandle,
+    stop: Arc<AtomicBool>,
+    accept_trust_requests: Arc<AtomicBool>,
+    profiles: Arc<Mutex<ProfileManager>>,
+    transfers: Mutex<Option<Arc<Mutex<TransferManager>>>>,
+}
+
+impl JamiSession {
+    /**
+     * Connect, select or create the account and start the event loop.
+     * Must be called from a tokio runtime
+     * @param config    How to select the account
+     * @return the running session
+     */
+    pub async fn start(config: SessionConfig) -> Result<JamiSession, JamiError> {
+        let client = JamiClient::new()?;
+        let events = EventLoopHandle::new(EVENT_CAPACITY);
+        let stop = Arc::new(AtomicBool::new(false));
+        events.start(stop.clone(), EventLoopOptions::default());
+
+        let account = match JamiSession::select_account(&config, &events).await {
+            Ok(account) => account,
+            Err(e) => {
+                stop.store(true, Ordering::Relaxed);
+                return Err(e);
+            }
+        };
+
+        let mut profiles = ProfileManager::new();
+        profiles.load_from_account(&account.id);
+        let session = JamiSession {
+            account,
+            client,
+            events,
+            stop,
+            accept_trust_requests: Arc::new(AtomicBool::new(config.accept_trust_requests)),
+            profiles: Arc::new(Mutex::new(profiles)),
+            transfers: Mutex::new(None),
+        };
+        session.spawn_handler();
+        Ok(session)
+    }
+
+    async fn select_account(
+        config: &SessionConfig,
+        events: &EventLoopHandle,
+    ) -> Result<Account, JamiError> {
+        if let Some(id) = &config.account_id {
+            let id = id.clone();
+            let account = tokio::task::spawn_blocking(move || Jami::try_get_account(&id))
+                .await
+                .map_err(|e| JamiError::OperationFailed(e.to_string()))??;
+            return account.ok_or_else(|| {
+                JamiError::InvalidArgument(
+                    String::from("account_id"),
+                    String::from("unknown account"),
+                )
+            });
+        }
+
+        let account = tokio::task::spawn_blocking(|| {
+            Jami::try_get_account_list().map(|accounts| {
+                accounts
+                    .into_iter()
+                    .find(|account| account.enabled && account.kind == AccountType::Ring)
+            })
+        })
+        .await
+        .map_err(|e| JamiError::OperationFailed(e.to_string()))??;
+        if let Some(account) = account {
+            return Ok(account);
+        }
+
+        let alias = match &config.create_account {
+            Some(alias) => alias,
+            None => {
+                return Err(JamiError::InvalidArgument(
+                    String::from("account_id"),
+                    String::from("no enabled Jami account"),
+                ))
+            }
+        };
+        let (id, mut progress) = AccountBuilder::new_ring()
+            .alias(alias)
+            .display_name(alias)
+            .build_with_progress(events)?;
+        while let Some(stage) = progress.next().await {
+            if let AccountCreationProgress::Failed(reason) = stage {
+                return Err(JamiError::OperationFailed(reason));
+            }
+        }
+        tokio::task::spawn_blocking(move || Jami::try_get_account(&id))
+            .await
+            .map_err(|e| JamiError::OperationFailed(e.to_string()))??
+            .ok_or_else(|| JamiError::OperationFailed(String::from("created account not found")))
+    }
+
+    // Accept requests (if enabled) and keep profiles up to date
+    fn spawn_handler(&self) {
+        let mut rx = self.events.subscribe();
+        let account_id = self.account.id.clone();
+        let accept = self.accept_trust_requests.clone();
+        let profiles = self.profiles.clone();
+        tokio::spawn(async move {
+            loop {
+                let event = match rx.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::RecvError::Closed) => break,
+                };
+                match event {
+                    Event::IncomingContactRequest {
+                        account_id: id,
+                        from,
+                        ..
+                    } if id == account_id && accept.load(Ordering::Relaxed) => {
+                        let _ = tokio::task::spawn_blocking(move || {
+                            Jami::try_accept_trust_request(&id, &from)
+                        })
+                        .await;
+                    }
+                    Event::ConversationRequest(id, conv_id)
+                        if id == account_id && accept.load(Ordering::Relaxed) =>
+                    {
+                        let _ = tokio::task::spawn_blocking(move || {
+                            Jami::try_accept_request(&id, &conv_id)
+                        })
+                        .await;
+                    }
+                    Event::ProfileReceived(id, _, path) if id == account_id => {
+                        profiles.lock().unwrap().load_profile(&path);
+                    }
+                    Event::RegisteredNameFound(id, 0, address, name) if id == account_id => {
+                        profiles.lock().unwrap().username_found(&address, &name);
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    /**
+     * @return the account of the session
+     */
+    pub fn account(&self) -> &Account {
+        &self.account
+    }
+
+    /**
+     * @return the client shared by the session
+     */
+    pub fn client(&self) -> &JamiClient {
+        &self.client
+    }
+
+    /**
+     * @return the event loop, e.g. for Account::watch
+     */
+    pub fn event_loop(&self) -> &EventLoopHandle {
+        &self.events
+    }
+
+    /**
+     * @return a receiver of the events of all accounts sent after this call
+     */
+    pub fn events(&self) -> broadcast::Receiver<Event<()>> {
+        self.events.subscribe()
+    }
+
+    /**
+     * Profiles of the contacts, updated with received profiles and name lookups
+     */
+    pub fn profiles(&self) -> Arc<Mutex<ProfileManager>> {
+        self.profiles.clone()
+    }
+
+    /**
+     * Transfers, opened on first use (jami::app_data + jami-cli.db)
+     */
+    pub fn transfers(&self) -> Arc<Mutex<TransferManager>> {
+        self.transfers
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| Arc::new(Mutex::new(TransferManager::new())))
+            .clone()
+    }
+
+    /**
+     * Send a text message from the account of the session
+     * @param conv_id   Id of the conversation
+     * @param body      Text to send
+     */
+    pub fn send_text(&self, conv_id: &str, body: &str) -> Result<(), JamiError> {
+        self.client
+            .configuration("sendMessage", (&self.account.id, conv_id, body, "", 0i32))
+    }
+
+    /**
+     * Accept, or stop accepting, incoming trust and conversation requests
+     * @param accept    If requests are accepted
+     */
+    pub fn accept_all_trust_requests(&self, accept: bool) {
+        self.accept_trust_requests.store(accept, Ordering::Relaxed);
+    }
+
+    /**
+     * Stop the event loop. Subscribers get their last events then see the channel closed
+     */
+    pub fn shutdown(self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,654 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use super::{DataTransferInfo, DataTransferTuple};
+
+use dbus::arg::AppendAll;
+use dbus::blocking::Connection;
+use dbus::channel::{Channel, Sender};
+use dbus::{Message, MethodErr};
+use dbus_crossroads::Crossroads;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+
+/**
+ * The private bus and the server, started once: libdbus reads DBUS_SESSION_BUS_ADDRESS
+ * only once per process
+ **/
+struct SharedBus {
+    address: String,
+    state: Arc<Mutex<FakeState>>,
+    // sh wrapper killing dbus-daemon when its stdin (kept by this process) is closed
+    _wrapper: Child,
+}
+
+static SESSION_BUS: Mutex<Option<SharedBus>> = Mutex::new(None);
+
+/**
+ * A message received by the fake daemon with sendMessage
+ **/
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SentMessage {
+    pub account_id: String,
+    pub conversation_id: String,
+    pub body: String,
+    pub reply_to: String,
+    pub flag: i32,
+}
+
+/**
+ * What the fake daemon answers, can be changed while it runs
+ **/
+#[derive(Default)]
+pub struct FakeState {
+    // getAccountList, in order
+    pub accounts: Vec<String>,
+    // getAccountDetails, unknown accounts get an empty map
+    pub details: HashMap<String, HashMap<String, String>>,
+    // getVolatileAccountDetails
+    pub volatile_details: HashMap<String, HashMap<String, String>>,
+    // getConversations, by account
+    pub conversations: HashMap<String, Vec<String>>,
+    // sendMessage answers a NotFound error for these conversations
+    pub removed_conversations: Vec<String>,
+    // dataTransferInfo, by file id
+    pub transfers: HashMap<u64, DataTransferInfo>,
+    // Received by sendMessage, each one is answered by messageReceived
+    pub sent_messages: Vec<SentMessage>,
+    // (conversation id, path) received by sendFile
+    pub sent_files: Vec<(String, String)>,
+    // (account id, uri) of each placeCallWithMedia, the call id is the index
+    pub placed_calls: Vec<(String, String)>,
+    // Ids of the calls not hung up
+    pub active_calls: Vec<String>,
+    // getContacts, by account then uri, true if banned
+    pub contacts: HashMap<String, HashMap<String, bool>>,
+    // getDefaultModerators, by account
+    pub moderators: HashMap<String, Vec<String>>,
+    // isAllModerators, by account
+    pub all_moderators: HashMap<String, bool>,
+    // (account id, display name, avatar, file type) received by updateProfile
+    pub updated_profiles: Vec<(String, String, String, String)>,
+    // getConversationMembers (uris), by conversation
+    pub members: HashMap<String, Vec<String>>,
+    // getSubscriptions, by account then uri, true if online
+    pub subscriptions: HashMap<String, HashMap<String, bool>>,
+    // These methods answer a Failed error, e.g. "addContact" (placeCallWithMedia answers "")
+    pub failing_methods: Vec<String>,
+}
+
+/**
+ * A minimal ConfigurationManager, CallManager and PresenceManager on a private bus,
+ * to test without jamid.
+ * The bus becomes the session bus of the process, so Jami's functions, JamiClient
+ * and handle_events talk to it. It is started by the first FakeDaemon and stops
+ * with the process, each FakeDaemon gets a fresh FakeState.
+ * Needs sh and dbus-daemon in the PATH.
+ **/
+pub struct FakeDaemon {
+    address: String,
+    state: Arc<Mutex<FakeState>>,
+    // One FakeDaemon at a time
+    _session: MutexGuard<'static, Option<SharedBus>>,
+}
+
+impl FakeDaemon {
+    /**
+     * Get the fake daemon, with empty answers. Waits for other FakeDaemons of the process
+     * @return the running daemon
+     */
+    pub fn start() -> FakeDaemon {
+        let mut session = SESSION_BUS.lock().unwrap_or_else(|e| e.into_inner());
+        if session.is_none() {
+            *session = Some(FakeDaemon::start_bus());
+        }
+        let bus = session.as_ref().unwrap();
+        let address = bus.address.clone();
+        let state = bus.state.clone();
+        *state.lock().unwrap_or_else(|e| e.into_inner()) = FakeState::default();
+        FakeDaemon {
+            address,
+            state,
+            _session: session,
+        }
+    }
+
+    fn start_bus() -> SharedBus {
+        let mut wrapper = Command::new("sh")
+            .args([
+                "-c",
+                "dbus-daemon --session --nofork --print-address & pid=$!; read _; kill $pid",
+            ])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("sh is needed by FakeDaemon");
+        let mut address = String::new();
+        BufReader::new(wrapper.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+        let address = address.trim().to_string();
+        assert!(!address.is_empty(), "dbus-daemon is needed by FakeDaemon");
+        std::env::set_var("DBUS_SESSION_BUS_ADDRESS", &address);
+
+        let state = Arc::new(Mutex::new(FakeState::default()));
+        let conn = FakeDaemon::connect(&address);
+        conn.request_name("cx.ring.Ring", false, true, false)
+            .unwrap();
+        let server_state = state.clone();
+        thread::spawn(move || {
+            let mut cr = Crossroads::new();
+            let iface = cr.register("cx.ring.Ring.ConfigurationManager", |b| {
+                b.method(
+                    "getAccountList",
+                    (),
+                    ("list",),
+                    |_, state: &mut Arc<Mutex<FakeState>>, ()| {
+                        Ok((state.lock().unwrap().accounts.clone(),))
+                    },
+                );
+                b.method(
+                    "getAccountDetails",
+                    ("accountId",),
+                    ("details",),
+                    |_, state: &mut Arc<Mutex<FakeState>>, (id,): (String,)| {
+                        let details = state.lock().unwrap().details.get(&id).cloned();
+                        Ok((details.unwrap_or_default(),))
+                    },
+                );
+                b.method(
+                    "getVolatileAccountDetails",
+                    ("accountId",),
+                    ("details",),
+                    |_, state: &mut Arc<Mutex<FakeState>>, (id,): (String,)| {
+                        let details = state.lock().unwrap().volatile_details.get(&id).cloned();
+                        Ok((details.unwrap_or_default(),))
+                    },
+                );
+                b.method(
+                    "getConversations",
+                    ("accountId",),
+                    ("conversations",),
+                    |_, state: &mut Arc<Mutex<FakeState>>, (id,): (String,)| {
+                        let conversations = state.lock().unwrap().conversations.get(&id).cloned();
+                        Ok((conversations.unwrap_or_default(),))
+                    },
+                );
+                b.method(
+                    "getContacts",
+                    ("accountId",),
+                    ("contacts",),
+                    |_, state: &mut Arc<Mutex<FakeState>>, (id,): (String,)| {
+                        let state = state.lock().unwrap();
+                        FakeDaemon::check_failing(&state, "getContacts")?;
+                        let contacts: Vec<HashMap<String, String>> = state
+                            .contacts
+                            .get(&id)
+                            .iter()
+                            .flat_map(|contacts| contacts.iter())
+                            .map(|(uri, banned)| {
+                                let mut contact = HashMap::new();
+                                contact.insert(String::from("id"), uri.clone());
+                                contact.insert(String::from("banned"), banned.to_string());
+                                contact
+                            })
+                            .collect();
+                        Ok((contacts,))
+                    },
+                );
+                b.method(
+                    "addContact",
+                    ("accountId", "uri"),
+                    (),
+                    |_, state: &mut Arc<Mutex<FakeState>>, (id, uri): (String, String)| {
+                        let mut state = state.lock().unwrap();
+                        FakeDaemon::check_failing(&state, "addContact")?;
+                        state.contacts.entry(id).or_default().insert(uri, false);
+                        Ok(())
+                    },
+                );
+                b.method(
+                    "removeContact",
+                    ("accountId", "uri", "ban"),
+                    (),
+                    |_,
+                     state: &mut Arc<Mutex<FakeState>>,
+                     (id, uri, ban): (String, String, bool)| {
+                        let mut state = state.lock().unwrap();
+                        FakeDaemon::check_failing(&state, "removeContact")?;
+                        let contacts = state.contacts.entry(id).or_default();
+                        if ban {
+                            contacts.insert(uri, true);
+                        } else {
+                            contacts.remove(&uri);
+                        }
+                        Ok(())
+                    },
+                );
+                b.method(
+                    "getDefaultModerators",
+                    ("accountId",),
+                    ("moderators",),
+                    |_, state: &mut Arc<Mutex<FakeState>>, (id,): (String,)| {
+                        let state = state.lock().unwrap();
+                        FakeDaemon::check_failing(&state, "getDefaultModerators")?;
+                        Ok((state.moderators.get(&id).cloned().unwrap_or_default(),))
+                    },
+                );
+                b.method(
+                    "updateProfile",
+                    ("accountId", "displayName", "avatar", "fileType", "flag"),
+                    (),
+                    |_,
+                     state: &mut Arc<Mutex<FakeState>>,
+                     (id, display_name, avatar, file_type, _): (
+                        String,
+                        String,
+                        String,
+                        String,
+                        i32,
+                    )| {
+                        let mut state = state.lock().unwrap();
+                        FakeDaemon::check_failing(&state, "updateProfile")?;
+                        state
+                            .updated_profiles
+                            .push((id, display_name, avatar, file_type));
+                        Ok(())
+                    },
+                );
+                b.method(
+                    "setAllModerators",
+                    ("accountId", "allModerators"),
+                    (),
+                    |_, state: &mut Arc<Mutex<FakeState>>, (id, enabled): (String, bool)| {
+                        let mut state = state.lock().unwrap();
+                        FakeDaemon::check_failing(&state, "setAllModerators")?;
+                        state.all_moderators.insert(id, enabled);
+                        Ok(())
+                    },
+                );
+                b.method(
+                    "isAllModerators",
+                    ("accountId",),
+                    ("allModerators",),
+                    |_, state: &mut Arc<Mutex<FakeState>>, (id,): (String,)| {
+                        let state = state.lock().unwrap();
+                        FakeDaemon::check_failing(&state, "isAllModerators")?;
+                        Ok((state.all_moderators.get(&id).cloned().unwrap_or(false),))
+                    },
+                );
+                b.method(
+                    "setDefaultModerator",
+                    ("accountId", "peerURI", "state"),
+                    (),
+                    |_,
+                     state: &mut Arc<Mutex<FakeState>>,
+                     (id, uri, moderator): (String, String, bool)| {
+                        let mut state = state.lock().unwrap();
+                        FakeDaemon::check_failing(&state, "setDefaultModerator")?;
+                        let moderators = state.moderators.entry(id).or_default();
+                        moderators.retain(|m| *m != uri);
+                        if moderator {
+                            moderators.push(uri);
+                        }
+                        Ok(())
+                    },
+                );
+                b.method(
+                    "getConversationMembers",
+                    ("accountId", "conversationId"),
+                    ("members",),
+                    |_, state: &mut Arc<Mutex<FakeState>>, (_, id): (String, String)| {
+                        let state = state.lock().unwrap();
+                        let members: Vec<HashMap<String, String>> = state
+                            .members
+                            .get(&id)
+                            .iter()
+                            .flat_map(|members| members.iter())
+                            .map(|uri| {
+                                let mut member = HashMap::new();
+                                member.insert(String::from("uri"), uri.clone());
+                                member.insert(String::from("role"), String::from("member"));
+                                member
+                            })
+                            .collect();
+                        Ok((members,))
+                    },
+                );
+                b.method(
+                    "addConversationMember",
+                    ("accountId", "conversationId", "contactUri"),
+                    (),
+                    |_,
+                     state: &mut Arc<Mutex<FakeState>>,
+                     (_, id, uri): (String, String, String)| {
+                        let mut state = state.lock().unwrap();
+                        FakeDaemon::check_failing(&state, "addConversationMember")?;
+                        state.members.entry(id).or_default().push(uri);
+                        Ok(())
+                    },
+                );
+                b.method(
+                    "removeConversationMember",
+                    ("accountId", "conversationId", "contactUri"),
+                    (),
+                    |_,
+                     state: &mut Arc<Mutex<FakeState>>,
+                     (_, id, uri): (String, String, String)| {
+                        let mut state = state.lock().unwrap();
+                        FakeDaemon::check_failing(&state, "removeConversationMember")?;
+                        state.members.entry(id).or_default().retain(|m| *m != uri);
+                        Ok(())
+                    },
+                );
+                b.method(
+                    "sendMessage",
+                    ("accountId", "conversationId", "message", "replyTo", "flag"),
+                    (),
+                    |ctx,
+                     state: &mut Arc<Mutex<FakeState>>,
+                     (account_id, conversation_id, body, reply_to, flag): (
+                        String,
+                        String,
+                        String,
+                        String,
+                        i32,
+                    )| {
+                        let mut state = state.lock().unwrap();
+                        if state.removed_conversations.contains(&conversation_id) {
+                            return Err(MethodErr::from((
+                                "cx.ring.Ring.Error.NotFound",
+                                "Conversation not found",
+                            )));
+                        }
+                        // Like the daemon, announce the commit of the message
+                        let mut payloads = HashMap::new();
+                        payloads.insert(
+                            String::from("id"),
+                            format!("{:040x}", state.sent_messages.len() + 1),
+                        );
+                        payloads.insert(String::from("type"), String::from("text/plain"));
+                        payloads.insert(String::from("body"), body.clone());
+                        let signal = ctx.make_signal(
+                            "messageReceived",
+                            (account_id.clone(), conversation_id.clone(), payloads),
+                        );
+                        ctx.push_msg(signal);
+                        state.sent_messages.push(SentMessage {
+                            account_id,
+                            conversation_id,
+                            body,
+                            reply_to,
+                            flag,
+                        });
+                        Ok(())
+                    },
+                );
+                b.method(
+                    "sendFile",
+                    (
+                        "accountId",
+                        "conversationId",
+                        "path",
+                        "displayName",
+                        "replyTo",
+                    ),
+                    (),
+                    |ctx,
+                     state: &mut Arc<Mutex<FakeState>>,
+                     (account_id, conversation_id, path, _, _): (
+                        String,
+                        String,
+                        String,
+                        String,
+                        String,
+                    )| {
+                        let mut state = state.lock().unwrap();
+                        if state.removed_conversations.contains(&conversation_id) {
+                            return Err(MethodErr::from((
+                                "cx.ring.Ring.Error.NotFound",
+                                "Conversation not found",
+                            )));
+                        }
+                        // Like the daemon, announce the transfer (created), ids from 1000
+                        let id = 1000 + state.sent_files.len() as u64;
+                        let signal = ctx.make_signal(
+                            "dataTransferEvent",
+                            (account_id, conversation_id.clone(), id, 1i32),
+                        );
+                        ctx.push_msg(signal);
+                        state.sent_files.push((conversation_id, path));
+                        Ok(())
+                    },
+                );
+                b.method(
+                    "dataTransferInfo",
+                    ("accountId", "conversationId", "fileId", "info"),
+                    ("error", "info"),
+                    |_,
+                     state: &mut Arc<Mutex<FakeState>>,
+                     (_, _, id, info): (
+                        String,
+                        String,
+                        u64,
+                        DataTransferTuple,
+                    )| {
+                        match state.lock().unwrap().transfers.get(&id) {
+                            Some(transfer) => Ok((0u32, transfer.tuple())),
+                            // Unknown transfer, the daemon leaves the info untouched
+                            None => Ok((1u32, info)),
+                        }
+                    },
+                );
+            });
+            cr.insert(
+                "/cx/ring/Ring/ConfigurationManager",
+                &[iface],
+                server_state.clone(),
+            );
+            let iface = cr.register("cx.ring.Ring.CallManager", |b| {
+                b.method(
+                    "placeCallWithMedia",
+                    ("accountId", "to", "mediaList"),
+                    ("callId",),
+                    |_,
+                     state: &mut Arc<Mutex<FakeState>>,
+                     (account_id, uri, _): (String, String, Vec<HashMap<String, String>>)| {
+                        let mut state = state.lock().unwrap();
+                        // Like the daemon, "" if the call can't be placed
+                        if state.failing_methods.iter().any(|m| m == "placeCallWithMedia") {
+                            return Ok((String::new(),));
+                        }
+                        let call_id = format!("call{}", state.placed_calls.len());
+                        state.placed_calls.push((account_id, uri));
+                        state.active_calls.push(call_id.clone());
+                        Ok((call_id,))
+                    },
+                );
+                b.method(
+                    "hangUp",
+                    ("accountId", "callId"),
+                    ("hungUp",),
+                    |_, state: &mut Arc<Mutex<FakeState>>, (_, call_id): (String, String)| {
+                        let mut state = state.lock().unwrap();
+                        let before = state.active_calls.len();
+                        state.active_calls.retain(|id| *id != call_id);
+                        Ok((state.active_calls.len() < before,))
+                    },
+                );
+            });
+            cr.insert("/cx/ring/Ring/CallManager", &[iface], server_state.clone());
+            let iface = cr.register("cx.ring.Ring.PresenceManager", |b| {
+                b.method(
+                    "getSubscriptions",
+                    ("accountId",),
+                    ("subscriptions",),
+                    |_, state: &mut Arc<Mutex<FakeState>>, (id,): (String,)| {
+                        let state = state.lock().unwrap();
+                        let subscriptions: Vec<HashMap<String, String>> = state
+                            .subscriptions
+                            .get(&id)
+                            .map(|buddies| {
+                                buddies
+                                    .iter()
+                                    .map(|(uri, online)| {
+                                        let mut buddy = HashMap::new();
+                                        buddy.insert(String::from("Buddy"), uri.clone());
+                                        let status = if *online { "Online" } else { "Offline" };
+                                        buddy.insert(String::from("Status"), status.to_string());
+                                        buddy
+                                    })
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+                        Ok((subscriptions,))
+                    },
+                );
+                b.method(
+                    "subscribeBuddy",
+                    ("accountId", "uri", "flag"),
+                    (),
+                    |_,
+                     state: &mut Arc<Mutex<FakeState>>,
+                     (id, uri, flag): (String, String, bool)| {
+                        let mut state = state.lock().unwrap();
+                        let buddies = state.subscriptions.entry(id).or_default();
+                        if flag {
+                            // Offline until the DHT finds the peer
+                            buddies.entry(uri).or_insert(false);
+                        } else {
+                            buddies.remove(&uri);
+                        }
+                        Ok(())
+                    },
+                );
+            });
+            cr.insert("/cx/ring/Ring/PresenceManager", &[iface], server_state);
+            // Fails when the bus stops, with the process
+            let _ = cr.serve(&conn);
+        });
+
+        SharedBus {
+            address,
+            state,
+            _wrapper: wrapper,
+        }
+    }
+
+    /**
+     * @param state     State of the daemon
+     * @param method    Called method
+     * @return a Failed error if the method is in failing_methods
+     */
+    fn check_failing(state: &FakeState, method: &str) -> Result<(), MethodErr> {
+        if state.failing_methods.iter().any(|m| m == method) {
+            return Err(MethodErr::from((
+                "cx.ring.Ring.Error.Failed",
+                "Injected failure",
+            )));
+        }
+        Ok(())
+    }
+
+    /**
+     * @return address of the private bus
+     */
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /**
+     * Answers of the daemon, to change them or to read received messages
+     */
+    pub fn state(&self) -> MutexGuard<'_, FakeState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /**
+     * Add an account to getAccountList
+     * @param id        Id of the account
+     * @param details   Its details
+     * @param volatile  Its volatile details
+     */
+    pub fn add_account(
+        &self,
+        id: &str,
+        details: HashMap<String, String>,
+        volatile: HashMap<String, String>,
+    ) {
+        let mut state = self.state();
+        state.accounts.push(id.to_string());
+        state.details.insert(id.to_string(), details);
+        state.volatile_details.insert(id.to_string(), volatile);
+    }
+
+    /**
+     * @return messages received by sendMessage, in order
+     */
+    pub fn sent_messages(&self) -> Vec<SentMessage> {
+        self.state().sent_messages.clone()
+    }
+
+    /**
+     * @return (conversation id, path) received by sendFile, in order
+     */
+    pub fn sent_files(&self) -> Vec<(String, String)> {
+        self.state().sent_files.clone()
+    }
+
+    /**
+     * Emit a signal of the ConfigurationManager
+     * @param member    Name of the signal, e.g. accountsChanged
+     * @param args      Arguments, as a tuple
+     */
+    pub fn emit<A: AppendAll>(&self, member: &str, args: A) {
+        self.emit_on("cx.ring.Ring.ConfigurationManager", member, args)
+    }
+
+    /**
+     * Emit a signal
+     * @param interface Interface, e.g. cx.ring.Ring.CallManager
+     * @param member    Name of the signal
+     * @param args      Arguments, as a tuple
+     */
+    pub fn emit_on<A: AppendAll>(&self, interface: &str, member: &str, args: A) {
+        let path = format!("/{}", interface.replace('.', "/"));
+        let mut signal = Message::new_signal(path, interface, member).unwrap();
+        signal.append_all(args);
+        let conn = FakeDaemon::connect(&self.address);
+        conn.send(signal).unwrap();
+        conn.channel().flush();
+    }
+
+    fn connect(address: &str) -> Connection {
+        let mut channel = Channel::open_private(address).unwrap();
+        channel.register().unwrap();
+        Connection::from(channel)
+    }
+}
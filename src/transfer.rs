@@ -0,0 +1,115 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use serde::{Deserialize, Serialize};
+
+/**
+ * State of a file transfer (cf Event::DataTransferEvent), as the daemon's
+ * DataTransferEventCode
+ **/
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum DataTransferEventCode {
+    Invalid,
+    // First event of a transfer
+    Created,
+    // The peer can't receive files
+    Unsupported,
+    WaitPeerAcceptance,
+    // Waiting for Jami::accept_file_transfer
+    WaitHostAcceptance,
+    Ongoing,
+    Finished,
+    ClosedByHost,
+    ClosedByPeer,
+    InvalidPathname,
+    UnjoinablePeer,
+    TimeoutExpired,
+    // Code not known by jami-rs
+    Unknown(i32),
+}
+
+impl From<i32> for DataTransferEventCode {
+    fn from(code: i32) -> Self {
+        match code {
+            0 => DataTransferEventCode::Invalid,
+            1 => DataTransferEventCode::Created,
+            2 => DataTransferEventCode::Unsupported,
+            3 => DataTransferEventCode::WaitPeerAcceptance,
+            4 => DataTransferEventCode::WaitHostAcceptance,
+            5 => DataTransferEventCode::Ongoing,
+            6 => DataTransferEventCode::Finished,
+            7 => DataTransferEventCode::ClosedByHost,
+            8 => DataTransferEventCode::ClosedByPeer,
+            9 => DataTransferEventCode::InvalidPathname,
+            10 => DataTransferEventCode::UnjoinablePeer,
+            11 => DataTransferEventCode::TimeoutExpired,
+            code => DataTransferEventCode::Unknown(code),
+        }
+    }
+}
+
+impl From<DataTransferEventCode> for i32 {
+    fn from(code: DataTransferEventCode) -> Self {
+        match code {
+            DataTransferEventCode::Invalid => 0,
+            DataTransferEventCode::Created => 1,
+            DataTransferEventCode::Unsupported => 2,
+            DataTransferEventCode::WaitPeerAcceptance => 3,
+            DataTransferEventCode::WaitHostAcceptance => 4,
+            DataTransferEventCode::Ongoing => 5,
+            DataTransferEventCode::Finished => 6,
+            DataTransferEventCode::ClosedByHost => 7,
+            DataTransferEventCode::ClosedByPeer => 8,
+            DataTransferEventCode::InvalidPathname => 9,
+            DataTransferEventCode::UnjoinablePeer => 10,
+            DataTransferEventCode::TimeoutExpired => 11,
+            DataTransferEventCode::Unknown(code) => code,
+        }
+    }
+}
+
+impl DataTransferEventCode {
+    /**
+     * @return if no other event will come for this transfer
+     */
+    pub fn is_over(&self) -> bool {
+        !matches!(
+            self,
+            DataTransferEventCode::Created
+                | DataTransferEventCode::WaitPeerAcceptance
+                | DataTransferEventCode::WaitHostAcceptance
+                | DataTransferEventCode::Ongoing
+                | DataTransferEventCode::Unknown(_)
+        )
+    }
+
+    /**
+     * @return if the transfer ended without the whole file
+     */
+    pub fn is_error(&self) -> bool {
+        self.is_over() && *self != DataTransferEventCode::Finished
+    }
+}
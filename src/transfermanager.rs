@@ -24,8 +24,11 @@
  * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
  * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  **/
+use super::Jami;
+
 use app_dirs::{get_app_dir, AppDataType, AppInfo};
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
 /**
  * A class used to store transfers per account per conversation
@@ -72,6 +75,16 @@ impl TransferManager {
         }
     }
 
+    /**
+     * Where to save a received file when the user doesn't choose
+     * @param display_name  Name of the file, given by the sender
+     * @return the path in Jami::get_download_dir, None if the name is not a file name
+     */
+    pub fn default_path(display_name: &str) -> Option<PathBuf> {
+        let name = Path::new(display_name).file_name()?;
+        Some(Jami::get_download_dir().join(name))
+    }
+
     pub fn path(&mut self, account_id: String, conv_id: String, tid: String) -> Option<String> {
         let conn = rusqlite::Connection::open(&*self.path).unwrap();
         let mut stmt = conn.prepare("SELECT path FROM transfers WHERE account_id=:account_id AND conversation_id=:conversation_id AND tid=:tid").unwrap();
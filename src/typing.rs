@@ -0,0 +1,163 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use super::Event;
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// Peers resend their status while typing, forget them if the stop is lost
+pub const DEFAULT_TYPING_EXPIRY: Duration = Duration::from_secs(15);
+
+/**
+ * Members typing in each conversation, from Event::ComposingStatusChanged.
+ * E.g. to show "Alice and Bob are typing..." in a group
+ **/
+#[derive(Clone, Debug)]
+pub struct TypingState {
+    expiry: Duration,
+    // (account, conversation) -> (member, last composing status)
+    typing: HashMap<(String, String), Vec<(String, Instant)>>,
+}
+
+impl Default for TypingState {
+    fn default() -> Self {
+        TypingState::new(DEFAULT_TYPING_EXPIRY)
+    }
+}
+
+impl TypingState {
+    /**
+     * @param expiry    Time after which a member without a new status stops typing
+     */
+    pub fn new(expiry: Duration) -> TypingState {
+        TypingState {
+            expiry,
+            typing: HashMap::new(),
+        }
+    }
+
+    /**
+     * Update members from an event
+     * @param event     Event from the event loop
+     * @return the conversation whose typing members changed
+     */
+    pub fn apply<I>(&mut self, event: &Event<I>) -> Option<String> {
+        match event {
+            Event::ComposingStatusChanged {
+                account_id,
+                conversation_id,
+                from,
+                status,
+            } => {
+                let key = (account_id.clone(), conversation_id.clone());
+                let members = self.typing.entry(key.clone()).or_default();
+                let index = members.iter().position(|(member, _)| member == from);
+                let changed = match (index, *status != 0) {
+                    (Some(index), true) => {
+                        members[index].1 = Instant::now();
+                        false
+                    }
+                    (None, true) => {
+                        members.push((from.clone(), Instant::now()));
+                        true
+                    }
+                    (Some(index), false) => {
+                        members.remove(index);
+                        true
+                    }
+                    (None, false) => false,
+                };
+                if members.is_empty() {
+                    self.typing.remove(&key);
+                }
+                if changed {
+                    Some(conversation_id.clone())
+                } else {
+                    None
+                }
+            }
+            // A message ends the typing of its author
+            Event::Message {
+                account_id,
+                conversation_id,
+                payloads,
+            } => {
+                let author = payloads.get("author")?;
+                let key = (account_id.clone(), conversation_id.clone());
+                let members = self.typing.get_mut(&key)?;
+                let index = members.iter().position(|(member, _)| member == author)?;
+                members.remove(index);
+                if members.is_empty() {
+                    self.typing.remove(&key);
+                }
+                Some(conversation_id.clone())
+            }
+            Event::ConversationRemoved(account_id, conversation_id) => {
+                self.typing
+                    .remove(&(account_id.clone(), conversation_id.clone()))?;
+                Some(conversation_id.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /**
+     * Forget members whose status expired
+     * @return conversations whose typing members changed
+     */
+    pub fn expire(&mut self) -> Vec<String> {
+        let expiry = self.expiry;
+        let mut changed = Vec::new();
+        self.typing.retain(|(_, conversation_id), members| {
+            let before = members.len();
+            members.retain(|(_, last)| last.elapsed() < expiry);
+            if members.len() != before {
+                changed.push(conversation_id.clone());
+            }
+            !members.is_empty()
+        });
+        changed
+    }
+
+    /**
+     * @param account_id
+     * @param conv_id
+     * @return members typing, in the order they started
+     */
+    pub fn typing_members(&self, account_id: &str, conv_id: &str) -> Vec<String> {
+        self.typing
+            .get(&(account_id.to_string(), conv_id.to_string()))
+            .map(|members| {
+                members
+                    .iter()
+                    .filter(|(_, last)| last.elapsed() < self.expiry)
+                    .map(|(member, _)| member.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,196 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use super::{Event, Jami};
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/**
+ * Unread messages of a conversation (cf UnreadTracker)
+ **/
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct UnreadConversation {
+    // Ids of the unread messages, oldest first
+    pub unread: Vec<String>,
+}
+
+impl UnreadConversation {
+    pub fn count(&self) -> usize {
+        self.unread.len()
+    }
+
+    pub fn first_unread(&self) -> Option<&String> {
+        self.unread.first()
+    }
+}
+
+/**
+ * Count unread messages per conversation of an account from the events.
+ * Own messages and messages received in the focused conversation are not counted.
+ * Serializable, to keep counts across restarts
+ **/
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct UnreadTracker {
+    pub account_id: String,
+    // Hash of the account, to ignore own messages
+    pub own_uri: String,
+    conversations: HashMap<String, UnreadConversation>,
+    #[serde(skip)]
+    focused: Option<String>,
+}
+
+impl UnreadTracker {
+    /**
+     * @param account_id    Account to follow
+     * @param own_uri       Hash of the account
+     */
+    pub fn new(account_id: &str, own_uri: &str) -> UnreadTracker {
+        UnreadTracker {
+            account_id: account_id.to_string(),
+            own_uri: own_uri.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /**
+     * Set the conversation shown to the user, its new messages are not counted
+     * @param conversation_id   None if no conversation is shown
+     */
+    pub fn set_focused(&mut self, conversation_id: Option<&str>) {
+        self.focused = conversation_id.map(|id| id.to_string());
+    }
+
+    /**
+     * Update counts from an event
+     * @param event     Event from the event loop
+     * @return the conversation whose count changed
+     */
+    pub fn apply<I>(&mut self, event: &Event<I>) -> Option<String> {
+        match event {
+            Event::Message {
+                account_id,
+                conversation_id,
+                payloads,
+            } if *account_id == self.account_id => {
+                if !UnreadTracker::is_counted(payloads)
+                    || payloads.get("author") == Some(&self.own_uri)
+                    || self.focused.as_ref() == Some(conversation_id)
+                {
+                    return None;
+                }
+                let id = payloads.get("id")?;
+                let conversation = self
+                    .conversations
+                    .entry(conversation_id.clone())
+                    .or_default();
+                // Messages can be received again after a reconnection
+                if conversation.unread.contains(id) {
+                    return None;
+                }
+                conversation.unread.push(id.clone());
+                Some(conversation_id.clone())
+            }
+            // Read on another device of the account
+            Event::MessageStatusChanged {
+                account_id,
+                conversation_id,
+                peer,
+                message_id,
+                status,
+            } if *account_id == self.account_id && *peer == self.own_uri && *status == 3 => {
+                let conversation = self.conversations.get_mut(conversation_id)?;
+                let index = conversation.unread.iter().position(|id| id == message_id)?;
+                conversation.unread.drain(..=index);
+                Some(conversation_id.clone())
+            }
+            Event::ConversationRemoved(account_id, conversation_id)
+                if *account_id == self.account_id =>
+            {
+                self.conversations.remove(conversation_id)?;
+                Some(conversation_id.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /**
+     * @param conversation_id
+     * @return number of unread messages
+     */
+    pub fn unread_count(&self, conversation_id: &str) -> usize {
+        self.conversations
+            .get(conversation_id)
+            .map(|c| c.count())
+            .unwrap_or(0)
+    }
+
+    /**
+     * @param conversation_id
+     * @return the oldest unread message, to scroll to it
+     */
+    pub fn first_unread(&self, conversation_id: &str) -> Option<String> {
+        self.conversations
+            .get(conversation_id)
+            .and_then(|c| c.first_unread().cloned())
+    }
+
+    /**
+     * @return total of unread messages
+     */
+    pub fn total(&self) -> usize {
+        self.conversations.values().map(|c| c.count()).sum()
+    }
+
+    /**
+     * Mark a conversation as read, and tell peers the last message was displayed
+     * @param conversation_id
+     */
+    pub fn mark_read(&mut self, conversation_id: &str) {
+        if let Some(conversation) = self.conversations.remove(conversation_id) {
+            if let Some(last) = conversation.unread.last() {
+                let _ = Jami::try_set_message_displayed(
+                    &self.account_id,
+                    &format!("swarm:{}", conversation_id),
+                    last,
+                    3,
+                );
+            }
+        }
+    }
+
+    /**
+     * @param payloads  Commit
+     * @return if the commit is a message for the user (not an edit, a reaction...)
+     */
+    fn is_counted(payloads: &HashMap<String, String>) -> bool {
+        match payloads.get("type").map(|t| &**t) {
+            Some("text/plain") => !payloads.contains_key("react-to"),
+            Some("application/data-transfer+json") => true,
+            _ => false,
+        }
+    }
+}
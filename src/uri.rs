@@ -0,0 +1,219 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+
+/**
+ * Errors when parsing a JamiUri
+ **/
+#[derive(Clone, Debug, PartialEq)]
+pub enum UriError {
+    Empty,
+    // Contains forbidden characters (spaces, separators)
+    Invalid(String),
+    // Not 40 hex chars
+    InvalidHash(String),
+}
+
+// Used for println!
+impl fmt::Display for UriError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UriError::Empty => write!(f, "Empty uri"),
+            UriError::Invalid(uri) => write!(f, "Invalid uri: {}", uri),
+            UriError::InvalidHash(hash) => write!(f, "Invalid hash: {}", hash),
+        }
+    }
+}
+
+impl Error for UriError {}
+
+/**
+ * @param string    String to check
+ * @param len       Expected length
+ * @return if string is len lowercase hex chars
+ */
+pub fn is_hex(string: &str, len: usize) -> bool {
+    string.len() == len
+        && string
+            .bytes()
+            .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/**
+ * @param string    String to check
+ * @return if string is an infohash (40 lowercase hex chars, no scheme)
+ */
+pub fn is_hash(string: &str) -> bool {
+    is_hex(string, 40)
+}
+
+/**
+ * Hash identifying an account or a device (40 hex chars, lowercase)
+ **/
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct InfoHash(String);
+
+impl InfoHash {
+    /**
+     * @param hash  Hash, uppercase is accepted
+     */
+    pub fn parse(hash: &str) -> Result<InfoHash, UriError> {
+        let lower = hash.to_ascii_lowercase();
+        if !is_hash(&lower) {
+            return Err(UriError::InvalidHash(hash.to_string()));
+        }
+        Ok(InfoHash(lower))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// Used for println!
+impl fmt::Display for InfoHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl AsRef<str> for InfoHash {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/**
+ * A peer or account, as a hash or a registered name.
+ * Accepts jami:, ring: or no scheme.
+ **/
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum JamiUri {
+    Hash(InfoHash),
+    // Registered name (needs a lookup)
+    Username(String),
+}
+
+/**
+ * Remove the jami: or ring: scheme if any
+ * @param uri   Uri to strip
+ * @return the uri without the scheme
+ */
+pub fn strip_scheme(uri: &str) -> &str {
+    let uri = uri.trim();
+    for scheme in &["jami:", "ring:"] {
+        if uri.len() >= scheme.len() && uri[..scheme.len()].eq_ignore_ascii_case(scheme) {
+            return &uri[scheme.len()..];
+        }
+    }
+    uri
+}
+
+/**
+ * Normalize a peer given by the user or the daemon: a hash loses its scheme and
+ * is lowercased, anything else is kept as is
+ * @param uri   Uri to normalize
+ * @return the bare hash or the uri
+ */
+pub fn normalize_peer(uri: &str) -> String {
+    match JamiUri::parse(uri) {
+        Ok(JamiUri::Hash(hash)) => hash.to_string(),
+        _ => uri.to_string(),
+    }
+}
+
+impl JamiUri {
+    /**
+     * Parse an uri
+     * @param uri   e.g. jami:<hash>, ring:<hash>, <hash> or a registered name
+     * @return the uri
+     */
+    pub fn parse(uri: &str) -> Result<JamiUri, UriError> {
+        let value = strip_scheme(uri);
+        if value.is_empty() {
+            return Err(UriError::Empty);
+        }
+        if let Ok(hash) = InfoHash::parse(value) {
+            return Ok(JamiUri::Hash(hash));
+        }
+        if value
+            .chars()
+            .any(|c| c.is_whitespace() || c == ':' || c == '/' || c == '@')
+        {
+            return Err(UriError::Invalid(uri.to_string()));
+        }
+        Ok(JamiUri::Username(value.to_string()))
+    }
+
+    /**
+     * @return the uri without scheme (bare hash or name)
+     */
+    pub fn canonical(&self) -> String {
+        match self {
+            JamiUri::Hash(hash) => hash.to_string(),
+            JamiUri::Username(name) => name.clone(),
+        }
+    }
+
+    /**
+     * @return the uri with the jami: scheme
+     */
+    pub fn with_scheme(&self) -> String {
+        format!("jami:{}", self.canonical())
+    }
+}
+
+// Used for println!
+impl fmt::Display for JamiUri {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.with_scheme())
+    }
+}
+
+/**
+ * Extract a peer from a share link or QR payload
+ * (https://jami.net/?uri=<id>, jami:<id>, ring:<id> or <id>)
+ * @param link  Link to parse
+ * @return the bare hash or registered name
+ */
+pub fn parse_share_uri(link: &str) -> Option<String> {
+    let link = link.trim();
+    let lower = link.to_ascii_lowercase();
+    let value = if lower.starts_with("http://") || lower.starts_with("https://") {
+        let query = &link[(link.find('?')? + 1)..];
+        let query = query.split('#').next().unwrap_or_default();
+        query
+            .split('&')
+            .find_map(|param| param.strip_prefix("uri="))?
+    } else {
+        link.split(['?', '#']).next().unwrap_or_default()
+    };
+    JamiUri::parse(value).ok().map(|uri| uri.canonical())
+}
@@ -0,0 +1,96 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use super::error::JamiError;
+use super::uri::{self, JamiUri};
+
+fn invalid(param: &str, reason: &str) -> JamiError {
+    JamiError::InvalidArgument(param.to_string(), reason.to_string())
+}
+
+/**
+ * Check an account id (non-empty, alphanumeric)
+ * @param param     Name of the parameter, for the error
+ * @param id        Id to check
+ */
+pub fn account_id(param: &str, id: &str) -> Result<(), JamiError> {
+    if id.is_empty() {
+        return Err(invalid(param, "empty account id"));
+    }
+    if !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(invalid(param, "account ids are alphanumeric"));
+    }
+    Ok(())
+}
+
+/**
+ * Check a conversation id (40 lowercase hex chars)
+ * @param param     Name of the parameter, for the error
+ * @param id        Id to check
+ */
+pub fn conversation_id(param: &str, id: &str) -> Result<(), JamiError> {
+    if !uri::is_hex(id, 40) {
+        return Err(invalid(param, "conversation ids are 40 hex chars"));
+    }
+    Ok(())
+}
+
+/**
+ * Check the hash of a peer, with or without scheme (jami:, ring:)
+ * @param param     Name of the parameter, for the error
+ * @param peer      Uri to check
+ */
+pub fn peer(param: &str, peer: &str) -> Result<(), JamiError> {
+    match JamiUri::parse(peer) {
+        Ok(JamiUri::Hash(_)) => Ok(()),
+        Ok(_) => Err(invalid(param, "a hash is expected, not a name")),
+        Err(e) => Err(invalid(param, &e.to_string())),
+    }
+}
+
+/**
+ * Check a file path (non-empty)
+ * @param param     Name of the parameter, for the error
+ * @param path      Path to check
+ */
+pub fn path(param: &str, path: &str) -> Result<(), JamiError> {
+    if path.is_empty() {
+        return Err(invalid(param, "empty path"));
+    }
+    Ok(())
+}
+
+/**
+ * Check a position in a file (non-negative)
+ * @param param     Name of the parameter, for the error
+ * @param offset    Offset to check
+ */
+pub fn offset(param: &str, offset: i64) -> Result<(), JamiError> {
+    if offset < 0 {
+        return Err(invalid(param, "negative offset"));
+    }
+    Ok(())
+}
@@ -0,0 +1,158 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use jami_rs::account::{self, Account, NetworkStatus, PushConfig};
+use jami_rs::profilemanager::ProfileManager;
+use jami_rs::Profile;
+use std::collections::HashMap;
+
+const HASH: &str = "a1b2c3d4e5f60718293a4b5c6d7e8f9012345678";
+
+fn account(alias: &str, registered_name: &str) -> Account {
+    let mut account = Account::new("acc1");
+    account.hash = HASH.to_string();
+    account.alias = alias.to_string();
+    account.registered_name = registered_name.to_string();
+    account
+}
+
+// The profile of the same identity, as seen by a contact
+fn profiles(alias: &str, registered_name: &str) -> ProfileManager {
+    let mut profile = Profile::new();
+    profile.uri = HASH.to_string();
+    profile.display_name = alias.to_string();
+    profile.username = registered_name.to_string();
+    let mut profiles = ProfileManager::new();
+    profiles.profiles.insert(HASH.to_string(), profile);
+    profiles
+}
+
+#[test]
+fn only_a_hash() {
+    let account = account("", "");
+    assert_eq!(account.display_identity(), "a1b2c3d4");
+    assert_eq!(account.get_display_name(), HASH);
+    assert_eq!(account.short_hash(4), "a1b2");
+    assert_eq!(account.to_string(), "a1b2c3d4…");
+    let profiles = profiles("", "");
+    assert_eq!(profiles.display_name(&HASH.to_string()), HASH);
+    assert_eq!(profiles.display_identity(HASH), "a1b2c3d4");
+    // Unknown uri
+    assert_eq!(ProfileManager::new().display_name(&HASH.to_string()), HASH);
+}
+
+#[test]
+fn only_an_alias() {
+    let account = account("Alice", "");
+    assert_eq!(account.display_identity(), "Alice");
+    assert_eq!(account.get_display_name(), "Alice");
+    assert_eq!(account.to_string(), "Alice (a1b2c3d4…)");
+    let profiles = profiles("Alice", "");
+    assert_eq!(profiles.display_name(&HASH.to_string()), "Alice");
+    assert_eq!(profiles.display_identity(HASH), "Alice");
+}
+
+#[test]
+fn all_fields_set() {
+    let account = account("Alice", "alice");
+    assert_eq!(account.display_identity(), "alice");
+    assert_eq!(account.to_string(), "alice (a1b2c3d4…)");
+    // The display names keep preferring the name chosen by the user
+    assert_eq!(account.get_display_name(), "Alice");
+    let profiles = profiles("Alice", "alice");
+    assert_eq!(profiles.display_identity(HASH), "alice");
+    assert_eq!(profiles.display_name(&HASH.to_string()), "Alice");
+    assert_eq!(profiles.profiles[HASH].bestname(), "Alice");
+}
+
+#[test]
+fn accounts_and_profiles_agree() {
+    let identities = [("", ""), ("Alice", ""), ("", "alice"), ("Alice", "alice")];
+    for (alias, registered_name) in identities.iter() {
+        let account = account(alias, registered_name);
+        let profiles = profiles(alias, registered_name);
+        let profile = &profiles.profiles[HASH];
+        assert_eq!(account.get_display_name(), profile.bestname());
+        assert_eq!(
+            account.get_display_name(),
+            profiles.display_name(&HASH.to_string())
+        );
+        assert_eq!(account.display_identity(), profiles.display_identity(HASH));
+        assert_eq!(
+            account.display_identity(),
+            account::display_identity(registered_name, alias, HASH)
+        );
+    }
+}
+
+#[test]
+fn network_status_from_details() {
+    let details = |kind: &str| -> HashMap<String, String> {
+        vec![(String::from("Account.type"), kind.to_string())]
+            .into_iter()
+            .collect()
+    };
+    let announced = String::from("Account.deviceAnnounced");
+    let mut volatile = HashMap::new();
+    // Not reported by the daemon
+    let status = NetworkStatus::from_details(&details("RING"), &volatile);
+    assert_eq!(status, NetworkStatus::default());
+
+    volatile.insert(announced.clone(), String::from("false"));
+    let status = NetworkStatus::from_details(&details("RING"), &volatile);
+    assert_eq!(status.dht_connected, Some(false));
+    volatile.insert(announced.clone(), String::from("true"));
+    let status = NetworkStatus::from_details(&details("RING"), &volatile);
+    assert_eq!(status.dht_connected, Some(true));
+    assert_eq!(status.nameserver_reachable, None);
+
+    // No DHT
+    let status = NetworkStatus::from_details(&details("SIP"), &volatile);
+    assert_eq!(status, NetworkStatus::default());
+}
+
+#[test]
+fn push_config_proxy_servers() {
+    let config = |server: &str| PushConfig {
+        proxy_enabled: true,
+        proxy_server: server.to_string(),
+        ..PushConfig::default()
+    };
+    // Default of the daemon
+    assert!(config("dhtproxy.jami.net:[80-95]").validate().is_ok());
+    assert!(config("https://proxy.example.com:8080").validate().is_ok());
+    assert!(config("[::1]:8080").validate().is_ok());
+    assert!(config("http://[fe80::1]").validate().is_ok());
+
+    assert!(config("").validate().is_err());
+    assert!(config(":80").validate().is_err());
+    assert!(config("proxy.example.com:70000").validate().is_err());
+    assert!(config("proxy.example.com:[95-80]").validate().is_err());
+    assert!(config("proxy.example.com:[80-]").validate().is_err());
+    assert!(config("[::1").validate().is_err());
+    assert!(config("[::1]8080").validate().is_err());
+    assert!(config("::1:8080").validate().is_err());
+}
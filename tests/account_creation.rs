@@ -0,0 +1,212 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use jami_rs::accountbuilder::CreationProgressTracker;
+use jami_rs::{AccountCreationProgress, Event};
+use std::collections::HashMap;
+
+const ACCOUNT: &str = "9f9b2e1c4a3d7e10";
+
+// registrationStateChanged
+fn state(account_id: &str, state: &str, code: i32, detail: &str) -> Event<()> {
+    Event::RegistrationStateChanged {
+        account_id: account_id.to_string(),
+        state: state.parse().unwrap(),
+        raw_state: state.to_string(),
+        code,
+        detail: detail.to_string(),
+    }
+}
+
+// volatileAccountDetailsChanged
+fn volatile(account_id: &str, details: &[(&str, &str)]) -> Event<()> {
+    let details: HashMap<String, String> = details
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    Event::VolatileDetailsChanged(account_id.to_string(), details)
+}
+
+// Stages reported for a sequence of signals
+fn stages(events: &[Event<()>]) -> Vec<AccountCreationProgress> {
+    let mut tracker = CreationProgressTracker::new(ACCOUNT);
+    events
+        .iter()
+        .filter_map(|event| tracker.on_event(event))
+        .collect()
+}
+
+#[test]
+fn new_ring_account() {
+    // Signals of jamid for addAccount with Account.type RING, in order
+    let events = vec![
+        state(ACCOUNT, "INITIALIZING", 0, ""),
+        Event::AccountsChanged(),
+        volatile(
+            ACCOUNT,
+            &[
+                ("Account.registrationStatus", "INITIALIZING"),
+                ("Account.deviceAnnounced", "false"),
+            ],
+        ),
+        // Keys generated
+        state(ACCOUNT, "UNREGISTERED", 0, ""),
+        Event::AccountDetailsChanged(ACCOUNT.to_string(), HashMap::new()),
+        state(ACCOUNT, "TRYING", 0, ""),
+        state(ACCOUNT, "REGISTERED", 0, ""),
+        volatile(
+            ACCOUNT,
+            &[
+                ("Account.registrationStatus", "REGISTERED"),
+                ("Account.deviceAnnounced", "true"),
+            ],
+        ),
+        // After the last stage
+        state(ACCOUNT, "TRYING", 0, ""),
+    ];
+    assert_eq!(
+        stages(&events),
+        vec![
+            AccountCreationProgress::GeneratingKeys,
+            AccountCreationProgress::Announcing,
+            AccountCreationProgress::Registered,
+        ]
+    );
+}
+
+#[test]
+fn announced_before_registered() {
+    let events = vec![
+        state(ACCOUNT, "INITIALIZING", 0, ""),
+        state(ACCOUNT, "TRYING", 0, ""),
+        volatile(ACCOUNT, &[("Account.deviceAnnounced", "true")]),
+        state(ACCOUNT, "REGISTERED", 0, ""),
+    ];
+    assert_eq!(
+        stages(&events),
+        vec![
+            AccountCreationProgress::GeneratingKeys,
+            AccountCreationProgress::Announcing,
+            AccountCreationProgress::Registered,
+        ]
+    );
+}
+
+#[test]
+fn registered_from_volatile_details() {
+    // Some daemons only update the volatile details once registered
+    let events = vec![
+        state(ACCOUNT, "INITIALIZING", 0, ""),
+        state(ACCOUNT, "TRYING", 0, ""),
+        volatile(
+            ACCOUNT,
+            &[
+                ("Account.registrationStatus", "REGISTERED"),
+                ("Account.deviceAnnounced", "true"),
+            ],
+        ),
+    ];
+    assert_eq!(
+        stages(&events),
+        vec![
+            AccountCreationProgress::GeneratingKeys,
+            AccountCreationProgress::Announcing,
+            AccountCreationProgress::Registered,
+        ]
+    );
+}
+
+#[test]
+fn volatile_details_wait_for_the_keys() {
+    // Nothing is known before the first registration state
+    let events = vec![
+        volatile(ACCOUNT, &[("Account.deviceAnnounced", "false")]),
+        volatile(ACCOUNT, &[("Account.registrationStatus", "INITIALIZING")]),
+    ];
+    assert!(stages(&events).is_empty());
+    let mut tracker = CreationProgressTracker::new(ACCOUNT);
+    for event in &events {
+        tracker.on_event(event);
+    }
+    assert_eq!(tracker.stage(), &AccountCreationProgress::Initializing);
+    assert!(!tracker.is_finished());
+}
+
+#[test]
+fn failed_creation() {
+    let events = vec![
+        state(ACCOUNT, "INITIALIZING", 0, ""),
+        state(ACCOUNT, "ERROR_GENERIC", 500, "key generation failed"),
+        // Nothing after Failed
+        state(ACCOUNT, "REGISTERED", 0, ""),
+    ];
+    assert_eq!(
+        stages(&events),
+        vec![
+            AccountCreationProgress::GeneratingKeys,
+            AccountCreationProgress::Failed(String::from(
+                "ERROR_GENERIC (500 key generation failed)"
+            )),
+        ]
+    );
+}
+
+#[test]
+fn network_error_while_announcing() {
+    let events = vec![
+        state(ACCOUNT, "INITIALIZING", 0, ""),
+        state(ACCOUNT, "UNREGISTERED", 0, ""),
+        state(ACCOUNT, "ERROR_NETWORK", 0, ""),
+    ];
+    let stages = stages(&events);
+    assert_eq!(
+        stages.last(),
+        Some(&AccountCreationProgress::Failed(String::from(
+            "ERROR_NETWORK (0 )"
+        )))
+    );
+}
+
+#[test]
+fn other_accounts_are_ignored() {
+    let other = "0123456789abcdef";
+    let events = vec![
+        state(other, "INITIALIZING", 0, ""),
+        state(ACCOUNT, "INITIALIZING", 0, ""),
+        state(other, "ERROR_GENERIC", 0, ""),
+        volatile(other, &[("Account.deviceAnnounced", "true")]),
+        state(ACCOUNT, "REGISTERED", 0, ""),
+        state(other, "REGISTERED", 0, ""),
+    ];
+    // Not announced yet
+    assert_eq!(
+        stages(&events),
+        vec![
+            AccountCreationProgress::GeneratingKeys,
+            AccountCreationProgress::Announcing,
+        ]
+    );
+}
@@ -0,0 +1,347 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use jami_rs::{
+    ConversationMessage, ConversationMode, ConversationState, Event, GeoLocation, MessageStatus,
+};
+use std::collections::HashMap;
+use std::time::Duration;
+
+const ACCOUNT: &str = "acc1";
+const CONVERSATION: &str = "conv1";
+
+fn commit(fields: &[(&str, &str)]) -> HashMap<String, String> {
+    fields
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+fn text(id: &str, timestamp: &str, body: &str) -> HashMap<String, String> {
+    commit(&[
+        ("id", id),
+        ("type", "text/plain"),
+        ("author", "alice"),
+        ("timestamp", timestamp),
+        ("body", body),
+    ])
+}
+
+fn edit(id: &str, target: &str, timestamp: &str, body: &str) -> HashMap<String, String> {
+    commit(&[
+        ("id", id),
+        ("type", "application/edited-message"),
+        ("edit", target),
+        ("timestamp", timestamp),
+        ("body", body),
+    ])
+}
+
+fn reaction(id: &str, target: &str, body: &str) -> HashMap<String, String> {
+    commit(&[
+        ("id", id),
+        ("type", "text/plain"),
+        ("author", "bob"),
+        ("react-to", target),
+        ("body", body),
+    ])
+}
+
+fn message(payloads: HashMap<String, String>) -> Event<()> {
+    Event::Message {
+        account_id: ACCOUNT.to_string(),
+        conversation_id: CONVERSATION.to_string(),
+        payloads,
+    }
+}
+
+// A page of load_conversation, newest first like the daemon sends it
+fn loaded(page: Vec<HashMap<String, String>>) -> Event<()> {
+    Event::ConversationLoaded(1, ACCOUNT.to_string(), CONVERSATION.to_string(), page)
+}
+
+fn member(uri: &str, event: i32) -> Event<()> {
+    Event::ConversationMemberEvent {
+        account_id: ACCOUNT.to_string(),
+        conversation_id: CONVERSATION.to_string(),
+        member_uri: uri.to_string(),
+        event,
+    }
+}
+
+fn status(message_id: &str, status: i32) -> Event<()> {
+    Event::MessageStatusChanged {
+        account_id: ACCOUNT.to_string(),
+        conversation_id: CONVERSATION.to_string(),
+        peer: String::from("bob"),
+        message_id: message_id.to_string(),
+        status,
+    }
+}
+
+fn ids(state: &ConversationState) -> Vec<&str> {
+    state.messages().iter().map(|m| &*m.id).collect()
+}
+
+fn body<'a>(state: &'a ConversationState, id: &str) -> &'a str {
+    &state.messages().iter().find(|m| m.id == id).unwrap().body
+}
+
+#[test]
+fn messages_are_ordered_by_timestamp() {
+    let mut state = ConversationState::new(ACCOUNT, CONVERSATION);
+    assert!(state.apply(&message(text("m3", "30", "third"))));
+    // An older page, loaded after the live message
+    assert!(state.apply(&loaded(vec![
+        text("m2", "20", "second"),
+        text("m1", "10", "first"),
+    ])));
+    assert!(state.apply(&message(text("m4", "30", "fourth"))));
+    // Equal timestamps keep the order of arrival
+    assert_eq!(ids(&state), vec!["m1", "m2", "m3", "m4"]);
+}
+
+#[test]
+fn duplicate_commits_are_applied_once() {
+    let mut state = ConversationState::new(ACCOUNT, CONVERSATION);
+    assert!(state.apply(&message(text("m1", "10", "hello"))));
+    assert!(!state.apply(&message(text("m1", "10", "hello"))));
+    // The same commit in a loaded page
+    assert!(!state.apply(&loaded(vec![text("m1", "10", "hello")])));
+    assert!(state.apply(&message(reaction("r1", "m1", "+1"))));
+    assert!(!state.apply(&message(reaction("r1", "m1", "+1"))));
+    assert_eq!(ids(&state), vec!["m1"]);
+    assert_eq!(state.messages()[0].reactions.len(), 1);
+}
+
+#[test]
+fn commits_without_id_are_ignored() {
+    let mut state = ConversationState::new(ACCOUNT, CONVERSATION);
+    assert!(!state.apply(&message(commit(&[("type", "text/plain"), ("body", "hi")]))));
+    assert!(state.messages().is_empty());
+}
+
+#[test]
+fn edits_wait_for_their_target() {
+    let mut state = ConversationState::new(ACCOUNT, CONVERSATION);
+    // The edit is live, the message is in an older page
+    assert!(!state.apply(&message(edit("e1", "m1", "20", "fixed"))));
+    assert!(state.messages().is_empty());
+    assert!(state.apply(&loaded(vec![text("m1", "10", "fxed")])));
+    assert_eq!(body(&state, "m1"), "fixed");
+    assert!(state.messages()[0].edited);
+    // Edits are not listed as messages
+    assert_eq!(ids(&state), vec!["m1"]);
+}
+
+#[test]
+fn older_edits_do_not_override_newer_ones() {
+    let mut state = ConversationState::new(ACCOUNT, CONVERSATION);
+    state.apply(&message(text("m1", "10", "one")));
+    assert!(state.apply(&message(edit("e2", "m1", "30", "three"))));
+    // Loaded after, from an older page
+    assert!(!state.apply(&loaded(vec![edit("e1", "m1", "20", "two")])));
+    assert_eq!(body(&state, "m1"), "three");
+}
+
+#[test]
+fn deletions_are_final() {
+    let mut state = ConversationState::new(ACCOUNT, CONVERSATION);
+    state.apply(&message(text("m1", "10", "oops")));
+    state.apply(&message(reaction("r1", "m1", "+1")));
+    assert!(state.apply(&message(edit("e1", "m1", "20", ""))));
+    let deleted = &state.messages()[0];
+    assert!(deleted.deleted);
+    assert!(deleted.body.is_empty());
+    assert!(deleted.reactions.is_empty());
+    // An edit arriving after the deletion doesn't bring the message back
+    assert!(!state.apply(&message(edit("e2", "m1", "30", "back"))));
+    assert!(state.messages()[0].deleted);
+    assert!(state.messages()[0].body.is_empty());
+}
+
+#[test]
+fn reactions_are_applied_and_removed() {
+    let mut state = ConversationState::new(ACCOUNT, CONVERSATION);
+    // Removed before being applied: everything arrives before the message
+    state.apply(&message(edit("e1", "r1", "30", "")));
+    state.apply(&message(reaction("r1", "m1", "+1")));
+    state.apply(&message(reaction("r2", "m1", "<3")));
+    assert!(state.messages().is_empty());
+    state.apply(&loaded(vec![text("m1", "10", "hello")]));
+    let reactions = &state.messages()[0].reactions;
+    assert_eq!(reactions.len(), 1);
+    assert_eq!(reactions[0].id, "r2");
+    assert_eq!(reactions[0].author, "bob");
+
+    // Removed once applied
+    assert!(state.apply(&message(edit("e2", "r2", "40", ""))));
+    assert!(state.messages()[0].reactions.is_empty());
+}
+
+#[test]
+fn statuses_wait_for_their_message() {
+    let mut state = ConversationState::new(ACCOUNT, CONVERSATION);
+    assert!(!state.apply(&status("m1", 2)));
+    state.apply(&message(text("m1", "10", "hello")));
+    assert_eq!(state.messages()[0].status.get("bob"), Some(&2));
+    assert!(state.apply(&status("m1", 3)));
+    // Same status again
+    assert!(!state.apply(&status("m1", 3)));
+    assert_eq!(state.messages()[0].status.get("bob"), Some(&3));
+}
+
+#[test]
+fn members_follow_their_last_event() {
+    let mut state = ConversationState::new(ACCOUNT, CONVERSATION);
+    assert!(state.apply(&member("bob", 0)));
+    assert!(state.apply(&member("carol", 0)));
+    assert!(state.apply(&member("bob", 1)));
+    assert!(!state.apply(&member("bob", 1)));
+    assert!(state.apply(&member("dave", 1)));
+    assert!(state.apply(&member("carol", 2)));
+    assert!(state.apply(&member("dave", 3)));
+    assert_eq!(state.members(), vec!["bob"]);
+}
+
+#[test]
+fn removal_resets_the_state() {
+    let mut state = ConversationState::new(ACCOUNT, CONVERSATION);
+    state.apply(&message(text("m1", "10", "hello")));
+    state.apply(&member("bob", 1));
+    assert!(state.apply(&Event::<()>::ConversationRemoved(
+        ACCOUNT.to_string(),
+        CONVERSATION.to_string()
+    )));
+    assert!(state.messages().is_empty());
+    assert!(state.members().is_empty());
+    assert_eq!(state.conversation_id, CONVERSATION);
+    // Commits seen before the removal can be applied again (conversation cloned back)
+    assert!(state.apply(&message(text("m1", "10", "hello"))));
+}
+
+#[test]
+fn other_conversations_are_ignored() {
+    let mut state = ConversationState::new(ACCOUNT, CONVERSATION);
+    let other = Event::<()>::Message {
+        account_id: ACCOUNT.to_string(),
+        conversation_id: String::from("conv2"),
+        payloads: text("m1", "10", "hello"),
+    };
+    assert!(!state.apply(&other));
+    let other_account =
+        Event::<()>::ConversationRemoved(String::from("acc2"), CONVERSATION.to_string());
+    assert!(!state.apply(&other_account));
+    assert!(!state.apply(&Event::<()>::AccountsChanged()));
+    assert!(state.messages().is_empty());
+}
+
+#[test]
+fn message_statuses() {
+    assert!(MessageStatus::from(1).is_queued());
+    assert!(MessageStatus::from(2).is_sent());
+    assert!(MessageStatus::from(3).is_sent());
+    assert!(MessageStatus::from(4).is_failed());
+    assert_eq!(MessageStatus::from(5), MessageStatus::Cancelled);
+    assert_eq!(MessageStatus::from(42), MessageStatus::Unknown);
+    assert!(!MessageStatus::Sending.is_sent());
+}
+
+#[test]
+fn conversation_modes() {
+    for mode in &[
+        ConversationMode::OneToOne,
+        ConversationMode::AdminInvitesOnly,
+        ConversationMode::InvitesOnly,
+        ConversationMode::Public,
+    ] {
+        assert_eq!(ConversationMode::from(mode.value()), *mode);
+    }
+    assert_eq!(
+        ConversationMode::from_infos(&commit(&[("mode", "3")])),
+        Some(ConversationMode::Public)
+    );
+    assert_eq!(
+        ConversationMode::from_infos(&commit(&[("mode", "x")])),
+        None
+    );
+    assert_eq!(ConversationMode::from_infos(&HashMap::new()), None);
+}
+
+#[test]
+fn geo_locations() {
+    let live = GeoLocation::from_json(
+        r#"{"type":"Position","lat":1.5,"long":-2.25,"time":123,"duration":60}"#,
+    )
+    .unwrap();
+    assert_eq!(live.lat, 1.5);
+    assert_eq!(live.lon, -2.25);
+    assert_eq!(live.time, 123);
+    assert_eq!(live.duration, Some(Duration::from_secs(60)));
+    assert!(live.is_live());
+    assert_eq!(GeoLocation::from_json(&live.to_json()), Some(live));
+
+    let stop = GeoLocation::from_json(r#"{"type":"Stop","time":124}"#).unwrap();
+    assert!(stop.stop);
+    assert!(!stop.is_live());
+    assert_eq!(GeoLocation::from_json(&stop.to_json()), Some(stop));
+
+    assert_eq!(
+        GeoLocation::from_json(r#"{"type":"Position","lat":1.5}"#),
+        None
+    );
+    assert_eq!(GeoLocation::from_json(r#"{"type":"Other"}"#), None);
+    assert_eq!(GeoLocation::from_json("not json"), None);
+}
+
+#[test]
+fn messages_from_payloads() {
+    let mut payloads = text("m1", "10", "hello");
+    payloads.insert(String::from("reply-to"), String::from("m0"));
+    let message = ConversationMessage::from_payloads(&payloads);
+    assert_eq!(message.id, "m1");
+    assert_eq!(message.author, "alice");
+    assert_eq!(message.kind, "text/plain");
+    assert_eq!(message.body, "hello");
+    assert_eq!(message.timestamp, 10);
+    assert_eq!(message.reply_to.as_deref(), Some("m0"));
+    assert_eq!(message.payloads, payloads);
+
+    let geo = commit(&[
+        ("id", "m2"),
+        ("type", "application/geo"),
+        ("timestamp", "bad"),
+        ("reply-to", ""),
+        (
+            "application/geo",
+            r#"{"type":"Position","lat":1.0,"long":2.0}"#,
+        ),
+    ]);
+    let message = ConversationMessage::from_payloads(&geo);
+    assert_eq!(message.timestamp, 0);
+    assert_eq!(message.reply_to, None);
+    assert_eq!(message.geo.map(|geo| geo.lon), Some(2.0));
+}
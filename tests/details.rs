@@ -0,0 +1,176 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use jami_rs::account::AccountType;
+use jami_rs::{details_keys, AccountDetails, DetailKind};
+use std::collections::HashMap;
+
+// getAccountDetails of a RING account, captured from jamid
+fn captured_details() -> HashMap<String, String> {
+    serde_json::from_str(include_str!("data/account_details.json")).unwrap()
+}
+
+// Read each typed detail and write it back
+macro_rules! round_trip {
+    ($details:ident, by_value: [$($get:ident => $set:ident),* $(,)?]) => {
+        $(
+            let value = $details.$get().expect(stringify!($get));
+            $details.$set(value);
+        )*
+    };
+    ($details:ident, by_ref: [$($get:ident => $set:ident),* $(,)?]) => {
+        $(
+            let value = $details.$get().expect(stringify!($get)).to_string();
+            $details.$set(&value);
+        )*
+    };
+}
+
+#[test]
+fn captured_details_round_trip() {
+    let map = captured_details();
+    let mut details = AccountDetails::from_map(map.clone());
+
+    assert_eq!(details.account_type(), Some(AccountType::Ring));
+    let account_type = details.account_type().unwrap();
+    details.set_account_type(account_type);
+    round_trip!(details, by_value: [
+        enabled => set_enabled,
+        has_custom_user_agent => set_has_custom_user_agent,
+        archive_has_password => set_archive_has_password,
+        auto_answer => set_auto_answer,
+        send_read_receipt => set_send_read_receipt,
+        send_composing => set_send_composing,
+        is_rendezvous => set_is_rendezvous,
+        ringtone_enabled => set_ringtone_enabled,
+        video_enabled => set_video_enabled,
+        upnp_enabled => set_upnp_enabled,
+        peer_discovery => set_peer_discovery,
+        account_discovery => set_account_discovery,
+        account_publish => set_account_publish,
+        proxy_enabled => set_proxy_enabled,
+        local_moderators_enabled => set_local_moderators_enabled,
+        all_moderators_enabled => set_all_moderators_enabled,
+        allow_cert_from_history => set_allow_cert_from_history,
+        allow_cert_from_contact => set_allow_cert_from_contact,
+        allow_cert_from_trusted => set_allow_cert_from_trusted,
+        presence_enabled => set_presence_enabled,
+        presence_publish_supported => set_presence_publish_supported,
+        presence_subscribe_supported => set_presence_subscribe_supported,
+        published_same_as_local => set_published_same_as_local,
+        stun_enabled => set_stun_enabled,
+        turn_enabled => set_turn_enabled,
+        srtp_enabled => set_srtp_enabled,
+        srtp_rtp_fallback => set_srtp_rtp_fallback,
+        tls_enabled => set_tls_enabled,
+        tls_verify_server => set_tls_verify_server,
+        tls_verify_client => set_tls_verify_client,
+        tls_require_client_certificate => set_tls_require_client_certificate,
+    ]);
+    round_trip!(details, by_value: [
+        registration_expire => set_registration_expire,
+        active_call_limit => set_active_call_limit,
+        dht_port => set_dht_port,
+        local_port => set_local_port,
+        published_port => set_published_port,
+        tls_listener_port => set_tls_listener_port,
+        tls_negotiation_timeout => set_tls_negotiation_timeout,
+    ]);
+    round_trip!(details, by_ref: [
+        alias => set_alias,
+        display_name => set_display_name,
+        username => set_username,
+        hostname => set_hostname,
+        mailbox => set_mailbox,
+        user_agent => set_user_agent,
+        routeset => set_routeset,
+        device_id => set_device_id,
+        device_name => set_device_name,
+        registered_name => set_registered_name,
+        manager_uri => set_manager_uri,
+        manager_username => set_manager_username,
+        name_server => set_name_server,
+        ringtone_path => set_ringtone_path,
+        proxy_server => set_proxy_server,
+        proxy_list_url => set_proxy_list_url,
+        proxy_push_token => set_proxy_push_token,
+        push_topic => set_push_topic,
+        default_moderators => set_default_moderators,
+        ui_customization => set_ui_customization,
+        presence_note => set_presence_note,
+        local_interface => set_local_interface,
+        published_address => set_published_address,
+        dtmf_type => set_dtmf_type,
+        stun_server => set_stun_server,
+        turn_server => set_turn_server,
+        turn_username => set_turn_username,
+        turn_password => set_turn_password,
+        turn_realm => set_turn_realm,
+        srtp_key_exchange => set_srtp_key_exchange,
+        tls_ca_list_file => set_tls_ca_list_file,
+        tls_certificate_file => set_tls_certificate_file,
+        tls_private_key_file => set_tls_private_key_file,
+        tls_password => set_tls_password,
+        tls_method => set_tls_method,
+        tls_ciphers => set_tls_ciphers,
+        tls_server_name => set_tls_server_name,
+    ]);
+    // Not sent by getAccountDetails
+    assert!(details.password().is_none());
+    assert!(details.archive_path().is_none());
+
+    // Unknown keys (e.g. Account.audioPortMin) are kept
+    assert_eq!(details.into_map(), map);
+}
+
+#[test]
+fn captured_details_have_typed_values() {
+    let map = captured_details();
+    let details = AccountDetails::from_map(map.clone());
+    for (key, value) in &map {
+        match DetailKind::of(key, value) {
+            DetailKind::Bool => assert!(details.get_bool(key).is_some(), "{}", key),
+            DetailKind::Int => assert!(details.get_parsed::<i64>(key).is_some(), "{}", key),
+            DetailKind::Enum(values) => assert!(values.contains(value), "{}", key),
+            DetailKind::String => {}
+        }
+    }
+    assert_eq!(details.tls_negotiation_timeout(), Some(-1));
+    assert_eq!(details.dht_port(), Some(4222));
+    assert_eq!(details.get(details_keys::REGISTERED_NAME), Some("alice"));
+}
+
+#[test]
+fn typed_setters_write_daemon_values() {
+    let mut details = AccountDetails::from_map(captured_details());
+    details.set_upnp_enabled(false);
+    details.set_dht_port(4000);
+    details.set_account_type(AccountType::Sip);
+    let map = details.into_map();
+    assert_eq!(map[details_keys::UPNP_ENABLED], "false");
+    assert_eq!(map[details_keys::DHT_PORT], "4000");
+    assert_eq!(map[details_keys::ACCOUNT_TYPE], "SIP");
+}
@@ -0,0 +1,517 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+// Needs --features test-util and dbus-daemon in the PATH
+use dbus::blocking::Connection;
+use dbus::channel::Channel;
+use dbus_crossroads::Crossroads;
+use jami_rs::account::{Account, AccountType};
+use jami_rs::{
+    AsyncJamiClient, BusType, DaemonFlavor, Event, EventLoopOptions, Jami, JamiConfig, JamiError,
+    ReconnectPolicy, StopHandle, TransferError,
+};
+use std::error::Error;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc;
+
+// Held by tests changing the bus, it is global
+static BUS_LOCK: Mutex<()> = Mutex::new(());
+
+// A bus where cx.ring.Ring is not running
+struct EmptyBus {
+    daemon: Child,
+    address: String,
+}
+
+impl EmptyBus {
+    fn start() -> EmptyBus {
+        EmptyBus::start_at(None)
+    }
+
+    // Listen on socket, to restart a bus at the same address
+    fn start_at(socket: Option<&Path>) -> EmptyBus {
+        let mut args = vec![
+            String::from("--session"),
+            String::from("--nofork"),
+            String::from("--print-address"),
+        ];
+        if let Some(socket) = socket {
+            let _ = std::fs::remove_file(socket);
+            args.push(format!("--address=unix:path={}", socket.display()));
+        }
+        let mut daemon = Command::new("dbus-daemon")
+            .args(&args)
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("dbus-daemon is needed");
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.as_mut().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+        EmptyBus {
+            daemon,
+            address: address.trim().to_string(),
+        }
+    }
+}
+
+impl Drop for EmptyBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+// Go back to the session bus and cx.ring.Ring, even if the test fails
+struct ResetBus;
+
+impl Drop for ResetBus {
+    fn drop(&mut self) {
+        Jami::set_bus(BusType::Session);
+        Jami::detect_daemon();
+    }
+}
+
+fn connect(address: &str) -> Connection {
+    let mut channel = Channel::open_private(address).unwrap();
+    channel.register().unwrap();
+    Connection::from(channel)
+}
+
+fn next_event(rt: &mut Runtime, rx: &mut mpsc::Receiver<Event<()>>) -> Option<Event<()>> {
+    rt.block_on(async { tokio::time::timeout(Duration::from_secs(5), rx.recv()).await })
+        .ok()
+        .flatten()
+}
+
+fn dbus_error() -> dbus::Error {
+    dbus::Error::new_custom("org.freedesktop.DBus.Error.Failed", "failed")
+}
+
+#[test]
+fn error_variants() {
+    let variants = vec![
+        (JamiError::BusConnection(dbus_error()), true),
+        (JamiError::DaemonUnavailable, false),
+        (JamiError::method_call("getAccountList", dbus_error()), true),
+        (JamiError::ConnectionLost(String::from("closed")), false),
+        (JamiError::Timeout, false),
+        (JamiError::InvalidReply(String::from("empty")), false),
+        (JamiError::OperationFailed(String::from("refused")), false),
+        (
+            JamiError::InvalidArgument(String::from("id"), String::from("empty")),
+            false,
+        ),
+        (JamiError::NotFound(String::from("conversation")), false),
+        (
+            JamiError::WrongAccountType {
+                expected: AccountType::Sip,
+                actual: AccountType::Ring,
+            },
+            false,
+        ),
+    ];
+    for (error, has_source) in variants {
+        assert!(!error.to_string().is_empty());
+        assert_eq!(error.source().is_some(), has_source, "{:?}", error);
+    }
+    let transfer_errors = vec![
+        (TransferError::from_code(1).unwrap(), false),
+        (TransferError::from_code(2).unwrap(), false),
+        (TransferError::from_code(3).unwrap(), false),
+        (TransferError::from_code(9).unwrap(), false),
+        (TransferError::Failed(JamiError::Timeout), true),
+    ];
+    for (error, has_source) in transfer_errors {
+        assert!(!error.to_string().is_empty());
+        assert_eq!(error.source().is_some(), has_source, "{:?}", error);
+    }
+    assert!(TransferError::from_code(0).is_none());
+
+    // Forced without any bus
+    match Account::new("a1b2c3d4e5f60718").ensure_kind(AccountType::Sip) {
+        Err(JamiError::WrongAccountType { .. }) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+    match Jami::try_get_conversations("") {
+        Err(JamiError::InvalidArgument(param, _)) => assert_eq!(param, "account_id"),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn bus_errors() {
+    let _lock = BUS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let _reset = ResetBus;
+    Jami::set_bus(BusType::Address(String::from(
+        "unix:path=/nonexistent/jami-rs-test",
+    )));
+    match Jami::try_get_account_list() {
+        Err(JamiError::BusConnection(_)) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+    let (tx, _rx) = mpsc::channel::<Event<()>>(1);
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    assert!(rt.block_on(Jami::handle_events(tx, stop)).is_err());
+
+    let bus = EmptyBus::start();
+    Jami::set_bus(BusType::Address(bus.address.clone()));
+    // Replies of the bus itself, decoded like the wrappers do
+    let conn = connect(&bus.address);
+    let proxy = conn.with_proxy(
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        Duration::from_secs(5),
+    );
+    let result: Result<(), _> = proxy.method_call("org.freedesktop.DBus", "NoSuchMethod", ());
+    match result.map_err(|e| JamiError::method_call("NoSuchMethod", e)) {
+        Err(JamiError::MethodCall { method, .. }) => assert_eq!(method, "NoSuchMethod"),
+        result => panic!("unexpected result {:?}", result),
+    }
+    let result: Result<(u32,), _> = proxy.method_call("org.freedesktop.DBus", "GetId", ());
+    match result.map_err(|e| JamiError::method_call("GetId", e)) {
+        Err(JamiError::InvalidReply(_)) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    let account = String::from("a1b2c3d4e5f60718");
+    match Jami::try_get_conversations(&account) {
+        Err(JamiError::DaemonUnavailable) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+    // Not an empty list of conversations
+    assert!(Jami::try_start_conversation(&account).is_err());
+    #[allow(deprecated)]
+    let conversations = Jami::get_conversations(&account);
+    assert!(conversations.is_empty());
+    // Nor empty members, contacts or a silent send
+    let (conv, peer) = (
+        "0123456789abcdef0123456789abcdef01234567",
+        "fedcba9876543210fedcba9876543210fedcba98",
+    );
+    let results = vec![
+        Jami::try_get_members(&account, conv).map(|_| ()),
+        Jami::try_get_conversation_infos(&account, conv).map(|_| ()),
+        Jami::try_get_contacts(&account).map(|_| ()),
+        Jami::try_add_contact(&account, peer),
+        Jami::try_remove_contact(&account, peer, true),
+        Jami::try_set_default_moderator(&account, peer, true),
+        Jami::try_load_conversation(&account, conv, "", 10).map(|_| ()),
+        Jami::try_send_message(&account, conv, &String::from("hi"), "", 0),
+    ];
+    for result in results {
+        match result {
+            Err(JamiError::DaemonUnavailable) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+    match Jami::try_get_account_list() {
+        Err(JamiError::DaemonUnavailable) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+    // Killing the bus ends the event loop
+    let (tx, mut rx) = mpsc::channel::<Event<()>>(8);
+    let stop = Arc::new(AtomicBool::new(false));
+    let events = thread::spawn(move || {
+        let mut rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(Jami::handle_events(tx, stop))
+    });
+    // Let the loop add its match rules
+    thread::sleep(Duration::from_millis(500));
+    drop(bus);
+    match next_event(&mut rt, &mut rx) {
+        Some(Event::DaemonDisconnected(_)) => {}
+        event => panic!("unexpected event {:?}", event),
+    }
+    assert!(events.join().unwrap().is_err());
+}
+
+#[test]
+fn reconnect() {
+    let _lock = BUS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let _reset = ResetBus;
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("bus");
+    let bus = EmptyBus::start_at(Some(&socket));
+    // Without the guid of the first bus
+    Jami::set_bus(BusType::Address(format!("unix:path={}", socket.display())));
+    let daemon = connect(&bus.address);
+    daemon
+        .request_name("cx.ring.Ring", false, true, false)
+        .unwrap();
+
+    let (tx, mut rx) = mpsc::channel::<Event<()>>(8);
+    let stop = Arc::new(AtomicBool::new(false));
+    let loop_stop = stop.clone();
+    let options = EventLoopOptions {
+        reconnect: Some(ReconnectPolicy {
+            initial_delay: Duration::from_millis(50),
+            max_delay: Duration::from_millis(200),
+            max_retries: Some(50),
+        }),
+        ..Default::default()
+    };
+    let events = thread::spawn(move || {
+        let mut rt = Runtime::new().unwrap();
+        rt.block_on(Jami::handle_events_with_options(tx, loop_stop, options))
+    });
+    thread::sleep(Duration::from_millis(500));
+    let mut rt = Runtime::new().unwrap();
+
+    // The daemon restarts
+    daemon.release_name("cx.ring.Ring").unwrap();
+    match next_event(&mut rt, &mut rx) {
+        Some(Event::DaemonDisconnected(_)) => {}
+        event => panic!("unexpected event {:?}", event),
+    }
+    daemon
+        .request_name("cx.ring.Ring", false, true, false)
+        .unwrap();
+    match next_event(&mut rt, &mut rx) {
+        Some(Event::DaemonReconnected) => {}
+        event => panic!("unexpected event {:?}", event),
+    }
+
+    // The bus restarts
+    drop(daemon);
+    drop(bus);
+    match next_event(&mut rt, &mut rx) {
+        Some(Event::DaemonDisconnected(_)) => {}
+        event => panic!("unexpected event {:?}", event),
+    }
+    thread::sleep(Duration::from_millis(300));
+    let _bus = EmptyBus::start_at(Some(&socket));
+    let daemon = connect(&_bus.address);
+    daemon
+        .request_name("cx.ring.Ring", false, true, false)
+        .unwrap();
+    match next_event(&mut rt, &mut rx) {
+        Some(Event::DaemonReconnected) => {}
+        event => panic!("unexpected event {:?}", event),
+    }
+
+    stop.store(true, Ordering::Relaxed);
+    assert!(events.join().unwrap().is_ok());
+    // Sent once per disconnection
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn stop_handle_ends_the_loop_at_once() {
+    let _lock = BUS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let _reset = ResetBus;
+    let bus = EmptyBus::start();
+    Jami::set_bus(BusType::Address(bus.address.clone()));
+
+    let (tx, _rx) = mpsc::channel::<Event<()>>(8);
+    let stop = StopHandle::new();
+    let loop_stop = stop.clone();
+    let events = thread::spawn(move || {
+        let mut rt = Runtime::new().unwrap();
+        rt.block_on(Jami::handle_events_with_cancel(tx, loop_stop))
+    });
+    thread::sleep(Duration::from_millis(500));
+    let start = Instant::now();
+    stop.stop();
+    assert!(events.join().unwrap().is_ok());
+    assert!(start.elapsed() < Duration::from_millis(50));
+}
+
+#[test]
+fn connect_to_ignores_the_default_bus() {
+    let _lock = BUS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let _reset = ResetBus;
+    let bus = EmptyBus::start();
+    Jami::set_bus(BusType::Address(String::from(
+        "unix:path=/nonexistent/jami-rs-test",
+    )));
+    assert!(matches!(Jami::connect(), Err(JamiError::BusConnection(_))));
+
+    let other = BusType::Address(bus.address.clone());
+    assert!(Jami::connect_to(&other).unwrap().is_connected());
+    let (tx, _rx) = mpsc::channel::<Event<()>>(8);
+    let stop = StopHandle::new();
+    let loop_stop = stop.clone();
+    let options = EventLoopOptions {
+        bus: Some(other),
+        ..Default::default()
+    };
+    let events = thread::spawn(move || {
+        let mut rt = Runtime::new().unwrap();
+        rt.block_on(Jami::handle_events_until(tx, loop_stop, options))
+    });
+    thread::sleep(Duration::from_millis(500));
+    stop.stop();
+    assert!(events.join().unwrap().is_ok());
+}
+
+#[test]
+fn renamed_daemon() {
+    let _lock = BUS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let _reset = ResetBus;
+    let bus = EmptyBus::start();
+    Jami::set_bus(BusType::Address(bus.address.clone()));
+    let address = bus.address.clone();
+    thread::spawn(move || {
+        let conn = connect(&address);
+        conn.request_name("net.jami.daemon", false, true, false)
+            .unwrap();
+        let mut cr = Crossroads::new();
+        let iface = cr.register("net.jami.daemon.ConfigurationManager", |b| {
+            b.method("getAccountList", (), ("list",), |_, _, _: ()| {
+                Ok((vec![String::from("acc1")],))
+            });
+        });
+        cr.insert("/net/jami/daemon/ConfigurationManager", &[iface], ());
+        // Fails when the bus stops
+        let _ = cr.serve(&conn);
+    });
+    thread::sleep(Duration::from_millis(200));
+
+    // Detected
+    let config = JamiConfig::named("net.jami.daemon", "/net/jami/daemon");
+    assert_eq!(Jami::daemon_flavor(), DaemonFlavor::NetJami);
+    assert!(JamiError::DaemonUnavailable
+        .to_string()
+        .contains("net.jami.daemon"));
+    let client = Jami::connect().unwrap();
+    assert_eq!(client.config(), &config);
+    let (ids,): (Vec<String>,) = client.configuration("getAccountList", ()).unwrap();
+    assert_eq!(ids, vec!["acc1"]);
+
+    Jami::set_config(JamiConfig::default());
+    assert_eq!(Jami::daemon_flavor(), DaemonFlavor::CxRing);
+    assert!(JamiError::DaemonUnavailable
+        .to_string()
+        .contains("cx.ring.Ring"));
+    assert!(matches!(
+        Jami::try_get_account_list(),
+        Err(JamiError::DaemonUnavailable)
+    ));
+    let client = Jami::connect().unwrap().with_config(config.clone());
+    let (ids,): (Vec<String>,) = client.configuration("getAccountList", ()).unwrap();
+    assert_eq!(ids, vec!["acc1"]);
+
+    Jami::set_config(config.clone());
+    let client = Jami::connect().unwrap();
+    assert_eq!(client.config(), &config);
+    let (ids,): (Vec<String>,) = client.configuration("getAccountList", ()).unwrap();
+    assert_eq!(ids, vec!["acc1"]);
+}
+
+#[test]
+fn slow_daemon_times_out() {
+    let _lock = BUS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let _reset = ResetBus;
+    let bus = EmptyBus::start();
+    Jami::set_bus(BusType::Address(bus.address.clone()));
+    let address = bus.address.clone();
+    thread::spawn(move || {
+        let conn = connect(&address);
+        conn.request_name("cx.ring.Ring", false, true, false)
+            .unwrap();
+        let mut cr = Crossroads::new();
+        let iface = cr.register("cx.ring.Ring.ConfigurationManager", |b| {
+            b.method("getAccountList", (), ("list",), |_, _, _: ()| {
+                // E.g. a busy daemon on a small board
+                thread::sleep(Duration::from_millis(300));
+                Ok((vec![String::from("acc1")],))
+            });
+        });
+        cr.insert("/cx/ring/Ring/ConfigurationManager", &[iface], ());
+        // Fails when the bus stops
+        let _ = cr.serve(&conn);
+    });
+    thread::sleep(Duration::from_millis(200));
+
+    let client = Jami::connect().unwrap();
+    let slow = client.clone().with_timeout(Duration::from_millis(50));
+    let result: Result<(Vec<String>,), JamiError> = slow.configuration("getAccountList", ());
+    assert!(matches!(result, Err(JamiError::Timeout)), "{:?}", result);
+    // The default timeout (5s) is enough
+    let (ids,): (Vec<String>,) = client.configuration("getAccountList", ()).unwrap();
+    assert_eq!(ids, vec!["acc1"]);
+}
+
+// Connections of the bus: its own name and the unique names
+fn connections(conn: &Connection) -> usize {
+    let proxy = conn.with_proxy(
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        Duration::from_secs(1),
+    );
+    let (names,): (Vec<String>,) = proxy
+        .method_call("org.freedesktop.DBus", "ListNames", ())
+        .unwrap();
+    names.len()
+}
+
+#[test]
+fn async_connections_end_with_their_users() {
+    let _lock = BUS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let _reset = ResetBus;
+    let bus = EmptyBus::start();
+    let address = BusType::Address(bus.address.clone());
+    let observer = connect(&bus.address);
+    let idle = connections(&observer);
+
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let client = AsyncJamiClient::connect_to(&address).unwrap();
+        let copy = client.clone();
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+        assert_eq!(connections(&observer), idle + 1);
+        drop(client);
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+        assert_eq!(connections(&observer), idle + 1);
+        drop(copy);
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+        assert_eq!(connections(&observer), idle);
+
+        // Same for the connection of an event loop
+        let (tx, _rx) = mpsc::channel::<Event<()>>(8);
+        let stop = StopHandle::new();
+        let options = EventLoopOptions {
+            bus: Some(address),
+            ..Default::default()
+        };
+        let events = tokio::spawn(Jami::handle_events_until(tx, stop.clone(), options));
+        tokio::time::delay_for(Duration::from_millis(200)).await;
+        assert_eq!(connections(&observer), idle + 1);
+        stop.stop();
+        assert!(events.await.unwrap().is_ok());
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+        assert_eq!(connections(&observer), idle);
+    });
+}
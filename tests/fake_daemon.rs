@@ -0,0 +1,945 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+// Needs --features test-util and dbus-daemon in the PATH
+use jami_rs::account::{Account, AccountChange, RegistrationState};
+use jami_rs::testutil::{FakeDaemon, SentMessage};
+use jami_rs::{
+    AsyncJamiClient, BusType, Conversation, DataTransferEventCode, DataTransferInfo, Event,
+    EventLoopHandle, Jami, JamiClient, JamiError, JamiSession, SessionConfig, TransferError,
+};
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::net::TcpListener;
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tokio::stream::{Stream, StreamExt};
+use tokio::sync::mpsc;
+
+const CONV1: &str = "c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0";
+const CONV2: &str = "d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1";
+const PEER: &str = "e2e2e2e2e2e2e2e2e2e2e2e2e2e2e2e2e2e2e2e2";
+
+fn map(entries: &[(&str, &str)]) -> HashMap<String, String> {
+    entries
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+/**
+ * Run handle_events on its own runtime, its loop blocks the thread
+ * @return the events, the stop flag and the thread to join
+ */
+fn start_event_loop() -> (mpsc::Receiver<Event<()>>, Arc<AtomicBool>, JoinHandle<()>) {
+    let (tx, rx) = mpsc::channel(32);
+    let stop = Arc::new(AtomicBool::new(false));
+    let loop_stop = stop.clone();
+    let handle = thread::spawn(move || {
+        let mut rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(Jami::handle_events(tx, loop_stop)).unwrap();
+    });
+    // Let the loop add its match rules
+    thread::sleep(Duration::from_millis(500));
+    (rx, stop, handle)
+}
+
+fn stop_event_loop(stop: Arc<AtomicBool>, handle: JoinHandle<()>) {
+    stop.store(true, Ordering::Relaxed);
+    handle.join().unwrap();
+}
+
+/**
+ * Forward a tcp port to the unix socket of a bus, like socat does to reach
+ * a jamid running in a container
+ * @param address   Address of the bus (unix:path=...)
+ * @return the port
+ */
+fn forward_tcp(address: &str) -> u16 {
+    let path = address["unix:path=".len()..]
+        .split(',')
+        .next()
+        .unwrap()
+        .to_string();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for client in listener.incoming() {
+            let mut client = client.unwrap();
+            let mut bus = UnixStream::connect(&path).unwrap();
+            let (mut client_read, mut bus_write) =
+                (client.try_clone().unwrap(), bus.try_clone().unwrap());
+            thread::spawn(move || io::copy(&mut client_read, &mut bus_write));
+            thread::spawn(move || io::copy(&mut bus, &mut client));
+        }
+    });
+    port
+}
+
+// Go back to the session bus, even if the test fails
+struct ResetBus;
+
+impl Drop for ResetBus {
+    fn drop(&mut self) {
+        Jami::set_bus(BusType::Session);
+    }
+}
+
+async fn next_event(rx: &mut mpsc::Receiver<Event<()>>) -> Event<()> {
+    tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("no event received")
+        .unwrap()
+}
+
+#[test]
+fn get_account_parses_details() {
+    let daemon = FakeDaemon::start();
+    let hash = "0123456789abcdef0123456789abcdef01234567";
+    daemon.add_account(
+        "acc1",
+        map(&[
+            ("Account.type", "RING"),
+            ("Account.alias", "Alice"),
+            ("Account.enable", "true"),
+            ("Account.username", &*format!("ring:{}", hash)),
+            ("Account.deviceID", "device1"),
+        ]),
+        map(&[
+            ("Account.registrationStatus", "REGISTERED"),
+            ("Account.registeredName", "alice"),
+        ]),
+    );
+
+    let account = Jami::try_get_account("acc1").unwrap().unwrap();
+    assert_eq!(account.id, "acc1");
+    assert_eq!(account.alias, "Alice");
+    assert!(account.enabled);
+    assert_eq!(account.hash, hash);
+    assert_eq!(account.device_id, "device1");
+    assert_eq!(account.registered_name, "alice");
+    assert_eq!(account.registration_state, RegistrationState::Registered);
+
+    assert!(Jami::try_get_account("unknown").unwrap().is_none());
+}
+
+#[test]
+fn get_account_list_keeps_order() {
+    let daemon = FakeDaemon::start();
+    for id in &["b", "a", "c"] {
+        daemon.add_account(id, map(&[("Account.alias", id)]), HashMap::new());
+    }
+    let ids: Vec<String> = Jami::try_get_account_list()
+        .unwrap()
+        .into_iter()
+        .map(|account| account.id)
+        .collect();
+    assert_eq!(ids, vec!["b", "a", "c"]);
+}
+
+#[test]
+fn handle_reuses_its_connection() {
+    let daemon = FakeDaemon::start();
+    daemon.add_account("acc1", map(&[("Account.alias", "Alice")]), HashMap::new());
+    let handle = Jami::connect().unwrap();
+    let details = handle.try_get_account_details("acc1").unwrap();
+    assert_eq!(details.get("Account.alias").unwrap(), "Alice");
+    assert_eq!(handle.try_get_account_list().unwrap().len(), 1);
+    assert!(handle.try_get_account("unknown").unwrap().is_none());
+
+    // Send, for the shared state of an app
+    let shared = Arc::new(std::sync::Mutex::new(handle));
+    let thread_shared = shared.clone();
+    let alias = thread::spawn(move || {
+        let handle = thread_shared.lock().unwrap();
+        handle.try_get_account("acc1").unwrap().unwrap().alias
+    })
+    .join()
+    .unwrap();
+    assert_eq!(alias, "Alice");
+}
+
+#[test]
+fn send_message_reaches_daemon() {
+    let daemon = FakeDaemon::start();
+    daemon
+        .state()
+        .conversations
+        .insert(String::from("acc1"), vec![String::from(CONV1)]);
+    Jami::try_send_message(
+        &String::from("acc1"),
+        &String::from(CONV1),
+        &String::from("hello"),
+        &String::from("parent"),
+        0,
+    )
+    .unwrap();
+    assert_eq!(
+        daemon.sent_messages(),
+        vec![SentMessage {
+            account_id: String::from("acc1"),
+            conversation_id: String::from(CONV1),
+            body: String::from("hello"),
+            reply_to: String::from("parent"),
+            flag: 0,
+        }]
+    );
+}
+
+#[tokio::test]
+async fn handle_events_decodes_signals() {
+    let daemon = FakeDaemon::start();
+    let (mut rx, stop, handle) = start_event_loop();
+
+    daemon.emit("conversationReady", ("acc1", CONV1));
+    match next_event(&mut rx).await {
+        Event::ConversationReady(account_id, conversation_id) => {
+            assert_eq!(account_id, "acc1");
+            assert_eq!(conversation_id, CONV1);
+        }
+        event => panic!("unexpected event {:?}", event),
+    }
+
+    let payloads = map(&[("id", "commit1"), ("type", "text/plain"), ("body", "hi")]);
+    daemon.emit("messageReceived", ("acc1", CONV1, payloads.clone()));
+    match next_event(&mut rx).await {
+        Event::Message {
+            account_id,
+            conversation_id,
+            payloads: received,
+        } => {
+            assert_eq!(account_id, "acc1");
+            assert_eq!(conversation_id, CONV1);
+            assert_eq!(received, payloads);
+        }
+        event => panic!("unexpected event {:?}", event),
+    }
+
+    daemon.emit(
+        "registrationStateChanged",
+        ("acc1", "ERROR_NETWORK", 408i32, "timeout"),
+    );
+    match next_event(&mut rx).await {
+        Event::RegistrationStateChanged {
+            account_id,
+            state,
+            raw_state,
+            code,
+            detail,
+        } => {
+            assert_eq!(account_id, "acc1");
+            assert_eq!(state, RegistrationState::ErrorNetwork);
+            assert_eq!(raw_state, "ERROR_NETWORK");
+            assert_eq!(code, 408);
+            assert_eq!(detail, "timeout");
+        }
+        event => panic!("unexpected event {:?}", event),
+    }
+
+    stop_event_loop(stop, handle);
+}
+
+#[tokio::test]
+async fn transfer_event_flow() {
+    let daemon = FakeDaemon::start();
+    daemon.state().transfers.insert(
+        42,
+        DataTransferInfo {
+            account_id: String::from("acc1"),
+            last_event: 4,
+            flags: 0,
+            total: 1024,
+            bytes_progress: 512,
+            author: String::from("peer"),
+            peer: String::from("peer"),
+            conv_id: String::from(CONV1),
+            display_name: String::from("file.txt"),
+            path: String::from("/tmp/file.txt"),
+            mimetype: String::from("text/plain"),
+        },
+    );
+    let (mut rx, stop, handle) = start_event_loop();
+
+    daemon.emit("dataTransferEvent", ("acc1", CONV1, 42u64, 4i32));
+    let (account_id, conversation_id, tid) = match next_event(&mut rx).await {
+        Event::DataTransferEvent(account_id, conversation_id, tid, code) => {
+            assert_eq!(code, DataTransferEventCode::WaitHostAcceptance);
+            assert!(!code.is_over());
+            assert_eq!(i32::from(code), 4);
+            (account_id, conversation_id, tid)
+        }
+        event => panic!("unexpected event {:?}", event),
+    };
+    assert_eq!((&*account_id, &*conversation_id, tid), ("acc1", CONV1, 42));
+
+    let info =
+        Jami::try_data_transfer_info(account_id.clone(), conversation_id.clone(), tid).unwrap();
+    assert_eq!(info.display_name, "file.txt");
+    assert_eq!(info.total, 1024);
+    assert_eq!(info.bytes_progress, 512);
+
+    stop_event_loop(stop, handle);
+}
+
+#[test]
+fn data_transfer_info_reports_stale_ids() {
+    let daemon = FakeDaemon::start();
+    let info = DataTransferInfo {
+        account_id: String::from("acc1"),
+        last_event: 6,
+        flags: 0,
+        total: 2048,
+        bytes_progress: 2048,
+        author: String::from("peer"),
+        peer: String::from("peer"),
+        conv_id: String::from(CONV1),
+        display_name: String::from("file.txt"),
+        path: String::from("/tmp/file.txt"),
+        mimetype: String::from("text/plain"),
+    };
+    daemon.state().transfers.insert(42, info);
+    let (account, conv) = (String::from("acc1"), String::from(CONV1));
+
+    let info = Jami::try_data_transfer_info(account.clone(), conv.clone(), 42).unwrap();
+    assert_eq!(info.total, 2048);
+    // E.g. kept from before a restart of the daemon
+    match Jami::try_data_transfer_info(account.clone(), conv.clone(), 43) {
+        Err(TransferError::Unknown) => {}
+        Err(error) => panic!("unexpected error {}", error),
+        Ok(_) => panic!("stale transfer reported as valid"),
+    }
+}
+
+#[tokio::test]
+async fn send_file_tracked_returns_the_transfer_id() {
+    let daemon = FakeDaemon::start();
+    let (account, conv) = (String::from("acc1"), String::from(CONV1));
+    let (path, name) = (String::from("/tmp/photo.png"), String::from("photo.png"));
+    let (mut rx, stop, handle) = start_event_loop();
+
+    let tid = Jami::send_file_tracked(&account, &conv, &path, &name, "")
+        .await
+        .unwrap();
+    assert_ne!(tid, 0);
+    match next_event(&mut rx).await {
+        Event::DataTransferEvent(account_id, conversation_id, id, _) => {
+            assert_eq!((&*account_id, &*conversation_id, id), ("acc1", CONV1, tid));
+        }
+        event => panic!("unexpected event {:?}", event),
+    }
+    assert_eq!(daemon.sent_files(), vec![(conv, path)]);
+
+    stop_event_loop(stop, handle);
+}
+
+#[tokio::test]
+async fn session_selects_enabled_account() {
+    let daemon = FakeDaemon::start();
+    daemon.add_account(
+        "disabled",
+        map(&[("Account.type", "RING"), ("Account.enable", "false")]),
+        HashMap::new(),
+    );
+    daemon.add_account(
+        "bot",
+        map(&[("Account.type", "RING"), ("Account.enable", "true")]),
+        HashMap::new(),
+    );
+
+    let session = JamiSession::start(SessionConfig::default()).await.unwrap();
+    assert_eq!(session.account().id, "bot");
+    session.send_text(CONV1, "beep").unwrap();
+    assert_eq!(daemon.sent_messages()[0].body, "beep");
+    session.shutdown();
+
+    let config = SessionConfig {
+        account_id: Some(String::from("unknown")),
+        ..Default::default()
+    };
+    assert!(JamiSession::start(config).await.is_err());
+}
+
+#[test]
+fn try_send_message_to_removed_conversation() {
+    let daemon = FakeDaemon::start();
+    let account = String::from("acc1");
+    let (conv1, conv2) = (String::from(CONV1), String::from(CONV2));
+    let body = String::from("hello");
+    daemon
+        .state()
+        .conversations
+        .insert(account.clone(), vec![conv1.clone()]);
+
+    assert!(Jami::try_send_message(&account, &conv1, &body, "", 0).is_ok());
+    assert_eq!(daemon.sent_messages().len(), 1);
+
+    // Unknown to the daemon
+    match Jami::try_send_message(&account, &conv2, &body, "", 0) {
+        Err(JamiError::NotFound(_)) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    // Removed while sending: the daemon answers an error
+    daemon.state().removed_conversations.push(conv1.clone());
+    match Jami::try_send_message(&account, &conv1, &body, "", 0) {
+        Err(JamiError::NotFound(_)) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert_eq!(daemon.sent_messages().len(), 1);
+}
+
+#[test]
+fn try_send_file_reports_errors() {
+    let daemon = FakeDaemon::start();
+    let account = String::from("acc1");
+    let (conv1, conv2) = (String::from(CONV1), String::from(CONV2));
+    let (path, name) = (String::from("/tmp/photo.png"), String::from("photo.png"));
+    daemon.state().removed_conversations.push(conv2.clone());
+
+    assert!(Jami::try_send_file(&account, &conv1, &path, &name, "").is_ok());
+    match Jami::try_send_file(&account, &conv2, &path, &name, "") {
+        Err(JamiError::MethodCall { method, .. }) => assert_eq!(method, "sendFile"),
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert_eq!(daemon.sent_files(), vec![(conv1, path)]);
+}
+
+#[test]
+fn ban_and_unban_contact() {
+    let daemon = FakeDaemon::start();
+    let peer = "fedcba9876543210fedcba9876543210fedcba98";
+    let mut account = Account::new("acc1");
+    assert!(account.banned_contacts().unwrap().is_empty());
+
+    account.ban_contact(&format!("jami:{}", peer)).unwrap();
+    assert!(daemon.state().contacts["acc1"][peer]);
+    assert!(account.is_banned(peer).unwrap());
+
+    // The daemon can't be read: the cache keeps the ban
+    daemon
+        .state()
+        .failing_methods
+        .push(String::from("getContacts"));
+    assert!(account.refresh_banned_contacts().is_err());
+    assert!(account.is_banned(peer).unwrap());
+    daemon.state().failing_methods.clear();
+
+    // Refused by the daemon: the cache keeps the ban
+    daemon
+        .state()
+        .failing_methods
+        .push(String::from("addContact"));
+    match account.unban_contact(peer) {
+        Err(JamiError::MethodCall { method, .. }) => assert_eq!(method, "addContact"),
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert!(account.is_banned(peer).unwrap());
+
+    daemon.state().failing_methods.clear();
+    account.unban_contact(peer).unwrap();
+    assert!(!daemon.state().contacts["acc1"][peer]);
+    assert!(!account.is_banned(peer).unwrap());
+
+    daemon
+        .state()
+        .failing_methods
+        .push(String::from("removeContact"));
+    assert!(account.ban_contact(peer).is_err());
+    assert!(account.banned_contacts().unwrap().is_empty());
+}
+
+#[test]
+fn add_and_remove_moderator() {
+    let daemon = FakeDaemon::start();
+    let peer = "fedcba9876543210fedcba9876543210fedcba98";
+    let mut account = Account::new("acc1");
+    assert!(account.moderators().unwrap().is_empty());
+
+    account.add_moderator(peer).unwrap();
+    assert_eq!(daemon.state().moderators["acc1"], vec![peer]);
+    assert_eq!(account.moderators().unwrap(), vec![peer]);
+
+    // The daemon can't be read: the cache is kept
+    daemon
+        .state()
+        .failing_methods
+        .push(String::from("getDefaultModerators"));
+    assert!(account.refresh_moderators().is_err());
+    assert_eq!(account.moderators().unwrap(), vec![peer]);
+    daemon.state().failing_methods.clear();
+
+    // Refused by the daemon: the cache is untouched
+    daemon
+        .state()
+        .failing_methods
+        .push(String::from("setDefaultModerator"));
+    match account.remove_moderator(peer) {
+        Err(JamiError::MethodCall { method, .. }) => assert_eq!(method, "setDefaultModerator"),
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert_eq!(account.moderators().unwrap(), vec![peer]);
+
+    daemon.state().failing_methods.clear();
+    account.remove_moderator(peer).unwrap();
+    assert!(daemon.state().moderators["acc1"].is_empty());
+    assert!(account.moderators().unwrap().is_empty());
+}
+
+#[test]
+fn all_moderators() {
+    let daemon = FakeDaemon::start();
+    let account = Account::new("acc1");
+    assert!(!account.all_moderators().unwrap());
+    account.set_all_moderators(true).unwrap();
+    assert!(account.all_moderators().unwrap());
+
+    daemon.state().failing_methods.extend(vec![
+        String::from("setAllModerators"),
+        String::from("isAllModerators"),
+    ]);
+    match account.set_all_moderators(false) {
+        Err(JamiError::MethodCall { method, .. }) => assert_eq!(method, "setAllModerators"),
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert!(account.all_moderators().is_err());
+    assert!(daemon.state().all_moderators["acc1"]);
+}
+
+#[test]
+fn set_avatar_reports_errors() {
+    let daemon = FakeDaemon::start();
+    let mut account = Account::new("acc1");
+    account.set_avatar("/tmp/photo.PNG").unwrap();
+    assert_eq!(
+        daemon.state().updated_profiles,
+        vec![(
+            String::from("acc1"),
+            String::new(),
+            String::from("/tmp/photo.PNG"),
+            String::from("png")
+        )]
+    );
+
+    daemon
+        .state()
+        .failing_methods
+        .push(String::from("updateProfile"));
+    match account.set_avatar("/tmp/other.png") {
+        Err(JamiError::MethodCall { method, .. }) => assert_eq!(method, "updateProfile"),
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert_eq!(daemon.state().updated_profiles.len(), 1);
+}
+
+#[test]
+fn conversation_handle() {
+    let daemon = FakeDaemon::start();
+    let peer = "fedcba9876543210fedcba9876543210fedcba98";
+    let conversation = Conversation::new(JamiClient::new().unwrap(), "acc1", CONV1);
+    assert_eq!(conversation.account_id(), "acc1");
+    assert_eq!(conversation.id(), CONV1);
+
+    conversation.send("hello").unwrap();
+    let sent = daemon.sent_messages();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].account_id, "acc1");
+    assert_eq!(sent[0].conversation_id, CONV1);
+    assert_eq!(sent[0].body, "hello");
+
+    // Members are cached until refresh()
+    assert!(conversation.members().unwrap().is_empty());
+    daemon
+        .state()
+        .members
+        .insert(String::from(CONV1), vec![String::from("alice")]);
+    assert!(conversation.members().unwrap().is_empty());
+    conversation.refresh();
+    assert_eq!(conversation.members().unwrap()[0]["uri"], "alice");
+
+    // Invites and kicks drop the cache
+    conversation.invite(peer).unwrap();
+    let uris = |members: Vec<HashMap<String, String>>| -> Vec<String> {
+        members.into_iter().map(|m| m["uri"].clone()).collect()
+    };
+    assert_eq!(uris(conversation.members().unwrap()), vec!["alice", peer]);
+    conversation.kick("alice").unwrap();
+    assert_eq!(uris(conversation.members().unwrap()), vec![peer]);
+
+    // Refused by the daemon: the cache is kept
+    daemon
+        .state()
+        .failing_methods
+        .push(String::from("removeConversationMember"));
+    assert!(conversation.kick(peer).is_err());
+    assert_eq!(uris(conversation.members().unwrap()), vec![peer]);
+}
+
+#[test]
+fn place_and_hang_up_call() {
+    let daemon = FakeDaemon::start();
+    let peer = "jami:0123456789abcdef0123456789abcdef01234567";
+
+    // Peers are normalized
+    let call_id = Jami::place_call("acc1", &peer[5..].to_uppercase()).unwrap();
+    assert_eq!(
+        daemon.state().placed_calls,
+        vec![(String::from("acc1"), String::from(peer))]
+    );
+    assert_eq!(daemon.state().active_calls, vec![call_id.clone()]);
+    Jami::place_call("acc1", "ring:alice").unwrap();
+    assert_eq!(daemon.state().placed_calls[1].1, "jami:alice");
+
+    // Invalid arguments don't reach the daemon
+    for (account, uri, param) in &[
+        ("", peer, "account_id"),
+        ("acc1", "", "uri"),
+        ("acc1", "a b", "uri"),
+    ] {
+        match Jami::place_call(account, uri) {
+            Err(JamiError::InvalidArgument(name, _)) => assert_eq!(name, *param),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+    assert!(matches!(
+        Jami::hang_up("a b", &call_id),
+        Err(JamiError::InvalidArgument(_, _))
+    ));
+    assert_eq!(daemon.state().placed_calls.len(), 2);
+
+    daemon
+        .state()
+        .failing_methods
+        .push(String::from("placeCallWithMedia"));
+    assert!(matches!(
+        Jami::place_call("acc1", peer),
+        Err(JamiError::OperationFailed(_))
+    ));
+
+    Jami::hang_up("acc1", &call_id).unwrap();
+    assert_eq!(daemon.state().active_calls, vec!["call1"]);
+    // Already hung up
+    assert!(matches!(
+        Jami::hang_up("acc1", &call_id),
+        Err(JamiError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn async_client_survives_dropped_calls() {
+    let daemon = FakeDaemon::start();
+    daemon.add_account("acc1", map(&[("Account.alias", "Alice")]), HashMap::new());
+    daemon
+        .state()
+        .conversations
+        .insert(String::from("acc1"), vec![String::from(CONV1)]);
+    let client = AsyncJamiClient::new().unwrap();
+
+    // Sent, then dropped before the reply
+    let mut call = Box::pin(client.get_account_details("acc1"));
+    let pending = tokio::future::poll_fn(|cx| Poll::Ready(call.as_mut().poll(cx).is_pending()));
+    assert!(pending.await);
+    drop(call);
+
+    let details = client.get_account_details("acc1").await.unwrap();
+    assert_eq!(details.get("Account.alias").unwrap(), "Alice");
+    assert_eq!(client.get_conversations("acc1").await.unwrap(), vec![CONV1]);
+    client
+        .send_message("acc1", CONV1, "hello", "", 0)
+        .await
+        .unwrap();
+    assert!(matches!(
+        client.send_message("acc1", CONV2, "hello", "", 0).await,
+        Err(JamiError::NotFound(_))
+    ));
+    assert_eq!(daemon.sent_messages().len(), 1);
+}
+
+#[tokio::test]
+async fn async_client_calls_and_transfers() {
+    let daemon = FakeDaemon::start();
+    daemon.add_account("acc1", map(&[("Account.alias", "Alice")]), HashMap::new());
+    daemon.state().transfers.insert(
+        42,
+        DataTransferInfo {
+            total: 2048,
+            ..DataTransferInfo::default()
+        },
+    );
+    let client = AsyncJamiClient::new().unwrap();
+    let peer = "jami:0123456789abcdef0123456789abcdef01234567";
+
+    assert_eq!(client.get_account("acc1").await.unwrap().alias, "Alice");
+    assert!(matches!(
+        client.get_account("unknown").await,
+        Err(JamiError::NotFound(_))
+    ));
+
+    let call_id = client.place_call("acc1", peer).await.unwrap();
+    assert_eq!(daemon.state().active_calls, vec![call_id.clone()]);
+    assert!(matches!(
+        client.place_call("acc1", "a b").await,
+        Err(JamiError::InvalidArgument(_, _))
+    ));
+    client.hang_up("acc1", &call_id).await.unwrap();
+    assert!(matches!(
+        client.hang_up("acc1", &call_id).await,
+        Err(JamiError::NotFound(_))
+    ));
+
+    let info = client.data_transfer_info("acc1", CONV1, 42).await.unwrap();
+    assert_eq!(info.total, 2048);
+    assert!(matches!(
+        client.data_transfer_info("acc1", CONV1, 43).await,
+        Err(TransferError::Unknown)
+    ));
+}
+
+#[tokio::test]
+async fn broadcast_message_reports_each_conversation() {
+    let daemon = FakeDaemon::start();
+    let account = String::from("acc1");
+    let (conv1, conv2) = (String::from(CONV1), String::from(CONV2));
+    let body = String::from("announcement");
+    daemon.state().removed_conversations.push(conv2.clone());
+
+    let empty = Jami::broadcast_message(&account, std::slice::from_ref(&conv1), "").await;
+    assert!(matches!(empty, Err(JamiError::InvalidArgument(_, _))));
+
+    let targets = vec![
+        conv1.clone(),
+        conv2.clone(),
+        conv1.clone(),
+        String::from("bad"),
+    ];
+    let result = Jami::broadcast_message(&account, &targets, &body)
+        .await
+        .unwrap();
+    assert_eq!(result.outcomes.len(), 3);
+    assert_eq!(result.succeeded, 1);
+    assert!(!result.all_succeeded());
+    assert_eq!(result.outcomes[0].0, conv1);
+    assert_eq!(result.outcomes[0].1.as_ref().unwrap().len(), 40);
+    assert!(matches!(result.outcomes[1].1, Err(JamiError::NotFound(_))));
+    assert!(matches!(
+        result.outcomes[2].1,
+        Err(JamiError::InvalidArgument(_, _))
+    ));
+    assert_eq!(daemon.sent_messages().len(), 1);
+}
+
+#[tokio::test]
+async fn reachability_probe_keeps_existing_subscriptions() {
+    let daemon = FakeDaemon::start();
+    let mut buddies = HashMap::new();
+    buddies.insert(PEER.to_string(), true);
+    daemon
+        .state()
+        .subscriptions
+        .insert(String::from("acc1"), buddies);
+    let timeout = Duration::from_millis(200);
+
+    // Already online: no notification will come, the subscription knows it
+    let peer = format!("jami:{}", PEER);
+    assert!(Jami::check_peer_reachable("acc1", &peer, timeout).await);
+    assert_eq!(daemon.state().subscriptions["acc1"].get(PEER), Some(&true));
+
+    // Subscribed but offline: still subscribed after the probe
+    daemon
+        .state()
+        .subscriptions
+        .get_mut("acc1")
+        .unwrap()
+        .insert(PEER.to_string(), false);
+    assert!(!Jami::check_peer_reachable("acc1", PEER, timeout).await);
+    assert_eq!(daemon.state().subscriptions["acc1"].get(PEER), Some(&false));
+
+    // Not subscribed: the subscription of the probe is removed
+    daemon.state().subscriptions.clear();
+    assert!(!Jami::check_peer_reachable("acc1", PEER, timeout).await);
+    assert!(daemon.state().subscriptions["acc1"].is_empty());
+}
+
+#[cfg(feature = "call-log")]
+#[test]
+fn call_log_redacts_bodies() {
+    let daemon = FakeDaemon::start();
+    daemon
+        .state()
+        .conversations
+        .insert(String::from("acc1"), vec![String::from(CONV1)]);
+    Jami::try_send_message("acc1", CONV1, "secret body", "", 0).unwrap();
+    let record = Jami::recent_calls()
+        .into_iter()
+        .rev()
+        .find(|record| record.member == "sendMessage")
+        .unwrap();
+    assert!(record.args.contains(CONV1));
+    assert!(!record.args.contains("secret body"));
+    assert!(record.duration.is_some());
+    assert!(record.error.is_none());
+}
+
+#[tokio::test]
+async fn custom_bus_address() {
+    let daemon = FakeDaemon::start();
+    daemon.add_account("acc1", map(&[("Account.alias", "Alice")]), HashMap::new());
+    let _reset = ResetBus;
+    assert!(daemon.address().starts_with("unix:path="));
+
+    Jami::set_bus(BusType::Address(daemon.address().to_string()));
+    assert_eq!(
+        Jami::try_get_account("acc1").unwrap().unwrap().alias,
+        "Alice"
+    );
+    let (mut rx, stop, handle) = start_event_loop();
+    daemon.emit("accountsChanged", ());
+    match next_event(&mut rx).await {
+        Event::AccountsChanged() => {}
+        event => panic!("unexpected event {:?}", event),
+    }
+    stop_event_loop(stop, handle);
+
+    let port = forward_tcp(daemon.address());
+    Jami::set_bus(BusType::Address(format!(
+        "tcp:host=127.0.0.1,port={}",
+        port
+    )));
+    assert_eq!(Jami::try_get_account_list().unwrap().len(), 1);
+    let client = JamiClient::new().unwrap();
+    let (accounts,): (Vec<String>,) = client
+        .call(
+            "/cx/ring/Ring/ConfigurationManager",
+            "cx.ring.Ring.ConfigurationManager",
+            "getAccountList",
+            (),
+        )
+        .unwrap();
+    assert_eq!(accounts, vec!["acc1"]);
+
+    // Nothing listens there
+    Jami::set_bus(BusType::Address(String::from("unix:path=/nonexistent/bus")));
+    assert!(JamiClient::new().is_err());
+}
+
+#[tokio::test]
+async fn account_watch_follows_one_account() {
+    let daemon = FakeDaemon::start();
+    daemon.add_account("acc1", map(&[("Account.alias", "Alice")]), HashMap::new());
+    daemon.add_account("acc2", map(&[("Account.alias", "Bob")]), HashMap::new());
+    let account = Jami::try_get_account("acc1").unwrap().unwrap();
+    let events = EventLoopHandle::new(16);
+    let mut changes = account.watch(&events);
+
+    let registration = |id: &str, state: RegistrationState| Event::RegistrationStateChanged {
+        account_id: id.to_string(),
+        raw_state: state.to_string(),
+        state,
+        code: 0,
+        detail: String::new(),
+    };
+    events.send(registration("acc2", RegistrationState::Registered));
+    events.send(registration("acc1", RegistrationState::Trying));
+    events.send(Event::VolatileDetailsChanged(
+        String::from("acc2"),
+        map(&[("Account.deviceAnnounced", "true")]),
+    ));
+    events.send(Event::VolatileDetailsChanged(
+        String::from("acc1"),
+        map(&[("Account.deviceAnnounced", "true")]),
+    ));
+    events.send(registration("acc1", RegistrationState::Registered));
+    events.send(Event::AccountDetailsChanged(
+        String::from("acc1"),
+        map(&[("Account.alias", "Alicia")]),
+    ));
+    // acc1 is still there, nothing to report
+    events.send(Event::AccountsChanged());
+    events.send(Event::ConversationReady(
+        String::from("acc1"),
+        String::from(CONV1),
+    ));
+
+    let expected = vec![
+        AccountChange::RegistrationState(RegistrationState::Trying),
+        AccountChange::VolatileDetails(map(&[("Account.deviceAnnounced", "true")])),
+        AccountChange::RegistrationState(RegistrationState::Registered),
+        AccountChange::DetailsChanged(map(&[("Account.alias", "Alicia")])),
+    ];
+    for change in expected {
+        assert_eq!(next_change(&mut changes).await, Some(change));
+    }
+
+    // acc2 goes away: not our account
+    {
+        let mut state = daemon.state();
+        state.accounts.retain(|id| id != "acc2");
+        state.details.remove("acc2");
+    }
+    events.send(Event::AccountsChanged());
+    events.send(registration("acc1", RegistrationState::Unregistered));
+    assert_eq!(
+        next_change(&mut changes).await,
+        Some(AccountChange::RegistrationState(
+            RegistrationState::Unregistered
+        ))
+    );
+
+    // acc1 goes away: Removed ends the stream, later events are not reported
+    {
+        let mut state = daemon.state();
+        state.accounts.retain(|id| id != "acc1");
+        state.details.remove("acc1");
+    }
+    events.send(Event::AccountsChanged());
+    events.send(registration("acc1", RegistrationState::Registered));
+    assert_eq!(
+        next_change(&mut changes).await,
+        Some(AccountChange::Removed)
+    );
+    assert_eq!(next_change(&mut changes).await, None);
+}
+
+#[tokio::test]
+async fn account_watch_ends_with_the_handle() {
+    let account = Account::new("acc1");
+    let events = EventLoopHandle::new(16);
+    let mut changes = account.watch(&events);
+    drop(events);
+    assert_eq!(next_change(&mut changes).await, None);
+}
+
+async fn next_change<S: Stream<Item = AccountChange> + Unpin>(
+    changes: &mut S,
+) -> Option<AccountChange> {
+    tokio::time::timeout(Duration::from_secs(5), changes.next())
+        .await
+        .expect("no change after 5s")
+}
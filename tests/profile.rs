@@ -0,0 +1,163 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use jami_rs::{Jami, Profile, ProfileManager};
+
+const HASH: &str = "0123456789abcdef0123456789abcdef01234567";
+
+#[test]
+fn vcard_fields() {
+    let vcard = format!(
+        "BEGIN:VCARD\nVERSION:2.1\nFN:Alice\nTEL;other:ring:{}\nEND:VCARD\n",
+        HASH
+    );
+    let profile = Profile::from_vcard(&vcard);
+    assert_eq!(profile.display_name, "Alice");
+    assert_eq!(profile.uri, HASH);
+}
+
+#[test]
+fn malformed_tel_lines_are_ignored() {
+    let lines = vec![
+        String::from("TEL"),
+        String::from("TEL:"),
+        String::from("TEL;other:ring:1234"),
+        // Multibyte chars where the hash would be cut
+        format!("TEL;other:ring:{}é", &HASH[1..]),
+        format!("TEL;other:ring:{}", "é".repeat(40)),
+        format!("TEL;other:ring:{}00", HASH),
+    ];
+    for line in lines {
+        let vcard = format!("BEGIN:VCARD\nFN:Mallory\n{}\nEND:VCARD\n", line);
+        let profile = Profile::from_vcard(&vcard);
+        assert_eq!(profile.uri, "", "{}", line);
+        assert_eq!(profile.display_name, "Mallory");
+    }
+}
+
+#[test]
+fn contact_request_with_garbage_vcard() {
+    let payload = "BEGIN:VCARD\nTEL;x\nTEL:\u{1F600}\nEND:VCARD\n";
+    let (conversation_id, profile) = Jami::parse_contact_request_payload(payload.as_bytes());
+    assert!(conversation_id.is_none());
+    assert_eq!(profile.unwrap().uri, "");
+}
+
+fn jpeg() -> Vec<u8> {
+    let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+    jpeg.extend_from_slice(b"JFIF\0");
+    jpeg.extend_from_slice(&[1; 32]);
+    jpeg
+}
+
+#[test]
+fn jpeg_labelled_as_png() {
+    // Seen from a real contact
+    let vcard = format!(
+        "BEGIN:VCARD\nFN:Bob\nPHOTO;ENCODING=BASE64;TYPE=PNG:{}\nEND:VCARD\n",
+        base64::encode(jpeg())
+    );
+    let profile = Profile::from_vcard(&vcard);
+    assert_eq!(profile.avatar_type, "PNG");
+    assert_eq!(profile.avatar_bytes().unwrap(), jpeg());
+    assert_eq!(profile.avatar_mimetype(), Some("image/jpeg"));
+}
+
+#[test]
+fn avatar_type_is_a_fallback() {
+    let mut profile = Profile::new();
+    assert!(profile.avatar_bytes().is_none());
+    assert!(profile.avatar_mimetype().is_none());
+    profile.avatar = base64::encode(b"not an image");
+    profile.avatar_type = String::from("GIF");
+    assert_eq!(profile.avatar_mimetype(), Some("image/gif"));
+    profile.avatar = String::from("!!! not base64");
+    assert!(profile.avatar_bytes().is_none());
+}
+
+#[test]
+fn photo_data_uri() {
+    let vcard = format!(
+        "BEGIN:VCARD\nVERSION:4.0\nPHOTO:data:image/jpeg;base64,{}\nEND:VCARD\n",
+        base64::encode(jpeg())
+    );
+    let profile = Profile::from_vcard(&vcard);
+    assert_eq!(profile.avatar_type, "image/jpeg");
+    assert_eq!(profile.avatar_bytes().unwrap(), jpeg());
+}
+
+#[test]
+fn folded_photo_lines() {
+    let data = base64::encode(jpeg());
+    let (first, rest) = data.split_at(10);
+    let (second, third) = rest.split_at(10);
+    // RFC 6350 3.2: continuation lines start with a space or a tab
+    let vcard = format!(
+        "BEGIN:VCARD\r\nPHOTO;ENCODING=b;TYPE=JPEG:{}\r\n {}\r\n\t{}\r\nFN:Carol\r\nEND:VCARD\r\n",
+        first, second, third
+    );
+    let profile = Profile::from_vcard(&vcard);
+    assert_eq!(profile.avatar, data);
+    assert_eq!(profile.avatar_bytes().unwrap(), jpeg());
+    assert_eq!(profile.display_name, "Carol");
+}
+
+fn add_profile(profiles: &mut ProfileManager, uri: &str, display_name: &str, username: &str) {
+    let mut profile = Profile::new();
+    profile.uri = uri.to_string();
+    profile.display_name = display_name.to_string();
+    profile.username = username.to_string();
+    profiles.profiles.insert(uri.to_string(), profile);
+}
+
+#[test]
+fn search_ranks_hits() {
+    let mut profiles = ProfileManager::new();
+    // As many as in the cache of a quick-switcher
+    for i in 0..1000 {
+        let (uri, name) = (format!("{:040x}", i), format!("Contact {}", i));
+        add_profile(&mut profiles, &uri, &name, "");
+    }
+    add_profile(&mut profiles, &"b".repeat(40), "Bob Alice", "");
+    add_profile(&mut profiles, &"c".repeat(40), "Alice Smith", "rabbit");
+    add_profile(&mut profiles, &"d".repeat(40), "", "alice_b");
+    add_profile(&mut profiles, &"e".repeat(40), "Someone", "alice");
+    add_profile(&mut profiles, &"f".repeat(40), "Éloïse", "");
+
+    let hits = profiles.search("", " ALICE", 0);
+    let uris: Vec<String> = hits.iter().map(|hit| hit.uri[..1].to_string()).collect();
+    assert_eq!(uris, vec!["e", "d", "c", "b"]);
+    assert_eq!(hits[0].score, 0);
+    assert_eq!(hits[0].bestname, "Someone");
+
+    assert_eq!(profiles.search("", "eloi", 0)[0].uri, "f".repeat(40));
+    assert_eq!(profiles.search("", "contact", 10).len(), 10);
+    // "Contact 99" and "Contact 990" to 999
+    assert_eq!(profiles.search("", "contact 99", 0).len(), 11);
+    assert!(profiles.search("", "  ", 0).is_empty());
+    // Profiles of another account only
+    assert!(profiles.search("acc1", "alice", 0).is_empty());
+}
@@ -0,0 +1,69 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use jami_rs::account::RegistrationState;
+
+#[test]
+fn registration_states_of_the_daemon() {
+    let states = vec![
+        ("UNREGISTERED", RegistrationState::Unregistered),
+        ("TRYING", RegistrationState::Trying),
+        ("REGISTERED", RegistrationState::Registered),
+        ("ERROR_GENERIC", RegistrationState::ErrorGeneric),
+        ("ERROR_AUTH", RegistrationState::ErrorAuth),
+        ("ERROR_NETWORK", RegistrationState::ErrorNetwork),
+        ("ERROR_HOST", RegistrationState::ErrorHost),
+        (
+            "ERROR_SERVICE_UNAVAILABLE",
+            RegistrationState::ErrorServiceUnavailable,
+        ),
+        (
+            "ERROR_NEED_MIGRATION",
+            RegistrationState::ErrorNeedMigration,
+        ),
+        ("INITIALIZING", RegistrationState::Initializing),
+    ];
+    for (name, state) in states {
+        assert_eq!(name.parse::<RegistrationState>().unwrap(), state);
+        assert_eq!(state.to_string(), name);
+        assert_eq!(state.is_error(), name.starts_with("ERROR"), "{}", name);
+    }
+    assert!(RegistrationState::Registered.is_registered());
+}
+
+#[test]
+fn unknown_registration_states_are_kept() {
+    let state: RegistrationState = "ERROR_NEW_REASON".parse().unwrap();
+    assert_eq!(
+        state,
+        RegistrationState::Unknown(String::from("ERROR_NEW_REASON"))
+    );
+    assert_eq!(state.to_string(), "ERROR_NEW_REASON");
+    assert!(state.is_error());
+    let state: RegistrationState = "PAUSED".parse().unwrap();
+    assert!(!state.is_error());
+    assert!(!state.is_registered());
+}
@@ -0,0 +1,132 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use jami_rs::{Event, TypingState};
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
+const ACCOUNT: &str = "acc1";
+const CONVERSATION: &str = "conv1";
+
+fn composing(from: &str, status: i32) -> Event<()> {
+    Event::ComposingStatusChanged {
+        account_id: ACCOUNT.to_string(),
+        conversation_id: CONVERSATION.to_string(),
+        from: from.to_string(),
+        status,
+    }
+}
+
+fn message(author: &str) -> Event<()> {
+    let mut payloads = HashMap::new();
+    payloads.insert(String::from("author"), author.to_string());
+    Event::Message {
+        account_id: ACCOUNT.to_string(),
+        conversation_id: CONVERSATION.to_string(),
+        payloads,
+    }
+}
+
+#[test]
+fn members_in_order() {
+    let mut state = TypingState::default();
+    assert_eq!(
+        state.apply(&composing("bob", 1)),
+        Some(CONVERSATION.to_string())
+    );
+    assert_eq!(
+        state.apply(&composing("alice", 1)),
+        Some(CONVERSATION.to_string())
+    );
+    assert_eq!(
+        state.typing_members(ACCOUNT, CONVERSATION),
+        vec!["bob", "alice"]
+    );
+    assert!(state.typing_members(ACCOUNT, "conv2").is_empty());
+    assert!(state.typing_members("acc2", CONVERSATION).is_empty());
+
+    assert_eq!(
+        state.apply(&composing("bob", 0)),
+        Some(CONVERSATION.to_string())
+    );
+    assert_eq!(state.typing_members(ACCOUNT, CONVERSATION), vec!["alice"]);
+}
+
+#[test]
+fn duplicate_events() {
+    let mut state = TypingState::default();
+    state.apply(&composing("bob", 1));
+    // Peers resend their status while typing: nothing changes
+    assert_eq!(state.apply(&composing("bob", 1)), None);
+    assert_eq!(state.typing_members(ACCOUNT, CONVERSATION), vec!["bob"]);
+    state.apply(&composing("bob", 0));
+    assert_eq!(state.apply(&composing("bob", 0)), None);
+    assert!(state.typing_members(ACCOUNT, CONVERSATION).is_empty());
+}
+
+#[test]
+fn message_ends_typing() {
+    let mut state = TypingState::default();
+    state.apply(&composing("bob", 1));
+    state.apply(&composing("alice", 1));
+    assert_eq!(state.apply(&message("bob")), Some(CONVERSATION.to_string()));
+    assert_eq!(state.typing_members(ACCOUNT, CONVERSATION), vec!["alice"]);
+    assert_eq!(state.apply(&message("carol")), None);
+}
+
+#[test]
+fn removed_conversation() {
+    let mut state = TypingState::default();
+    state.apply(&composing("bob", 1));
+    let removed = Event::<()>::ConversationRemoved(ACCOUNT.to_string(), CONVERSATION.to_string());
+    assert_eq!(state.apply(&removed), Some(CONVERSATION.to_string()));
+    assert_eq!(state.apply(&removed), None);
+    assert!(state.typing_members(ACCOUNT, CONVERSATION).is_empty());
+}
+
+#[test]
+fn expiry() {
+    let mut state = TypingState::new(Duration::from_millis(50));
+    state.apply(&composing("bob", 1));
+    assert!(state.expire().is_empty());
+    thread::sleep(Duration::from_millis(30));
+    state.apply(&composing("alice", 1));
+    thread::sleep(Duration::from_millis(30));
+    // Expired members are hidden before expire() is called
+    assert_eq!(state.typing_members(ACCOUNT, CONVERSATION), vec!["alice"]);
+    assert_eq!(state.expire(), vec![CONVERSATION.to_string()]);
+    // A new status restarts the delay
+    state.apply(&composing("alice", 1));
+    thread::sleep(Duration::from_millis(30));
+    assert!(state.expire().is_empty());
+    assert_eq!(state.typing_members(ACCOUNT, CONVERSATION), vec!["alice"]);
+    thread::sleep(Duration::from_millis(60));
+    assert_eq!(state.expire(), vec![CONVERSATION.to_string()]);
+    assert!(state.typing_members(ACCOUNT, CONVERSATION).is_empty());
+    // Once expired, typing again is a change
+    assert!(state.apply(&composing("alice", 1)).is_some());
+}
@@ -0,0 +1,194 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use jami_rs::{Event, UnreadTracker};
+use std::collections::HashMap;
+
+const ACCOUNT: &str = "acc1";
+const OWN: &str = "0123456789abcdef0123456789abcdef01234567";
+
+fn commit(id: &str, kind: &str, author: &str) -> HashMap<String, String> {
+    let mut payloads = HashMap::new();
+    payloads.insert(String::from("id"), id.to_string());
+    payloads.insert(String::from("type"), kind.to_string());
+    payloads.insert(String::from("author"), author.to_string());
+    payloads
+}
+
+fn message(conversation_id: &str, payloads: HashMap<String, String>) -> Event<()> {
+    Event::Message {
+        account_id: ACCOUNT.to_string(),
+        conversation_id: conversation_id.to_string(),
+        payloads,
+    }
+}
+
+fn text(conversation_id: &str, id: &str) -> Event<()> {
+    message(conversation_id, commit(id, "text/plain", "bob"))
+}
+
+fn displayed(conversation_id: &str, message_id: &str) -> Event<()> {
+    Event::MessageStatusChanged {
+        account_id: ACCOUNT.to_string(),
+        conversation_id: conversation_id.to_string(),
+        peer: OWN.to_string(),
+        message_id: message_id.to_string(),
+        status: 3,
+    }
+}
+
+#[test]
+fn counts_messages_per_conversation() {
+    let mut tracker = UnreadTracker::new(ACCOUNT, OWN);
+    assert_eq!(
+        tracker.apply(&text("conv1", "m1")),
+        Some(String::from("conv1"))
+    );
+    tracker.apply(&text("conv1", "m2"));
+    tracker.apply(&text("conv2", "m3"));
+    assert_eq!(tracker.unread_count("conv1"), 2);
+    assert_eq!(tracker.unread_count("conv2"), 1);
+    assert_eq!(tracker.unread_count("conv3"), 0);
+    assert_eq!(tracker.first_unread("conv1"), Some(String::from("m1")));
+    assert_eq!(tracker.first_unread("conv3"), None);
+    assert_eq!(tracker.total(), 3);
+}
+
+#[test]
+fn ignored_messages() {
+    let mut tracker = UnreadTracker::new(ACCOUNT, OWN);
+    // Own messages
+    assert_eq!(
+        tracker.apply(&message("conv1", commit("m1", "text/plain", OWN))),
+        None
+    );
+    // Reactions, edits and other commits
+    let mut reaction = commit("m2", "text/plain", "bob");
+    reaction.insert(String::from("react-to"), String::from("m1"));
+    assert_eq!(tracker.apply(&message("conv1", reaction)), None);
+    let edit = commit("m3", "application/edited-message", "bob");
+    assert_eq!(tracker.apply(&message("conv1", edit)), None);
+    assert_eq!(
+        tracker.apply(&message("conv1", commit("m4", "member", "bob"))),
+        None
+    );
+    // Other accounts
+    let other = Event::<()>::Message {
+        account_id: String::from("acc2"),
+        conversation_id: String::from("conv1"),
+        payloads: commit("m5", "text/plain", "bob"),
+    };
+    assert_eq!(tracker.apply(&other), None);
+    assert_eq!(tracker.total(), 0);
+
+    // Files are counted
+    let file = commit("m6", "application/data-transfer+json", "bob");
+    assert!(tracker.apply(&message("conv1", file)).is_some());
+    assert_eq!(tracker.unread_count("conv1"), 1);
+}
+
+#[test]
+fn duplicates_after_reconnection() {
+    let mut tracker = UnreadTracker::new(ACCOUNT, OWN);
+    tracker.apply(&text("conv1", "m1"));
+    assert_eq!(tracker.apply(&text("conv1", "m1")), None);
+    assert_eq!(tracker.unread_count("conv1"), 1);
+}
+
+#[test]
+fn focused_conversation() {
+    let mut tracker = UnreadTracker::new(ACCOUNT, OWN);
+    tracker.set_focused(Some("conv1"));
+    assert_eq!(tracker.apply(&text("conv1", "m1")), None);
+    assert!(tracker.apply(&text("conv2", "m2")).is_some());
+    tracker.set_focused(None);
+    assert!(tracker.apply(&text("conv1", "m3")).is_some());
+    assert_eq!(tracker.unread_count("conv1"), 1);
+    assert_eq!(tracker.unread_count("conv2"), 1);
+}
+
+#[test]
+fn read_on_another_device() {
+    let mut tracker = UnreadTracker::new(ACCOUNT, OWN);
+    for id in &["m1", "m2", "m3"] {
+        tracker.apply(&text("conv1", id));
+    }
+    // Everything up to the displayed message is read
+    assert_eq!(
+        tracker.apply(&displayed("conv1", "m2")),
+        Some(String::from("conv1"))
+    );
+    assert_eq!(tracker.unread_count("conv1"), 1);
+    assert_eq!(tracker.first_unread("conv1"), Some(String::from("m3")));
+    // Unknown message or displayed by a peer
+    assert_eq!(tracker.apply(&displayed("conv1", "m0")), None);
+    let by_peer = Event::<()>::MessageStatusChanged {
+        account_id: ACCOUNT.to_string(),
+        conversation_id: String::from("conv1"),
+        peer: String::from("bob"),
+        message_id: String::from("m3"),
+        status: 3,
+    };
+    assert_eq!(tracker.apply(&by_peer), None);
+    assert_eq!(tracker.unread_count("conv1"), 1);
+}
+
+#[test]
+fn removed_conversation() {
+    let mut tracker = UnreadTracker::new(ACCOUNT, OWN);
+    tracker.apply(&text("conv1", "m1"));
+    let removed = Event::<()>::ConversationRemoved(ACCOUNT.to_string(), String::from("conv1"));
+    assert_eq!(tracker.apply(&removed), Some(String::from("conv1")));
+    assert_eq!(tracker.apply(&removed), None);
+    assert_eq!(tracker.total(), 0);
+}
+
+#[test]
+fn counts_survive_a_restart() {
+    let mut tracker = UnreadTracker::new(ACCOUNT, OWN);
+    tracker.set_focused(Some("conv2"));
+    tracker.apply(&text("conv1", "m1"));
+    tracker.apply(&text("conv1", "m2"));
+    let json = serde_json::to_string(&tracker).unwrap();
+    let mut restored: UnreadTracker = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.unread_count("conv1"), 2);
+    assert_eq!(restored.first_unread("conv1"), Some(String::from("m1")));
+    // The focus is not kept
+    assert!(restored.apply(&text("conv2", "m3")).is_some());
+    // Still ignores duplicates
+    assert_eq!(restored.apply(&text("conv1", "m2")), None);
+}
+
+#[test]
+fn mark_read_clears_the_conversation() {
+    let mut tracker = UnreadTracker::new(ACCOUNT, OWN);
+    tracker.apply(&text("conv1", "m1"));
+    tracker.apply(&text("conv2", "m2"));
+    // Without daemon, only the displayed status is lost
+    tracker.mark_read("conv1");
+    assert_eq!(tracker.unread_count("conv1"), 0);
+    assert_eq!(tracker.total(), 1);
+}
@@ -0,0 +1,114 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use jami_rs::uri::{normalize_peer, parse_share_uri, strip_scheme, UriError};
+use jami_rs::{InfoHash, JamiUri};
+
+const HASH: &str = "0123456789abcdef0123456789abcdef01234567";
+
+#[test]
+fn parse_hash_with_any_scheme() {
+    let expected = JamiUri::Hash(InfoHash::parse(HASH).unwrap());
+    for uri in &[
+        HASH.to_string(),
+        format!("jami:{}", HASH),
+        format!("ring:{}", HASH),
+        format!("JAMI:{}", HASH),
+        format!("  ring:{}  ", HASH),
+    ] {
+        assert_eq!(JamiUri::parse(uri), Ok(expected.clone()));
+    }
+}
+
+#[test]
+fn uppercase_hash_is_lowercased() {
+    let upper = HASH.to_ascii_uppercase();
+    let uri = JamiUri::parse(&format!("jami:{}", upper)).unwrap();
+    assert_eq!(uri.canonical(), HASH);
+    assert_eq!(InfoHash::parse(&upper).unwrap().as_str(), HASH);
+}
+
+#[test]
+fn invalid_hash() {
+    assert_eq!(
+        InfoHash::parse("0123"),
+        Err(UriError::InvalidHash("0123".to_string()))
+    );
+    let not_hex = "g123456789abcdef0123456789abcdef01234567";
+    assert!(InfoHash::parse(not_hex).is_err());
+    // Not a hash, but still a valid registered name
+    assert_eq!(
+        JamiUri::parse(not_hex),
+        Ok(JamiUri::Username(not_hex.to_string()))
+    );
+}
+
+#[test]
+fn usernames() {
+    let uri = JamiUri::parse("jami:alice").unwrap();
+    assert_eq!(uri, JamiUri::Username("alice".to_string()));
+    assert_eq!(uri.canonical(), "alice");
+    assert_eq!(uri.with_scheme(), "jami:alice");
+    assert_eq!(uri.to_string(), "jami:alice");
+}
+
+#[test]
+fn invalid_uris() {
+    assert_eq!(JamiUri::parse(""), Err(UriError::Empty));
+    assert_eq!(JamiUri::parse("jami:"), Err(UriError::Empty));
+    for uri in &["alice bob", "sip:alice", "alice@example.com", "a/b"] {
+        assert_eq!(JamiUri::parse(uri), Err(UriError::Invalid(uri.to_string())));
+    }
+}
+
+#[test]
+fn normalization() {
+    assert_eq!(strip_scheme("ring:alice"), "alice");
+    assert_eq!(strip_scheme("alice"), "alice");
+    assert_eq!(
+        normalize_peer(&format!("ring:{}", HASH.to_ascii_uppercase())),
+        HASH
+    );
+    // Only hashes are normalized
+    assert_eq!(normalize_peer("jami:alice"), "jami:alice");
+    let hash = JamiUri::parse(HASH).unwrap();
+    assert_eq!(hash.with_scheme(), format!("jami:{}", HASH));
+}
+
+#[test]
+fn share_links() {
+    assert_eq!(
+        parse_share_uri(&format!("https://jami.net/?uri={}", HASH)),
+        Some(HASH.to_string())
+    );
+    assert_eq!(
+        parse_share_uri(&format!("https://jami.net/?lang=en&uri=jami:{}#top", HASH)),
+        Some(HASH.to_string())
+    );
+    assert_eq!(parse_share_uri("ring:alice?foo"), Some("alice".to_string()));
+    assert_eq!(parse_share_uri("https://jami.net/"), None);
+    assert_eq!(parse_share_uri("https://jami.net/?lang=en"), None);
+}
@@ -0,0 +1,314 @@
+/**
+ * Copyright (c) 2018-2021, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+use jami_rs::error::NameRegistrationError;
+use jami_rs::{validate, Jami, JamiError, TransferError};
+use std::collections::HashMap;
+use std::time::Duration;
+
+const ACCOUNT: &str = "a1b2c3d4e5f60718";
+const CONV: &str = "c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0";
+const PEER: &str = "0123456789abcdef0123456789abcdef01234567";
+
+type Validator = fn(&str, &str) -> Result<(), JamiError>;
+type Call<'a> = Box<dyn Fn() -> Result<(), JamiError> + 'a>;
+
+// Fail on any daemon call: invalid arguments must be refused before
+fn without_bus() {
+    std::env::set_var(
+        "DBUS_SESSION_BUS_ADDRESS",
+        "unix:path=/nonexistent/jami-rs-test",
+    );
+}
+
+fn assert_invalid<T: std::fmt::Debug>(param: &str, result: Result<T, JamiError>) {
+    match result {
+        Err(JamiError::InvalidArgument(name, _)) => assert_eq!(name, param),
+        result => panic!("{}: unexpected result {:?}", param, result),
+    }
+}
+
+#[test]
+fn validators() {
+    let cases: Vec<(Validator, &str, bool)> = vec![
+        (validate::account_id, ACCOUNT, true),
+        (validate::account_id, "", false),
+        (validate::account_id, "a b", false),
+        (validate::account_id, "../acc", false),
+        (validate::conversation_id, CONV, true),
+        (validate::conversation_id, "", false),
+        (validate::conversation_id, "conv1", false),
+        (validate::conversation_id, &CONV[1..], false),
+        (
+            validate::conversation_id,
+            "C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0",
+            false,
+        ),
+        (validate::peer, PEER, true),
+        (
+            validate::peer,
+            "jami:0123456789abcdef0123456789abcdef01234567",
+            true,
+        ),
+        (
+            validate::peer,
+            "ring:0123456789abcdef0123456789abcdef01234567",
+            true,
+        ),
+        (validate::peer, "", false),
+        (validate::peer, "alice", false),
+        (validate::path, "/tmp/file", true),
+        (validate::path, "", false),
+    ];
+    for (validator, value, valid) in cases {
+        assert_eq!(validator("param", value).is_ok(), valid, "{:?}", value);
+        if !valid {
+            assert_invalid("param", validator("param", value));
+        }
+    }
+    assert!(validate::offset("offset", 0).is_ok());
+    assert!(validate::offset("offset", 4096).is_ok());
+    assert_invalid("offset", validate::offset("offset", -1));
+}
+
+#[test]
+fn result_wrappers_refuse_invalid_ids() {
+    without_bus();
+    let (account, conv) = (String::from(ACCOUNT), String::from(CONV));
+    let text = String::from("text");
+    let cases: Vec<(&str, Call)> = vec![
+        (
+            "account_id",
+            Box::new(|| Jami::set_account_detail("", "Account.alias", "a")),
+        ),
+        (
+            "account_id",
+            Box::new(|| Jami::update_account_details("a b", HashMap::new())),
+        ),
+        (
+            "account_id",
+            Box::new(|| Jami::set_account_avatar("", Vec::new())),
+        ),
+        (
+            "account_id",
+            Box::new(|| Jami::try_send_message("", &conv, &text, "", 0)),
+        ),
+        (
+            "conversation_id",
+            Box::new(|| Jami::try_send_message(&account, &text, &text, "", 0)),
+        ),
+        (
+            "account_id",
+            Box::new(|| Jami::try_get_conversations(&String::from("a b")).map(|_| ())),
+        ),
+        (
+            "account_id",
+            Box::new(|| Jami::try_start_conversation("").map(|_| ())),
+        ),
+        (
+            "conversation_id",
+            Box::new(|| Jami::try_rm_conversation(&account, &text).map(|_| ())),
+        ),
+        (
+            "uri",
+            Box::new(|| Jami::try_accept_trust_request(&account, &text).map(|_| ())),
+        ),
+        (
+            "account_id",
+            Box::new(|| Jami::try_discard_trust_request(&String::from("a b"), &conv).map(|_| ())),
+        ),
+        (
+            "path",
+            Box::new(|| Jami::try_send_file(&account, &conv, "", &text, "")),
+        ),
+    ];
+    for (param, call) in cases {
+        assert_invalid(param, call());
+    }
+}
+
+#[test]
+#[allow(deprecated)]
+fn account_list_reports_missing_bus() {
+    without_bus();
+    match Jami::try_get_account_list() {
+        Err(JamiError::BusConnection(_)) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert!(Jami::get_account_list().is_empty());
+}
+
+#[tokio::test]
+async fn async_wrappers_refuse_invalid_ids() {
+    without_bus();
+    let (account, conv) = (String::from(ACCOUNT), String::from(CONV));
+    let (empty, bad) = (String::new(), String::from("conv1"));
+    let timeout = Duration::from_millis(100);
+
+    assert_invalid(
+        "account_id",
+        Jami::start_conversation_await(&empty, timeout).await,
+    );
+    assert_invalid(
+        "account_id",
+        Jami::load_conversation_await(&empty, &conv, &empty, 0, timeout).await,
+    );
+    assert_invalid(
+        "conversation_id",
+        Jami::load_conversation_await(&account, &bad, &empty, 0, timeout).await,
+    );
+    assert_invalid(
+        "conversation_id",
+        Jami::get_message(&account, &bad, &empty).await,
+    );
+    assert_invalid("account_id", Jami::reload_conversation(&empty, &conv).await);
+    assert_invalid(
+        "account_id",
+        Jami::export_on_ring_await("", "", timeout).await,
+    );
+    match Jami::register_name_await("", "", "name", timeout).await {
+        Err(NameRegistrationError::Failed(JamiError::InvalidArgument(name, _))) => {
+            assert_eq!(name, "account_id")
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+#[allow(deprecated)]
+fn default_wrappers_skip_the_daemon() {
+    without_bus();
+    let (account, conv, peer) = (ACCOUNT, CONV, PEER);
+    let path = "/tmp/file";
+
+    // The defaulting shims call their try_ variant, which must refuse before the daemon
+    assert_invalid("account_id", Jami::try_get_account_details(""));
+    assert!(Jami::get_account_details("").is_empty());
+    assert_invalid("account_id", Jami::try_get_volatile_account_details("a b"));
+    assert_invalid("path", Jami::try_export_to_file(account, "", ""));
+    assert_invalid("account_id", Jami::try_get_contacts(""));
+    assert_invalid("uri", Jami::try_accept_trust_request(account, "alice"));
+    assert!(!Jami::accept_trust_request(account, "alice"));
+    assert_invalid("account_id", Jami::try_get_conversations(""));
+    assert_invalid("conversation_id", Jami::try_get_members(account, "conv1"));
+    assert_invalid("account_id", Jami::try_get_conversation_infos("", conv));
+    assert_invalid(
+        "conversation_id",
+        Jami::try_rm_conversation(account, "conv1"),
+    );
+    assert_invalid(
+        "conversation_id",
+        Jami::try_load_conversation(account, "conv1", "", 10),
+    );
+    assert_eq!(Jami::load_conversation(account, "conv1", "", 10), 0);
+    assert_invalid("path", Jami::try_accept_file_transfer(account, conv, 1, ""));
+    assert_invalid(
+        "offset",
+        Jami::try_accept_file_transfer_from(account, conv, 1, path, -1),
+    );
+    match Jami::try_data_transfer_info(account.to_string(), String::from("conv1"), 1) {
+        Err(TransferError::Failed(JamiError::InvalidArgument(name, _))) => {
+            assert_eq!(name, "conversation_id")
+        }
+        Err(e) => panic!("unexpected error {:?}", e),
+        Ok(_) => panic!("unexpected transfer info"),
+    }
+    assert_invalid("uri", Jami::try_add_contact(account, ""));
+    assert_invalid(
+        "uri",
+        Jami::try_add_conversation_member(account, conv, "alice"),
+    );
+    assert_invalid("account_id", Jami::try_send_message("", conv, peer, "", 0));
+    assert_invalid("path", Jami::try_send_file(account, conv, "", "", ""));
+}
+
+#[test]
+fn download_dir_must_be_a_writable_directory() {
+    let missing = std::env::temp_dir().join("jami-rs-test/missing/dir");
+    assert_invalid(
+        "path",
+        Jami::set_download_dir(std::path::Path::new(""), false),
+    );
+    assert_invalid("path", Jami::set_download_dir(&missing, false));
+    let file = tempfile::NamedTempFile::new().unwrap();
+    assert_invalid("path", Jami::set_download_dir(file.path(), true));
+}
+
+// Set in the child process of no_session_bus_does_not_panic
+const CHILD_VAR: &str = "JAMI_RS_TEST_CHILD";
+
+#[test]
+#[allow(deprecated)]
+fn no_session_bus_does_not_panic() {
+    if std::env::var(CHILD_VAR).is_ok() {
+        match Jami::try_get_account_list() {
+            Err(JamiError::BusConnection(_)) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert!(Jami::get_account_list().is_empty());
+        assert!(Jami::get_account(ACCOUNT).is_none());
+        assert!(Jami::try_version().is_err());
+        assert!(Jami::get_conversations(ACCOUNT).is_empty());
+        assert_eq!(Jami::load_conversation(ACCOUNT, CONV, "", 10), 0);
+        Jami::send_message(ACCOUNT, CONV, "text", "", &0);
+        return;
+    }
+    // libdbus keeps the address of the session bus, so a new process is needed
+    let status = std::process::Command::new(std::env::current_exe().unwrap())
+        .args([
+            "--exact",
+            "no_session_bus_does_not_panic",
+            "--test-threads=1",
+        ])
+        .env(CHILD_VAR, "1")
+        .env_remove("DBUS_SESSION_BUS_ADDRESS")
+        // No fallback to $XDG_RUNTIME_DIR/bus or to an autolaunched bus
+        .env_remove("XDG_RUNTIME_DIR")
+        .env_remove("DISPLAY")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn call_timeout_can_be_scoped() {
+    let default = Jami::call_timeout();
+    let inner = Jami::with_timeout(Duration::from_secs(20), || {
+        // Nested calls see the innermost timeout
+        assert_eq!(
+            Jami::with_timeout(Duration::from_secs(1), Jami::call_timeout),
+            Duration::from_secs(1)
+        );
+        Jami::call_timeout()
+    });
+    assert_eq!(inner, Duration::from_secs(20));
+    assert_eq!(Jami::call_timeout(), default);
+    // Only for the thread calling with_timeout
+    Jami::with_timeout(Duration::from_secs(20), || {
+        let other = std::thread::spawn(Jami::call_timeout).join().unwrap();
+        assert_eq!(other, default);
+    });
+}